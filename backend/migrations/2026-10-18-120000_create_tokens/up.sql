@@ -0,0 +1,15 @@
+-- Your SQL goes here
+CREATE TABLE refresh_tokens (
+    token_hash VARCHAR PRIMARY KEY,
+    family_id VARCHAR NOT NULL,
+    username VARCHAR NOT NULL REFERENCES users (username) ON DELETE CASCADE,
+    expires_at BIGINT NOT NULL,
+    used BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE INDEX refresh_tokens_family_id ON refresh_tokens (family_id);
+
+CREATE TABLE revoked_tokens (
+    jti VARCHAR PRIMARY KEY,
+    expires_at BIGINT NOT NULL
+)
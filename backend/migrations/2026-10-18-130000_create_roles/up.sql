@@ -0,0 +1,53 @@
+-- Your SQL goes here
+CREATE TABLE roles (
+    name VARCHAR PRIMARY KEY
+);
+
+-- GET_LEDGER_INFO and SET_LEDGER_INFO cover the journals, accounts and music,
+-- the time and finance data have their own permissions.
+CREATE TABLE role_permissions (
+    role VARCHAR NOT NULL REFERENCES roles (name) ON DELETE CASCADE,
+    permission VARCHAR NOT NULL,
+    PRIMARY KEY (role, permission)
+);
+
+CREATE TABLE user_roles (
+    username VARCHAR NOT NULL REFERENCES users (username) ON DELETE CASCADE,
+    role VARCHAR NOT NULL REFERENCES roles (name) ON DELETE CASCADE,
+    PRIMARY KEY (username, role)
+);
+
+INSERT INTO roles (name) VALUES
+    ('viewer'),
+    ('time_editor'),
+    ('finance_editor'),
+    ('editor'),
+    ('admin');
+
+INSERT INTO role_permissions (role, permission) VALUES
+    ('viewer', 'GET_LEDGER_INFO'),
+    ('viewer', 'GET_TIME_INFO'),
+    ('viewer', 'GET_FINANCE_INFO'),
+    ('time_editor', 'GET_LEDGER_INFO'),
+    ('time_editor', 'GET_TIME_INFO'),
+    ('time_editor', 'SET_TIME_INFO'),
+    ('finance_editor', 'GET_LEDGER_INFO'),
+    ('finance_editor', 'GET_FINANCE_INFO'),
+    ('finance_editor', 'SET_FINANCE_INFO'),
+    ('editor', 'GET_LEDGER_INFO'),
+    ('editor', 'SET_LEDGER_INFO'),
+    ('editor', 'GET_TIME_INFO'),
+    ('editor', 'SET_TIME_INFO'),
+    ('editor', 'GET_FINANCE_INFO'),
+    ('editor', 'SET_FINANCE_INFO'),
+    ('admin', 'ADMIN_ROLE'),
+    ('admin', 'GET_LEDGER_INFO'),
+    ('admin', 'SET_LEDGER_INFO'),
+    ('admin', 'GET_TIME_INFO'),
+    ('admin', 'SET_TIME_INFO'),
+    ('admin', 'GET_FINANCE_INFO'),
+    ('admin', 'SET_FINANCE_INFO');
+
+-- the existing users could read and write all journals before, the admin is granted
+-- explicitly with ADMIN_USER
+INSERT INTO user_roles (username, role) SELECT username, 'editor' FROM users
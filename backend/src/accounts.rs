@@ -0,0 +1,155 @@
+//! Tree of all accounts, that are used in the time and finance journals of a user.
+use chrono::NaiveDate;
+use log::debug;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::errors::ServiceError;
+use crate::journal::{self, Journal};
+use crate::journals;
+use crate::utils::user_file;
+use shared::models::{AccountNode, JournalDefinition, JournalKind};
+
+/// the account is the parent account or one of its sub accounts
+pub fn is_below(account: &str, parent: &str) -> bool {
+    account
+        .strip_prefix(parent)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(':'))
+}
+
+/// usage of one account and its sub accounts
+#[derive(Default)]
+struct Usage {
+    count: u32,
+    last_used: Option<NaiveDate>,
+    journals: Vec<String>,
+    kinds: Vec<JournalKind>,
+}
+
+/// Builds the account tree. Every posting counts for its account and all parent accounts.
+/// Accounts are sorted by name.
+pub fn account_tree(journals: &[(JournalDefinition, Journal)]) -> Vec<AccountNode> {
+    let mut usages: BTreeMap<String, Usage> = BTreeMap::new();
+    for (definition, journal) in journals {
+        for transaction in journal.transactions() {
+            for posting in &transaction.postings {
+                let segments = posting.account.split(':').collect::<Vec<_>>();
+                for depth in 1..=segments.len() {
+                    let usage = usages.entry(segments[..depth].join(":")).or_default();
+                    usage.count += 1;
+                    usage.last_used = usage.last_used.max(Some(transaction.date));
+                    if !usage.journals.contains(&definition.name) {
+                        usage.journals.push(definition.name.clone());
+                    }
+                    if !usage.kinds.contains(&definition.kind) {
+                        usage.kinds.push(definition.kind);
+                    }
+                }
+            }
+        }
+    }
+    children(&usages, None)
+}
+
+/// nodes directly below the parent account, all top level accounts without parent
+fn children(usages: &BTreeMap<String, Usage>, parent: Option<&str>) -> Vec<AccountNode> {
+    let prefix = parent.map(|p| format!("{}:", p)).unwrap_or_default();
+    usages
+        .range(prefix.clone()..)
+        .take_while(|(account, _)| account.starts_with(&prefix))
+        .filter(|(account, _)| !account[prefix.len()..].contains(':'))
+        .map(|(account, usage)| AccountNode {
+            name: account[prefix.len()..].to_string(),
+            account: account.clone(),
+            usage: usage.count,
+            last_used: usage
+                .last_used
+                .map(|d| d.format("%Y/%m/%d").to_string())
+                .unwrap_or_default(),
+            journals: usage.journals.clone(),
+            kinds: usage.kinds.clone(),
+            children: children(usages, Some(account)),
+        })
+        .collect()
+}
+
+/// account tree of the time and finance journals of the given kinds, archived ones included
+pub fn ledger_account_tree(
+    user: &str,
+    kinds: &[JournalKind],
+) -> Result<Vec<AccountNode>, ServiceError> {
+    let journals = journals::user_journals(user)?
+        .into_iter()
+        .filter(|j| j.kind != JournalKind::Music && kinds.contains(&j.kind))
+        .filter(|j| Path::new(&user_file(user, &j.path)).exists())
+        .map(|j| {
+            let journal = journal::read_file(&user_file(user, &j.path))?;
+            Ok((j, journal))
+        })
+        .collect::<Result<Vec<_>, ServiceError>>()?;
+    debug!("Build account tree from {} journals", journals.len());
+    Ok(account_tree(&journals))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn definition(name: &str, kind: JournalKind) -> JournalDefinition {
+        JournalDefinition {
+            name: name.to_string(),
+            kind,
+            ..JournalDefinition::default()
+        }
+    }
+
+    #[test]
+    fn test_account_tree() {
+        let time = journal::parse(
+            "2022/10/10 Programming
+ \tFreeTime
+ \tEducation:Rust\t\t90m
+
+2022/10/12 Reading
+ \tFreeTime
+ \tEducation:Books\t\t30m
+",
+        );
+        let finance = journal::parse(
+            "2022/10/11 Books
+ \tGirokonto:N2
+ \tEducation:Books\t\t12.50€
+",
+        );
+        let tree = account_tree(&[
+            (definition("Time", JournalKind::Time), time),
+            (definition("Gesamt", JournalKind::Finance), finance),
+        ]);
+        assert_eq!(
+            tree.iter().map(|n| n.name.as_str()).collect::<Vec<_>>(),
+            vec!["Education", "FreeTime", "Girokonto"]
+        );
+        let education = &tree[0];
+        assert_eq!(education.usage, 3);
+        assert_eq!(education.last_used, "2022/10/12");
+        assert_eq!(education.journals, vec!["Time", "Gesamt"]);
+        assert_eq!(
+            education.kinds,
+            vec![JournalKind::Time, JournalKind::Finance]
+        );
+        let books = &education.children[0];
+        assert_eq!(books.account, "Education:Books");
+        assert_eq!(books.usage, 2);
+        assert_eq!(education.children[1].name, "Rust");
+        assert_eq!(education.children[1].journals, vec!["Time"]);
+        assert_eq!(tree[2].children[0].account, "Girokonto:N2");
+    }
+
+    #[test]
+    fn test_is_below() {
+        assert!(is_below("Education:Books", "Education"));
+        assert!(is_below("Education", "Education"));
+        assert!(!is_below("EducationPlan", "Education"));
+        assert!(!is_below("Education", "Education:Books"));
+    }
+}
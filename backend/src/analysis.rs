@@ -0,0 +1,289 @@
+//! Checks the time journal for overlapping enteries and untracked time.
+use chrono::{Datelike, Duration, NaiveDate};
+use log::debug;
+
+use crate::errors::ServiceError;
+use crate::utils;
+use shared::models::{
+    RequestTimeAnalysis, ResponseTimeAnalysis, StartTimeEntery, TimeDayAnalysis, TimeEnteryHistory,
+    TimeGap, TimeOverlap,
+};
+
+const MINUTES_PER_DAY: i64 = 24 * 60;
+// longest selection, that is analysed at once
+const MAX_DAYS: i64 = 366;
+
+/// entery with start and end in minutes since 0001/01/01
+struct Tracked<'a> {
+    entery: &'a TimeEnteryHistory,
+    start: i64,
+    end: i64,
+}
+
+/// parses `HH:MM`. `24:00` is the end of the day.
+fn parse_time(time: &str) -> Option<i64> {
+    let (hour, minute) = time.trim().split_once(':')?;
+    let minute = hour.parse::<i64>().ok()? * 60 + minute.parse::<i64>().ok()?;
+    if (0..=MINUTES_PER_DAY).contains(&minute) {
+        Some(minute)
+    } else {
+        None
+    }
+}
+
+fn format_time(minute: i64) -> String {
+    format!("{:02}:{:02}", minute / 60, minute % 60)
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, ServiceError> {
+    NaiveDate::parse_from_str(&date.replace('-', "/"), "%Y/%m/%d")
+        .map_err(|_| ServiceError::BadRequest(format!("Invalid date '{}'.", date)))
+}
+
+fn day_minute(date: NaiveDate) -> i64 {
+    date.num_days_from_ce() as i64 * MINUTES_PER_DAY
+}
+
+/// Start and end of the entery. A timespan ending before it started ends on the next day,
+/// enteries over several days end after their duration.
+fn tracked(entery: &TimeEnteryHistory) -> Option<Tracked<'_>> {
+    let day = day_minute(parse_date(&entery.date).ok()?);
+    let (start, end) = entery.timespan.split_once(" - ")?;
+    let start = day + parse_time(start)?;
+    let mut end = day + parse_time(end)?;
+    if entery.duration as i64 >= MINUTES_PER_DAY {
+        end = start + entery.duration as i64;
+    } else if end <= start {
+        end += MINUTES_PER_DAY;
+    }
+    Some(Tracked { entery, start, end })
+}
+
+/// overlaps and gaps between `from` and `to` (minutes of the day) of the date
+fn analyse_day(
+    date: NaiveDate,
+    from: i64,
+    to: i64,
+    tracked: &[Tracked],
+    min_gap: u32,
+) -> TimeDayAnalysis {
+    let day = day_minute(date);
+    let (from, to) = (day + from, day + to);
+    let date = date.format("%Y/%m/%d").to_string();
+    let timespan =
+        |start: i64, end: i64| format!("{} - {}", format_time(start - day), format_time(end - day));
+    let enteries = tracked
+        .iter()
+        .filter(|t| t.start < to && t.end > from)
+        .collect::<Vec<_>>();
+
+    let mut overlaps = Vec::new();
+    for (i, first) in enteries.iter().enumerate() {
+        for second in &enteries[i + 1..] {
+            let start = first.start.max(second.start).max(from);
+            let end = first.end.min(second.end).min(to);
+            if start < end {
+                overlaps.push(TimeOverlap {
+                    date: date.clone(),
+                    first_id: first.entery.id.clone(),
+                    second_id: second.entery.id.clone(),
+                    timespan: timespan(start, end),
+                    duration: (end - start) as u32,
+                });
+            }
+        }
+    }
+
+    // the enteries are sorted by start, so everything before `covered` is tracked
+    let mut gaps = Vec::new();
+    let mut covered = from;
+    let mut tracked_minutes = 0;
+    for entery in enteries
+        .iter()
+        .map(|t| (t.start.max(from), t.end.min(to)))
+        .chain([(to, to)])
+    {
+        let (start, end) = entery;
+        if start - covered >= min_gap.max(1) as i64 {
+            gaps.push(TimeGap {
+                date: date.clone(),
+                timespan: timespan(covered, start),
+                duration: (start - covered) as u32,
+            });
+        }
+        if end > covered {
+            tracked_minutes += end - covered.max(start);
+            covered = end;
+        }
+    }
+    TimeDayAnalysis {
+        date,
+        tracked: tracked_minutes as u32,
+        untracked: (to - from - tracked_minutes) as u32,
+        overlaps,
+        gaps,
+    }
+}
+
+/// Analyses every day of the selection. Enteries without timespan are ignored.
+pub fn analyse(
+    history: &[TimeEnteryHistory],
+    request: &RequestTimeAnalysis,
+) -> Result<ResponseTimeAnalysis, ServiceError> {
+    let date_from = parse_date(&request.date_from)?;
+    let date_to = parse_date(&request.date_to)?;
+    if date_to < date_from || (date_to - date_from).num_days() >= MAX_DAYS {
+        return Err(ServiceError::BadRequest(format!(
+            "The selection has to contain 1 to {} days.",
+            MAX_DAYS
+        )));
+    }
+    let time = |time: &Option<String>, default: i64| match time {
+        Some(t) => {
+            parse_time(t).ok_or_else(|| ServiceError::BadRequest(format!("Invalid time '{}'.", t)))
+        }
+        None => Ok(default),
+    };
+    let day_start = time(&request.day_start, 0)?;
+    let day_end = time(&request.day_end, MINUTES_PER_DAY)?;
+    if day_end <= day_start {
+        return Err(ServiceError::BadRequest(
+            "The day has to end after it starts.".to_string(),
+        ));
+    }
+
+    let mut tracked = history.iter().filter_map(tracked).collect::<Vec<_>>();
+    tracked.sort_by_key(|t| t.start);
+    let days = date_from
+        .iter_days()
+        .take_while(|d| d <= &date_to)
+        .map(|d| {
+            analyse_day(
+                d,
+                day_start,
+                day_end,
+                &tracked,
+                request.min_gap.unwrap_or(1),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let fillers = match &request.filler_account {
+        Some(account) => days
+            .iter()
+            .flat_map(|d| d.gaps.iter())
+            .map(|gap| TimeEnteryHistory {
+                id: String::new(),
+                headline: "Untracked time".to_string(),
+                account_origin: StartTimeEntery::default().account_origin,
+                account_target: account.clone(),
+                date: gap.date.clone(),
+                timespan: gap.timespan.clone(),
+                duration: gap.duration,
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+    debug!(
+        "Analysed {} days from {}",
+        (date_to - date_from + Duration::days(1)).num_days(),
+        date_from
+    );
+    Ok(ResponseTimeAnalysis { days, fillers })
+}
+
+/// checks the time journal of the user
+pub fn ledger_time_analysis(
+    user: &str,
+    request: &RequestTimeAnalysis,
+) -> Result<ResponseTimeAnalysis, ServiceError> {
+    analyse(&utils::ledger_time_history(user)?, request)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entery(id: &str, date: &str, timespan: &str, duration: u32) -> TimeEnteryHistory {
+        TimeEnteryHistory {
+            id: id.to_string(),
+            date: date.to_string(),
+            timespan: timespan.to_string(),
+            duration,
+            ..TimeEnteryHistory::default()
+        }
+    }
+
+    fn request(date_from: &str, date_to: &str) -> RequestTimeAnalysis {
+        RequestTimeAnalysis {
+            date_from: date_from.to_string(),
+            date_to: date_to.to_string(),
+            day_start: Some("08:00".to_string()),
+            day_end: Some("18:00".to_string()),
+            ..RequestTimeAnalysis::default()
+        }
+    }
+
+    #[test]
+    fn test_overlaps_and_gaps() {
+        let history = vec![
+            entery("a", "2022/10/10", "08:00 - 10:00", 120),
+            entery("b", "2022/10/10", "09:30 - 11:00", 90),
+            entery("c", "2022/10/10", "12:00 - 18:30", 390),
+        ];
+        let analysis = analyse(&history, &request("2022/10/10", "2022/10/10")).unwrap();
+        let day = &analysis.days[0];
+        assert_eq!(
+            day.overlaps,
+            vec![TimeOverlap {
+                date: "2022/10/10".to_string(),
+                first_id: "a".to_string(),
+                second_id: "b".to_string(),
+                timespan: "09:30 - 10:00".to_string(),
+                duration: 30,
+            }]
+        );
+        assert_eq!(
+            day.gaps,
+            vec![TimeGap {
+                date: "2022/10/10".to_string(),
+                timespan: "11:00 - 12:00".to_string(),
+                duration: 60,
+            }]
+        );
+        assert_eq!(day.tracked, 540);
+        assert_eq!(day.untracked, 60);
+        assert!(analysis.fillers.is_empty());
+    }
+
+    #[test]
+    fn test_midnight_and_fillers() {
+        let history = vec![entery("night", "2022/10/10", "23:00 - 07:00", 480)];
+        let analysis = analyse(
+            &history,
+            &RequestTimeAnalysis {
+                date_from: "2022-10-11".to_string(),
+                date_to: "2022-10-11".to_string(),
+                min_gap: Some(30),
+                filler_account: Some("FreeTime".to_string()),
+                ..RequestTimeAnalysis::default()
+            },
+        )
+        .unwrap();
+        let day = &analysis.days[0];
+        assert_eq!(day.tracked, 420);
+        assert_eq!(day.gaps[0].timespan, "07:00 - 24:00");
+        assert_eq!(analysis.fillers.len(), 1);
+        assert_eq!(analysis.fillers[0].account_target, "FreeTime");
+        assert_eq!(analysis.fillers[0].duration, 1020);
+    }
+
+    #[test]
+    fn test_invalid_selection() {
+        assert!(analyse(&[], &request("2022/10/10", "2022/10/09")).is_err());
+        assert!(analyse(&[], &request("2022/10/10", "2024/10/10")).is_err());
+        let mut invalid = request("2022/10/10", "2022/10/10");
+        invalid.day_end = Some("25:00".to_string());
+        assert!(analyse(&[], &invalid).is_err());
+    }
+}
@@ -0,0 +1,360 @@
+//! Budgets of the users, stored in `./files/<user>/budgets.json`, and their comparison
+//! with the postings of the finance journals.
+use chrono::{Datelike, Duration, NaiveDate};
+use log::debug;
+use std::fs;
+use std::path::Path;
+
+use crate::errors::ServiceError;
+use crate::journal::{self, Journal};
+use crate::report;
+use crate::utils::{user_dir, user_file};
+use shared::amount::{Amount, Decimal};
+use shared::models::{Budget, BudgetPeriodReport, BudgetReport, RequestBudgetReport};
+
+pub const BUDGETS_FILE: &str = "budgets.json";
+pub const PERIODS: [&str; 3] = ["week", "month", "year"];
+// reported periods, if the request does not say otherwise
+const DEFAULT_PERIODS: u32 = 6;
+// most periods, that are reported at once
+const MAX_PERIODS: usize = 1000;
+
+fn parse_budgets(content: &str) -> Result<Vec<Budget>, ServiceError> {
+    if content.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    Ok(serde_json::from_str(content)?)
+}
+
+fn format_date(date: NaiveDate) -> String {
+    date.format("%Y/%m/%d").to_string()
+}
+
+fn parse_start(budget: &Budget) -> Result<Option<NaiveDate>, ServiceError> {
+    match &budget.start {
+        Some(start) => NaiveDate::parse_from_str(&start.replace('-', "/"), "%Y/%m/%d")
+            .map(Some)
+            .map_err(|_| ServiceError::BadRequest(format!("Invalid start '{}'.", start))),
+        None => Ok(None),
+    }
+}
+
+/// all budgets of the user
+pub fn user_budgets(user: &str) -> Result<Vec<Budget>, ServiceError> {
+    let path = user_file(user, BUDGETS_FILE);
+    if !Path::new(&path).exists() {
+        return Ok(Vec::new());
+    }
+    parse_budgets(&fs::read_to_string(path)?)
+}
+
+/// Saves the budget. An existing budget of the account is replaced.
+pub fn set_budget(user: &str, budget: Budget) -> Result<Vec<Budget>, ServiceError> {
+    if budget.account.trim().is_empty() || budget.account.trim() != budget.account {
+        return Err(ServiceError::BadRequest(format!(
+            "Invalid account '{}'.",
+            budget.account
+        )));
+    }
+    if !PERIODS.contains(&budget.period.as_str()) {
+        return Err(ServiceError::BadRequest(format!(
+            "Unknown period '{}'.",
+            budget.period
+        )));
+    }
+    if budget.ammount.commodity.is_empty() || budget.ammount.quantity.is_sign_negative() {
+        return Err(ServiceError::BadRequest(format!(
+            "Invalid ammount '{}'.",
+            budget.ammount
+        )));
+    }
+    parse_start(&budget)?;
+    fs::create_dir_all(user_dir(user))?;
+    let path = user_file(user, BUDGETS_FILE);
+    journal::create_file(&path)?;
+    journal::modify_file(&path, |content| {
+        let mut budgets = parse_budgets(content)?;
+        match budgets.iter_mut().find(|b| b.account == budget.account) {
+            Some(existing) => *existing = budget.clone(),
+            None => budgets.push(budget.clone()),
+        }
+        Ok((serde_json::to_string_pretty(&budgets)?, budgets))
+    })
+}
+
+/// removes the budget of the account
+pub fn delete_budget(user: &str, account: &str) -> Result<Vec<Budget>, ServiceError> {
+    let path = user_file(user, BUDGETS_FILE);
+    if !Path::new(&path).exists() {
+        return Err(ServiceError::NotFound(format!(
+            "No budget for '{}'.",
+            account
+        )));
+    }
+    journal::modify_file(&path, |content| {
+        let mut budgets = parse_budgets(content)?;
+        let count = budgets.len();
+        budgets.retain(|b| b.account != account);
+        if budgets.len() == count {
+            return Err(ServiceError::NotFound(format!(
+                "No budget for '{}'.",
+                account
+            )));
+        }
+        Ok((serde_json::to_string_pretty(&budgets)?, budgets))
+    })
+}
+
+/// Sum of the postings to the account and its sub accounts between start (inclusive) and
+/// end (exclusive) in the commodity. Postings without amount have the commodity of the
+/// other postings.
+pub fn actual(
+    journals: &[Journal],
+    account: &str,
+    commodity: &str,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Decimal {
+    let sub_accounts = format!("{}:", account);
+    let mut sum = Decimal::ZERO;
+    let transactions = journals
+        .iter()
+        .flat_map(|j| j.transactions())
+        .filter(|t| t.date >= start && t.date < end);
+    for transaction in transactions {
+        let balancing = transaction
+            .postings
+            .iter()
+            .find_map(|p| p.cost.as_ref().or(p.amount.as_ref()))
+            .map(|a| a.commodity.as_str());
+        for (posting, value) in transaction.postings.iter().zip(transaction.values()) {
+            if posting.account != account && !posting.account.starts_with(&sub_accounts) {
+                continue;
+            }
+            let posting_commodity = posting
+                .cost
+                .as_ref()
+                .or(posting.amount.as_ref())
+                .map(|a| a.commodity.as_str())
+                .or(balancing);
+            if posting_commodity == Some(commodity) {
+                sum += value;
+            }
+        }
+    }
+    sum
+}
+
+/// Compares the budget with the journals for the last `periods` periods up to the date.
+pub fn budget_report(
+    journals: &[Journal],
+    budget: &Budget,
+    today: NaiveDate,
+    periods: usize,
+) -> Result<BudgetReport, ServiceError> {
+    let (current, _) = report::period(&format_date(today), &budget.period)?;
+    let budget_start = parse_start(budget)?;
+    // the first period, that is reported
+    let mut first = current;
+    for _ in 1..periods {
+        first = report::period(&format_date(first - Duration::days(1)), &budget.period)?.0;
+    }
+    // the rollover is calculated from the start of the budget
+    let mut start = match budget_start {
+        Some(date) if budget.rollover => report::period(&format_date(date), &budget.period)?.0,
+        Some(date) => first.max(report::period(&format_date(date), &budget.period)?.0),
+        None => first,
+    };
+    let amount = |quantity: Decimal| Amount::new(quantity, &budget.ammount.commodity);
+    let mut reports = Vec::new();
+    let mut rollover = Decimal::ZERO;
+    // the periods before the report add their budget and subtract their spendings
+    if budget.rollover && start < first {
+        let skipped = periods_between(start, first, &budget.period);
+        rollover = budget.ammount.quantity * Decimal::from(skipped)
+            - actual(
+                journals,
+                &budget.account,
+                &budget.ammount.commodity,
+                start,
+                first,
+            );
+        start = first;
+    }
+    while start <= current {
+        let (_, end) = report::period(&format_date(start), &budget.period)?;
+        let available = budget.ammount.quantity + rollover;
+        let actual = actual(
+            journals,
+            &budget.account,
+            &budget.ammount.commodity,
+            start,
+            end,
+        );
+        reports.push(BudgetPeriodReport {
+            start: format_date(start),
+            end: format_date(end - Duration::days(1)),
+            budget: budget.ammount.clone(),
+            rollover: amount(rollover),
+            available: amount(available),
+            actual: amount(actual),
+            remaining: amount(available - actual),
+        });
+        if budget.rollover {
+            rollover = available - actual;
+        }
+        start = end;
+    }
+    let skip = reports.len().saturating_sub(periods);
+    Ok(BudgetReport {
+        budget: budget.clone(),
+        periods: reports.split_off(skip),
+    })
+}
+
+/// number of periods from `start` to `end`, both are the first day of a period
+fn periods_between(start: NaiveDate, end: NaiveDate, period: &str) -> i64 {
+    match period {
+        "week" => (end - start).num_days() / 7,
+        "month" => {
+            (end.year() - start.year()) as i64 * 12 + end.month() as i64 - start.month() as i64
+        }
+        _ => (end.year() - start.year()) as i64,
+    }
+}
+
+/// compares all budgets of the user with the finance journals
+pub fn ledger_budget_report(
+    user: &str,
+    request: &RequestBudgetReport,
+) -> Result<Vec<BudgetReport>, ServiceError> {
+    let (journals, _) = report::target_journals(user, "finance")?;
+    let today = chrono::Local::now().date_naive();
+    let periods = request.periods.unwrap_or(DEFAULT_PERIODS).max(1) as usize;
+    if periods > MAX_PERIODS {
+        return Err(ServiceError::BadRequest(format!(
+            "At most {} periods can be reported.",
+            MAX_PERIODS
+        )));
+    }
+    let reports = user_budgets(user)?
+        .iter()
+        .map(|b| budget_report(&journals, b, today, periods))
+        .collect::<Result<Vec<_>, _>>()?;
+    debug!("Created {} budget reports for '{}'", reports.len(), user);
+    Ok(reports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dirs::UserDir;
+
+    const JOURNAL: &str = "2022/09/10 Supermarkt
+ \tGirokonto:N2
+ \tAusgaben:Essen\t\t250€
+
+2022/10/02 Supermarkt
+ \tAusgaben:Essen:Obst\t\t12.50€
+ \tAusgaben:Haushalt\t\t3€
+ \tGirokonto:N2
+
+2022/10/05 Restaurant
+ \tAusgaben:Essen\t\t$20
+ \tKreditkarte
+
+2022/10/30 Supermarkt
+ \tGirokonto:N2\t\t-80.25€
+ \tAusgaben:Essen
+";
+
+    fn budget(rollover: bool) -> Budget {
+        Budget {
+            account: "Ausgaben:Essen".to_string(),
+            ammount: Amount::parse("100€").unwrap(),
+            period: "month".to_string(),
+            rollover,
+            start: None,
+        }
+    }
+
+    fn date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y/%m/%d").unwrap()
+    }
+
+    #[test]
+    fn test_actual() {
+        let journals = [journal::parse(JOURNAL)];
+        let october = (date("2022/10/01"), date("2022/11/01"));
+        assert_eq!(
+            actual(&journals, "Ausgaben:Essen", "€", october.0, october.1),
+            Decimal::new(9275, 2)
+        );
+        assert_eq!(
+            actual(&journals, "Ausgaben:Essen", "$", october.0, october.1),
+            Decimal::from(20)
+        );
+        // accounts only match by whole segments
+        assert_eq!(
+            actual(&journals, "Ausgaben:Ess", "€", october.0, october.1),
+            Decimal::ZERO
+        );
+    }
+
+    #[test]
+    fn test_budget_report() {
+        let journals = [journal::parse(JOURNAL)];
+        let report = budget_report(&journals, &budget(false), date("2022/10/18"), 3).unwrap();
+        let periods = &report.periods;
+        assert_eq!(periods.len(), 3);
+        assert_eq!(periods[0].start, "2022/08/01");
+        assert_eq!(periods[2].end, "2022/10/31");
+        assert_eq!(periods[1].remaining, Amount::parse("-150€").unwrap());
+        assert_eq!(periods[2].rollover, Amount::parse("0€").unwrap());
+        assert_eq!(periods[2].remaining, Amount::parse("7.25€").unwrap());
+
+        // with rollover the overspent ammount of september is missing in october
+        let mut with_rollover = budget(true);
+        with_rollover.start = Some("2022/09/15".to_string());
+        let report = budget_report(&journals, &with_rollover, date("2022/10/18"), 3).unwrap();
+        let periods = &report.periods;
+        assert_eq!(periods.len(), 2);
+        assert_eq!(periods[0].start, "2022/09/01");
+        assert_eq!(periods[1].rollover, Amount::parse("-150€").unwrap());
+        assert_eq!(periods[1].available, Amount::parse("-50€").unwrap());
+        assert_eq!(periods[1].remaining, Amount::parse("-142.75€").unwrap());
+
+        // a rollover over many years still reaches the current period
+        let mut weekly = budget(true);
+        weekly.ammount = Amount::parse("10€").unwrap();
+        weekly.period = "week".to_string();
+        weekly.start = Some("1990/01/01".to_string());
+        let report = budget_report(&journals, &weekly, date("2022/10/18"), 2).unwrap();
+        let periods = &report.periods;
+        assert_eq!(periods.len(), 2);
+        assert_eq!(periods[1].start, "2022/10/17");
+        // 1710 weeks before the report minus 262.50€ spent
+        assert_eq!(periods[0].rollover, Amount::parse("16837.50€").unwrap());
+        assert_eq!(periods[1].rollover, Amount::parse("16847.50€").unwrap());
+    }
+
+    #[test]
+    fn test_set_and_delete_budget() {
+        let user = "budget-test";
+        let _dir = UserDir::new(user);
+        assert!(user_budgets(user).unwrap().is_empty());
+        set_budget(user, budget(false)).unwrap();
+        let budgets = set_budget(user, budget(true)).unwrap();
+        assert_eq!(budgets, vec![budget(true)]);
+        let invalid = Budget {
+            period: "day".to_string(),
+            ..budget(false)
+        };
+        assert!(set_budget(user, invalid).is_err());
+        assert!(delete_budget(user, "Ausgaben:Essen").unwrap().is_empty());
+        assert!(matches!(
+            delete_budget(user, "Ausgaben:Essen"),
+            Err(ServiceError::NotFound(_))
+        ));
+    }
+}
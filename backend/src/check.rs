@@ -0,0 +1,184 @@
+//! Validation of the journals. Every problem is reported with file and line, so it can be
+//! fixed by hand.
+use log::warn;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::errors::ServiceError;
+use crate::journal::{self, Journal, Transaction};
+use crate::journals;
+use crate::utils::user_file;
+use shared::amount::{Amount, Decimal};
+use shared::models::{Diagnostic, JournalKind, Severity};
+
+/// Sums the amounts per commodity and returns the first sum, that is not zero
+/// in the precision of its commodity.
+pub fn unbalanced(amounts: impl Iterator<Item = Amount>) -> Option<Amount> {
+    let mut sums: BTreeMap<String, Decimal> = BTreeMap::new();
+    for amount in amounts {
+        *sums.entry(amount.commodity).or_default() += amount.quantity;
+    }
+    sums.into_iter()
+        .map(|(commodity, sum)| Amount::new(sum, &commodity))
+        .find(|a| !a.rounded().is_zero())
+}
+
+fn check_transaction(transaction: &Transaction) -> Vec<(usize, Severity, String)> {
+    let line = transaction.span.start;
+    let mut problems = Vec::new();
+    if transaction.headline.is_empty() {
+        problems.push((line, Severity::Warning, "Missing headline.".to_string()));
+    }
+    if transaction.postings.len() < 2 {
+        problems.push((
+            line,
+            Severity::Error,
+            "A transaction needs at least two postings.".to_string(),
+        ));
+    }
+    for posting in &transaction.postings {
+        if posting.account.contains(char::is_whitespace) {
+            problems.push((
+                posting.line,
+                Severity::Warning,
+                format!("The account '{}' contains whitespace.", posting.account),
+            ));
+        }
+    }
+    let elided = transaction
+        .postings
+        .iter()
+        .filter(|p| p.amount.is_none())
+        .count();
+    let weights = transaction.postings.iter().filter_map(|p| p.weight());
+    match (elided, unbalanced(weights)) {
+        (0, Some(difference)) => problems.push((
+            line,
+            Severity::Error,
+            format!(
+                "The transaction does not balance, the difference is {}.",
+                difference
+            ),
+        )),
+        (0 | 1, _) => (),
+        _ => problems.push((
+            line,
+            Severity::Error,
+            "Only one posting may have no amount.".to_string(),
+        )),
+    }
+    problems
+}
+
+/// all problems of the journal, sorted by line
+pub fn check_journal(file: &str, journal: &Journal) -> Vec<Diagnostic> {
+    let mut diagnostics = journal
+        .errors
+        .iter()
+        .map(|e| (e.line, Severity::Error, e.message.clone()))
+        .chain(journal.transactions().flat_map(check_transaction))
+        .map(|(line, severity, message)| Diagnostic {
+            file: file.to_string(),
+            line,
+            severity,
+            message,
+        })
+        .collect::<Vec<_>>();
+    diagnostics.sort_by_key(|d| (d.line, d.severity));
+    diagnostics
+}
+
+/// checks every existing journal of the given kinds
+pub fn ledger_check(user: &str, kinds: &[JournalKind]) -> Result<Vec<Diagnostic>, ServiceError> {
+    let mut diagnostics = Vec::new();
+    for definition in journals::user_journals(user)?
+        .into_iter()
+        .filter(|j| kinds.contains(&j.kind))
+    {
+        let path = user_file(user, &definition.path);
+        if Path::new(&path).exists() {
+            diagnostics.extend(check_journal(&definition.path, &journal::read_file(&path)?));
+        }
+    }
+    Ok(diagnostics)
+}
+
+/// Checks the journal file after it was written and logs its problems.
+pub fn check_written(user: &str, file: &str) -> Vec<Diagnostic> {
+    let diagnostics = match journal::read_file(&user_file(user, file)) {
+        Ok(journal) => check_journal(file, &journal),
+        Err(e) => {
+            warn!("Could not check '{}' of user '{}': {}", file, user, e);
+            return Vec::new();
+        }
+    };
+    for diagnostic in &diagnostics {
+        warn!(
+            "User '{}' {}:{} {:?}: {}",
+            user, diagnostic.file, diagnostic.line, diagnostic.severity, diagnostic.message
+        );
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_journal() {
+        let journal = journal::parse(
+            "2022/10/01 Balanced
+ \tAusgaben:Essen\t\t12.50€
+ \tGirokonto:N2\t\t-12.50€
+
+2022/10/02 Unbalanced
+ \tAusgaben:Essen\t\t12.50€
+ \tGirokonto:N2\t\t-12€
+
+2022/10/03
+ \tAusgaben:Essen
+ \tGiro konto
+
+2022/10/04 Single
+ \tAusgaben:Essen\t\t1€
+
+2022/13/05 Broken date
+ \tAusgaben:Essen\t\t1€
+ \tGirokonto:N2
+",
+        );
+        let diagnostics = check_journal("gesamt.dat", &journal)
+            .into_iter()
+            .map(|d| (d.line, d.severity))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            diagnostics,
+            vec![
+                (5, Severity::Error),
+                (9, Severity::Error),
+                (9, Severity::Warning),
+                (11, Severity::Warning),
+                (13, Severity::Error),
+                (13, Severity::Error),
+                (16, Severity::Error),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unbalanced_with_cost() {
+        let journal = journal::parse(
+            "2022/10/03 ETF
+ \tDepot:ETF\t\t3 ETF @@ 60.30€
+ \tGirokonto:N2\t\t-60.30€
+",
+        );
+        assert!(check_journal("invest.dat", &journal).is_empty());
+        let amounts = [
+            Amount::parse("1€").unwrap(),
+            Amount::parse("-1 USD").unwrap(),
+        ];
+        assert_eq!(unbalanced(amounts.iter().cloned()), Amount::parse("-1 USD"));
+    }
+}
@@ -1,4 +1,5 @@
 use std::env;
+use std::fmt;
 
 #[derive(Debug)]
 pub struct Application {
@@ -7,6 +8,87 @@ pub struct Application {
     pub database_url: String,
     pub server_ip: String,
     pub server_port: String,
+    pub time_tracking: TimeTracking,
+    pub recurring: Recurring,
+    pub registration: Registration,
+    pub passwords: Passwords,
+    pub jwt: Jwt,
+}
+
+/// Settings for the time tracking
+#[derive(Debug, Clone)]
+pub struct TimeTracking {
+    /// save stopped enteries running over midnight as one entery per day
+    pub split_at_midnight: bool,
+}
+
+/// Settings for the job, that creates the recurring transactions
+#[derive(Debug, Clone)]
+pub struct Recurring {
+    /// minutes between two runs of the job
+    pub interval_minutes: u64,
+}
+
+/// Settings for the creation of users
+#[derive(Debug, Clone)]
+pub struct Registration {
+    /// everybody can create an account, otherwise only the admins create users
+    pub open: bool,
+    /// user, that gets the admin role at startup
+    pub admin: Option<String>,
+}
+
+/// Settings for the passwords of the users
+#[derive(Debug, Clone)]
+pub struct Passwords {
+    /// rules for new passwords, existing passwords are not checked again
+    pub min_length: usize,
+    pub require_lowercase: bool,
+    pub require_uppercase: bool,
+    pub require_digit: bool,
+    pub require_special: bool,
+    /// Argon2 parameters of new hashes. Hashes with other parameters are replaced at the next
+    /// login of the user.
+    pub argon2_memory_kib: u32,
+    pub argon2_iterations: u32,
+    pub argon2_parallelism: u32,
+}
+
+/// Settings for the signing of the access tokens
+#[derive(Clone)]
+pub struct Jwt {
+    /// `HS256`, `HS384`, `HS512`, `RS256`, `RS384`, `RS512`, `PS256`, `PS384`, `PS512` or `EdDSA`
+    pub algorithm: String,
+    /// id of the signing key, written into the `kid` header of every token
+    pub key_id: String,
+    /// secret of the HMAC algorithms
+    pub secret: Option<String>,
+    /// PEM files of the RSA and Ed25519 algorithms
+    pub private_key_file: Option<String>,
+    pub public_key_file: Option<String>,
+    /// Keys of earlier rotations, that are still accepted until their tokens expired.
+    /// Every key is `kid:algorithm:secret` or `kid:algorithm:public key file`.
+    pub previous_keys: Vec<String>,
+}
+
+// the secrets must not end up in the log
+impl fmt::Debug for Jwt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Jwt")
+            .field("algorithm", &self.algorithm)
+            .field("key_id", &self.key_id)
+            .field("private_key_file", &self.private_key_file)
+            .field("public_key_file", &self.public_key_file)
+            .field(
+                "previous_keys",
+                &self
+                    .previous_keys
+                    .iter()
+                    .map(|k| k.split(':').next().unwrap_or_default())
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
 }
 
 impl Default for Application {
@@ -17,6 +99,90 @@ impl Default for Application {
             database_url: env::var("DATABASE_URL").expect("DATABASE_URL must be set in .env"),
             server_ip: env::var("SERVER_IP").expect("SERVER_IP must be set in .evn."),
             server_port: env::var("SERVER_PORT").expect("SERVER_PORT must be set in .evn."),
+            time_tracking: TimeTracking::default(),
+            recurring: Recurring::default(),
+            registration: Registration::default(),
+            passwords: Passwords::default(),
+            jwt: Jwt::default(),
+        }
+    }
+}
+
+impl Default for TimeTracking {
+    fn default() -> TimeTracking {
+        TimeTracking {
+            split_at_midnight: env::var("SPLIT_AT_MIDNIGHT")
+                .map(|v| v == "true")
+                .unwrap_or(false),
+        }
+    }
+}
+
+impl Default for Recurring {
+    fn default() -> Recurring {
+        Recurring {
+            interval_minutes: env::var("RECURRING_INTERVAL_MINUTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|m| *m > 0)
+                .unwrap_or(60),
+        }
+    }
+}
+
+impl Default for Registration {
+    fn default() -> Registration {
+        Registration {
+            open: env::var("OPEN_REGISTRATION")
+                .map(|v| v == "true")
+                .unwrap_or(false),
+            admin: env::var("ADMIN_USER").ok().filter(|u| !u.is_empty()),
+        }
+    }
+}
+
+fn env_flag(key: &str) -> bool {
+    env::var(key).map(|v| v == "true").unwrap_or(false)
+}
+
+fn env_number<T: std::str::FromStr>(key: &str, default: T) -> T {
+    env::var(key)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+impl Default for Passwords {
+    fn default() -> Passwords {
+        Passwords {
+            min_length: env_number("PASSWORD_MIN_LENGTH", 8),
+            require_lowercase: env_flag("PASSWORD_REQUIRE_LOWERCASE"),
+            require_uppercase: env_flag("PASSWORD_REQUIRE_UPPERCASE"),
+            require_digit: env_flag("PASSWORD_REQUIRE_DIGIT"),
+            require_special: env_flag("PASSWORD_REQUIRE_SPECIAL"),
+            argon2_memory_kib: env_number("ARGON2_MEMORY_KIB", argon2::Params::DEFAULT_M_COST),
+            argon2_iterations: env_number("ARGON2_ITERATIONS", argon2::Params::DEFAULT_T_COST),
+            argon2_parallelism: env_number("ARGON2_PARALLELISM", argon2::Params::DEFAULT_P_COST),
+        }
+    }
+}
+
+impl Default for Jwt {
+    fn default() -> Jwt {
+        Jwt {
+            algorithm: env::var("JWT_ALGORITHM").unwrap_or_else(|_| "HS256".to_string()),
+            key_id: env::var("JWT_KEY_ID").unwrap_or_default(),
+            secret: env::var("JWT_SECRET").ok(),
+            private_key_file: env::var("JWT_PRIVATE_KEY_FILE").ok(),
+            public_key_file: env::var("JWT_PUBLIC_KEY_FILE").ok(),
+            previous_keys: env::var("JWT_PREVIOUS_KEYS")
+                .map(|keys| {
+                    keys.split(',')
+                        .map(|k| k.trim().to_string())
+                        .filter(|k| !k.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default(),
         }
     }
 }
@@ -1 +1,3 @@
+pub mod roles;
+pub mod tokens;
 pub mod users;
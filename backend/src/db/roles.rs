@@ -0,0 +1,73 @@
+use crate::db::users;
+use crate::errors::ServiceError;
+use crate::models::db::UserRole;
+use crate::models::schema::{role_permissions, roles, user_roles};
+use diesel::prelude::*;
+use log::{debug, info};
+
+/// role of every new user
+pub const DEFAULT_ROLE: &str = "editor";
+/// role with the permission to manage the users
+pub const ADMIN: &str = "admin";
+
+/// All permissions of the roles of the user. They are attached to the access token.
+pub fn user_permissions(conn: &mut PgConnection, name: &str) -> Result<Vec<String>, ServiceError> {
+    debug!("Selecting permissions of User: {:?}", name);
+    Ok(user_roles::table
+        .inner_join(role_permissions::table.on(role_permissions::role.eq(user_roles::role)))
+        .filter(user_roles::username.eq(name))
+        .select(role_permissions::permission)
+        .distinct()
+        .order(role_permissions::permission)
+        .load::<String>(conn)?)
+}
+
+pub fn assign_role(conn: &mut PgConnection, name: &str, role: &str) -> Result<(), ServiceError> {
+    info!("Assign role {:?} to User: {:?}", role, name);
+    diesel::insert_into(user_roles::table)
+        .values(&UserRole {
+            username: name,
+            role,
+        })
+        .on_conflict_do_nothing()
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Gives the existing user the admin role.
+pub fn grant_admin(conn: &mut PgConnection, name: &str) -> Result<(), ServiceError> {
+    users::get_user(conn, name)?;
+    assign_role(conn, name, ADMIN)
+}
+
+/// Replaces the roles of the user. Unknown roles are rejected.
+pub fn set_roles(
+    conn: &mut PgConnection,
+    name: &str,
+    new_roles: &[String],
+) -> Result<(), ServiceError> {
+    let known = roles::table
+        .filter(roles::name.eq_any(new_roles))
+        .select(roles::name)
+        .load::<String>(conn)?;
+    if let Some(unknown) = new_roles.iter().find(|r| !known.contains(r)) {
+        return Err(ServiceError::BadRequest(format!(
+            "Unknown role '{}'.",
+            unknown
+        )));
+    }
+    info!("Set roles {:?} of User: {:?}", new_roles, name);
+    diesel::delete(user_roles::table.filter(user_roles::username.eq(name))).execute(conn)?;
+    for role in new_roles {
+        assign_role(conn, name, role)?;
+    }
+    Ok(())
+}
+
+/// roles of all users as pairs of username and role
+pub fn all_user_roles(conn: &mut PgConnection) -> Result<Vec<(String, String)>, ServiceError> {
+    Ok(user_roles::table
+        .select((user_roles::username, user_roles::role))
+        .order((user_roles::username, user_roles::role))
+        .load(conn)?)
+}
@@ -0,0 +1,126 @@
+use crate::errors::ServiceError;
+use crate::models::db::{NewRefreshToken, RefreshToken, RevokedToken};
+use crate::models::schema::{refresh_tokens, revoked_tokens};
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use log::{debug, warn};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+const REFRESH_EXPIRATION_HOURS: i64 = 24 * 31;
+
+/// Only the hash of a refresh token is stored, so a leaked database holds no valid token.
+fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+/// Creates a new refresh token for the user. Without family a new login is started.
+pub fn create_refresh_token(
+    conn: &mut PgConnection,
+    name: &str,
+    family: Option<&str>,
+) -> Result<String, ServiceError> {
+    let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    let family_id = match family {
+        Some(f) => f.to_string(),
+        None => Uuid::new_v4().to_string(),
+    };
+    debug!("Create refresh token of family {} for {}", family_id, name);
+    diesel::insert_into(refresh_tokens::table)
+        .values(&NewRefreshToken {
+            token_hash: &hash_token(&token),
+            family_id: &family_id,
+            username: name,
+            expires_at: (Utc::now() + Duration::hours(REFRESH_EXPIRATION_HOURS)).timestamp(),
+        })
+        .execute(conn)?;
+    Ok(token)
+}
+
+/// Marks the refresh token as used and returns it. A token can only be used once, if a used
+/// token is presented again, it was stolen and the whole family is revoked.
+pub fn use_refresh_token(
+    conn: &mut PgConnection,
+    token: &str,
+) -> Result<RefreshToken, ServiceError> {
+    // the revocation of a reused family has to be committed, so the transaction returns no
+    // token instead of an error
+    conn.transaction::<_, ServiceError, _>(|conn| {
+        let stored = match refresh_tokens::table
+            .find(hash_token(token))
+            .for_update()
+            .first::<RefreshToken>(conn)
+            .optional()?
+        {
+            Some(stored) => stored,
+            None => return Ok(None),
+        };
+        if stored.used {
+            warn!(
+                "Refresh token of family {} was reused, revoke the family.",
+                stored.family_id
+            );
+            revoke_family(conn, &stored.family_id)?;
+            return Ok(None);
+        }
+        if stored.expires_at < Utc::now().timestamp() {
+            return Ok(None);
+        }
+        diesel::update(refresh_tokens::table.find(&stored.token_hash))
+            .set(refresh_tokens::used.eq(true))
+            .execute(conn)?;
+        Ok(Some(stored))
+    })?
+    .ok_or(ServiceError::Unauthorized)
+}
+
+fn revoke_family(conn: &mut PgConnection, family: &str) -> Result<usize, ServiceError> {
+    Ok(
+        diesel::delete(refresh_tokens::table.filter(refresh_tokens::family_id.eq(family)))
+            .execute(conn)?,
+    )
+}
+
+/// Revokes the login, that the refresh token belongs to. Unknown tokens are ignored.
+pub fn revoke_refresh_token(conn: &mut PgConnection, token: &str) -> Result<(), ServiceError> {
+    let family = refresh_tokens::table
+        .find(hash_token(token))
+        .select(refresh_tokens::family_id)
+        .first::<String>(conn)
+        .optional()?;
+    if let Some(family) = family {
+        debug!("Revoke refresh token family {}", family);
+        revoke_family(conn, &family)?;
+    }
+    Ok(())
+}
+
+/// Rejects the access token until it expires. Expired enteries are removed on the way.
+pub fn revoke_access_token(
+    conn: &mut PgConnection,
+    jti: &str,
+    expires_at: i64,
+) -> Result<(), ServiceError> {
+    let now = Utc::now().timestamp();
+    diesel::delete(revoked_tokens::table.filter(revoked_tokens::expires_at.lt(now)))
+        .execute(conn)?;
+    diesel::delete(refresh_tokens::table.filter(refresh_tokens::expires_at.lt(now)))
+        .execute(conn)?;
+    diesel::insert_into(revoked_tokens::table)
+        .values(&RevokedToken { jti, expires_at })
+        .on_conflict_do_nothing()
+        .execute(conn)?;
+    Ok(())
+}
+
+pub fn is_revoked(conn: &mut PgConnection, jti: &str) -> Result<bool, ServiceError> {
+    Ok(diesel::select(diesel::dsl::exists(revoked_tokens::table.find(jti))).get_result(conn)?)
+}
+
+/// Revokes every login of the user, the access tokens stay valid until they expire.
+pub fn revoke_user_tokens(conn: &mut PgConnection, name: &str) -> Result<(), ServiceError> {
+    debug!("Revoke refresh tokens of {}", name);
+    diesel::delete(refresh_tokens::table.filter(refresh_tokens::username.eq(name)))
+        .execute(conn)?;
+    Ok(())
+}
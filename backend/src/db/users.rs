@@ -1,73 +1,167 @@
+use crate::configuration::Passwords;
+use crate::db::{roles, tokens};
 use crate::errors::ServiceError;
 use crate::models::db::{NewUser, User};
 use crate::models::schema::users::dsl::*;
 use crate::utils;
 use diesel::prelude::*;
 use log::{debug, info};
+use shared::auth::UserInfo;
 use uuid::Uuid;
 
-pub fn insert_user(conn: &mut PgConnection, name: &str, pwd: &str) -> Result<User, ServiceError> {
-    info!("Creating new User Name: {:?} pwd: {:?} ", &name, &pwd);
+// Passwords and their hashes are never logged.
 
+pub fn insert_user(
+    conn: &mut PgConnection,
+    name: &str,
+    pwd: &str,
+    settings: &Passwords,
+) -> Result<User, ServiceError> {
+    info!("Creating new User Name: {:?}", &name);
+
+    utils::check_password_policy(pwd, settings)?;
     let new_user = NewUser {
         user_id: &Uuid::new_v4().to_string(),
         username: &name,
-        password: &utils::hash_password(&pwd)?,
+        password: &utils::hash_password(pwd, settings)?,
     };
 
-    Ok(diesel::insert_into(users)
+    let user = diesel::insert_into(users)
         .values(&new_user)
-        .get_result(conn)
-        .expect("Error inserting new user"))
+        .get_result(conn)?;
+    roles::assign_role(conn, name, roles::DEFAULT_ROLE)?;
+    Ok(user)
 }
 
-#[allow(dead_code)]
-pub fn get_user(conn: &mut PgConnection, _username: &str) -> Result<User, String> {
+pub fn get_user(conn: &mut PgConnection, _username: &str) -> Result<User, ServiceError> {
     debug!("Selecting User with username: {:?}", _username);
 
-    let mut results = users
-        .filter(username.eq(_username))
-        .load::<User>(conn)
-        .expect("Error loading users");
+    users
+        .find(_username)
+        .first::<User>(conn)
+        .optional()?
+        .ok_or_else(|| ServiceError::NotFound(format!("User '{}' not found.", _username)))
+}
+
+/// all users with their roles
+pub fn list_users(conn: &mut PgConnection) -> Result<Vec<UserInfo>, ServiceError> {
+    debug!("Selecting all Users");
+
+    let user_roles = roles::all_user_roles(conn)?;
+    Ok(users
+        .order(username)
+        .load::<User>(conn)?
+        .into_iter()
+        .map(|user| UserInfo {
+            roles: user_roles
+                .iter()
+                .filter(|(name, _)| name == &user.username)
+                .map(|(_, role)| role.clone())
+                .collect(),
+            username: user.username,
+            disabled: user.disabled,
+        })
+        .collect())
+}
+
+fn not_found(_username: &str, changed: usize) -> Result<(), ServiceError> {
+    match changed {
+        0 => Err(ServiceError::NotFound(format!(
+            "User '{}' not found.",
+            _username
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Disabled users can not login anymore and their logins are revoked.
+pub fn set_disabled(
+    conn: &mut PgConnection,
+    _username: &str,
+    _disabled: bool,
+) -> Result<(), ServiceError> {
+    info!("Set User {:?} disabled: {:?}", _username, _disabled);
+
+    let changed = diesel::update(users.find(_username))
+        .set(disabled.eq(_disabled))
+        .execute(conn)?;
+    not_found(_username, changed)?;
+    if _disabled {
+        tokens::revoke_user_tokens(conn, _username)?;
+    }
+    Ok(())
+}
+
+/// Sets a new password and revokes the logins of the user.
+pub fn reset_password(
+    conn: &mut PgConnection,
+    _username: &str,
+    _password: &str,
+    settings: &Passwords,
+) -> Result<(), ServiceError> {
+    info!("Reset password of User with username: {:?}", _username);
+
+    utils::check_password_policy(_password, settings)?;
+    let changed = diesel::update(users.find(_username))
+        .set(password.eq(utils::hash_password(_password, settings)?))
+        .execute(conn)?;
+    not_found(_username, changed)?;
+    tokens::revoke_user_tokens(conn, _username)
+}
+
+/// Changes the password of the user, if the current password is correct. All logins of the
+/// user are revoked.
+pub fn change_password(
+    conn: &mut PgConnection,
+    _username: &str,
+    current: &str,
+    new: &str,
+    settings: &Passwords,
+) -> Result<(), ServiceError> {
+    info!("Change password of User with username: {:?}", _username);
 
-    for user in results.pop() {
-        return Ok(user);
+    if !check_login(conn, _username, current, settings)? {
+        return Err(ServiceError::Unauthorized);
+    }
+    if current == new {
+        return Err(ServiceError::BadRequest(
+            "The new password has to differ from the current one.".to_string(),
+        ));
     }
-    return Err("UserNotFound".to_string());
+    reset_password(conn, _username, new, settings)
 }
 
-#[allow(dead_code)]
-pub fn delete_user(conn: &mut PgConnection, _username: &str) {
+pub fn delete_user(conn: &mut PgConnection, _username: &str) -> Result<(), ServiceError> {
     info!("Delete User with username: {:?}", _username);
 
-    diesel::delete(users.filter(username.like(_username)))
-        .execute(conn)
-        .expect("Error deleting posts");
+    let changed = diesel::delete(users.find(_username)).execute(conn)?;
+    not_found(_username, changed)
 }
 
+/// Checks the password of an enabled user. Hashes with outdated Argon2 parameters are
+/// replaced, while the password is known.
 pub fn check_login(
     conn: &mut PgConnection,
     _username: &str,
     _password: &str,
+    settings: &Passwords,
 ) -> Result<bool, ServiceError> {
-    debug!(
-        "Check Login for User with username: {:?} password:{:?}",
-        &_username, &_password
-    );
-
-    let results = users
-        .filter(username.eq(_username))
-        .load::<User>(conn)
-        .expect("Error loading users");
-
-    for user in &results {
-        if utils::verify(&user.password, &_password)? {
-            info!(
-                "Logging in: User {:?} Pwd:{:?} Id:{:?}",
-                user.username, user.password, user.user_id
-            );
-            return Ok(true);
-        }
+    debug!("Check Login for User with username: {:?}", &_username);
+
+    let user = match get_user(conn, _username) {
+        Ok(user) if !user.disabled => user,
+        Ok(_) | Err(ServiceError::NotFound(_)) => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    if !utils::verify(&user.password, _password)? {
+        return Ok(false);
+    }
+    if utils::needs_rehash(&user.password, settings)? {
+        info!("Rehash password of User: {:?}", user.username);
+        diesel::update(users.find(_username))
+            .set(password.eq(utils::hash_password(_password, settings)?))
+            .execute(conn)?;
     }
-    return Ok(false);
+    info!("Logging in: User {:?} Id:{:?}", user.username, user.user_id);
+    Ok(true)
 }
@@ -1,6 +1,7 @@
 use actix_web::{error::ResponseError, Error as ActixWebError, HttpResponse};
 use argon2::password_hash::Error as ArgonError;
 use derive_more::Display;
+use diesel::r2d2::PoolError;
 use diesel::result::{DatabaseErrorKind, Error as DBError};
 use serde_json::Error as SerdeJsonError;
 
@@ -13,9 +14,15 @@ pub enum ServiceError {
     #[display(fmt = "BadRequest: {}", _0)]
     BadRequest(String),
 
+    #[display(fmt = "NotFound: {}", _0)]
+    NotFound(String),
+
     //TODO map to actix unotharized
     #[display(fmt = "Unauthorized")]
     Unauthorized,
+
+    #[display(fmt = "Forbidden")]
+    Forbidden,
 }
 
 // impl ResponseError trait allows to convert our errors into http responses with appropriate data
@@ -26,7 +33,9 @@ impl ResponseError for ServiceError {
                 HttpResponse::InternalServerError().json(message)
             }
             ServiceError::BadRequest(ref message) => HttpResponse::BadRequest().json(message),
+            ServiceError::NotFound(ref message) => HttpResponse::NotFound().json(message),
             ServiceError::Unauthorized => HttpResponse::Unauthorized().json("Unauthorized"),
+            ServiceError::Forbidden => HttpResponse::Forbidden().json("Forbidden"),
         }
     }
 }
@@ -46,6 +55,12 @@ impl From<DBError> for ServiceError {
     }
 }
 
+impl From<PoolError> for ServiceError {
+    fn from(_error: PoolError) -> ServiceError {
+        ServiceError::InternalServerError("No connection to the database".to_string())
+    }
+}
+
 impl From<ArgonError> for ServiceError {
     fn from(error: ArgonError) -> ServiceError {
         match error {
@@ -0,0 +1,292 @@
+//! Export of the time enteries and finance transactions as CSV or JSON.
+use chrono::NaiveDate;
+use log::debug;
+use std::path::Path;
+
+use crate::accounts;
+use crate::errors::ServiceError;
+use crate::journal::{self, Journal, Transaction};
+use crate::journals;
+use crate::utils::{self, user_file};
+use shared::amount::Amount;
+use shared::models::{
+    FinancePosting, FinanceTransaction, JournalDefinition, JournalKind, RequestExport,
+    TimeEnteryHistory,
+};
+
+pub const FORMATS: [&str; 2] = ["csv", "json"];
+
+/// filter of the export request
+struct Filter<'a> {
+    start: Option<NaiveDate>,
+    end: Option<NaiveDate>,
+    account: Option<&'a str>,
+}
+
+impl Filter<'_> {
+    fn new(request: &RequestExport) -> Result<Filter<'_>, ServiceError> {
+        if !FORMATS.contains(&request.format.as_str()) {
+            return Err(ServiceError::BadRequest(format!(
+                "Unknown format '{}'.",
+                request.format
+            )));
+        }
+        let parse = |date: &Option<String>| match date {
+            Some(d) => NaiveDate::parse_from_str(&d.replace('-', "/"), "%Y/%m/%d")
+                .map(Some)
+                .map_err(|_| ServiceError::BadRequest(format!("Invalid date '{}'.", d))),
+            None => Ok(None),
+        };
+        Ok(Filter {
+            start: parse(&request.start)?,
+            end: parse(&request.end)?,
+            account: request.account.as_deref().filter(|a| !a.is_empty()),
+        })
+    }
+
+    fn date(&self, date: NaiveDate) -> bool {
+        self.start.is_none_or(|s| date >= s) && self.end.is_none_or(|e| date <= e)
+    }
+
+    /// the account is the filter account or one of its sub accounts
+    fn account(&self, account: &str) -> bool {
+        self.account
+            .is_none_or(|parent| accounts::is_below(account, parent))
+    }
+}
+
+/// journals of the kind, that are selected by the request. Archived journals are included.
+fn selected_journals(
+    user: &str,
+    kind: JournalKind,
+    names: &[String],
+) -> Result<Vec<(JournalDefinition, Journal)>, ServiceError> {
+    let definitions = journals::user_journals(user)?
+        .into_iter()
+        .filter(|j| j.kind == kind)
+        .collect::<Vec<_>>();
+    if let Some(unknown) = names
+        .iter()
+        .find(|n| !definitions.iter().any(|j| &&j.name == n))
+    {
+        return Err(ServiceError::BadRequest(format!(
+            "Unknown journal '{}'.",
+            unknown
+        )));
+    }
+    definitions
+        .into_iter()
+        .filter(|j| names.is_empty() || names.contains(&j.name))
+        .filter(|j| Path::new(&user_file(user, &j.path)).exists())
+        .map(|j| {
+            let journal = journal::read_file(&user_file(user, &j.path))?;
+            Ok((j, journal))
+        })
+        .collect()
+}
+
+/// the postings of the transaction, a posting without amount gets the balancing value
+fn postings(transaction: &Transaction) -> Vec<FinancePosting> {
+    let balancing = transaction
+        .postings
+        .iter()
+        .find_map(|p| p.weight())
+        .map(|a| a.commodity.clone())
+        .unwrap_or_default();
+    transaction
+        .postings
+        .iter()
+        .zip(transaction.values())
+        .map(|(posting, value)| FinancePosting {
+            account: posting.account.clone(),
+            ammount: Some(
+                posting
+                    .amount
+                    .clone()
+                    .unwrap_or_else(|| Amount::new(value, &balancing)),
+            ),
+            cost: posting.cost.clone(),
+        })
+        .collect()
+}
+
+/// time enteries of the user, that match the request
+pub fn time_export(
+    user: &str,
+    request: &RequestExport,
+) -> Result<Vec<TimeEnteryHistory>, ServiceError> {
+    let filter = Filter::new(request)?;
+    let mut enteries = Vec::new();
+    for (_, journal) in selected_journals(user, JournalKind::Time, &request.journals)? {
+        enteries.extend(utils::time_history(&journal).into_iter().filter(|e| {
+            NaiveDate::parse_from_str(&e.date, "%Y/%m/%d").is_ok_and(|d| filter.date(d))
+                && (filter.account(&e.account_origin) || filter.account(&e.account_target))
+        }));
+    }
+    debug!("Export {} time enteries of '{}'", enteries.len(), user);
+    Ok(enteries)
+}
+
+/// finance transactions of the user, that match the request. Transactions are exported
+/// with all postings, if one of them matches the account.
+pub fn finance_export(
+    user: &str,
+    request: &RequestExport,
+) -> Result<Vec<FinanceTransaction>, ServiceError> {
+    let filter = Filter::new(request)?;
+    let mut transactions = Vec::new();
+    for (definition, journal) in selected_journals(user, JournalKind::Finance, &request.journals)? {
+        transactions.extend(
+            journal
+                .transactions()
+                .zip(journal.transaction_ids())
+                .filter(|(t, _)| filter.date(t.date))
+                .filter(|(t, _)| t.postings.iter().any(|p| filter.account(&p.account)))
+                .map(|(t, id)| FinanceTransaction {
+                    id,
+                    date: t.date.format("%Y/%m/%d").to_string(),
+                    headline: t.headline.clone(),
+                    journal: definition.name.clone(),
+                    postings: postings(t),
+                }),
+        );
+    }
+    debug!("Export {} transactions of '{}'", transactions.len(), user);
+    Ok(transactions)
+}
+
+fn csv_error(error: impl std::fmt::Display) -> ServiceError {
+    ServiceError::InternalServerError(format!("Could not write CSV: {}", error))
+}
+
+/// one line for every time entery
+pub fn time_csv(enteries: &[TimeEnteryHistory]) -> Result<String, ServiceError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record([
+            "date",
+            "timespan",
+            "headline",
+            "account_origin",
+            "account_target",
+            "minutes",
+            "id",
+        ])
+        .map_err(csv_error)?;
+    for entery in enteries {
+        writer
+            .write_record([
+                &entery.date,
+                &entery.timespan,
+                &entery.headline,
+                &entery.account_origin,
+                &entery.account_target,
+                &entery.duration.to_string(),
+                &entery.id,
+            ])
+            .map_err(csv_error)?;
+    }
+    String::from_utf8(writer.into_inner().map_err(csv_error)?).map_err(csv_error)
+}
+
+/// one line for every posting
+pub fn finance_csv(transactions: &[FinanceTransaction]) -> Result<String, ServiceError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record([
+            "date",
+            "journal",
+            "headline",
+            "account",
+            "quantity",
+            "commodity",
+            "cost",
+            "id",
+        ])
+        .map_err(csv_error)?;
+    for transaction in transactions {
+        for posting in &transaction.postings {
+            let ammount = posting.ammount.clone().unwrap_or_default();
+            writer
+                .write_record([
+                    &transaction.date,
+                    &transaction.journal,
+                    &transaction.headline,
+                    &posting.account,
+                    &ammount.quantity.to_string(),
+                    &ammount.commodity,
+                    &posting
+                        .cost
+                        .as_ref()
+                        .map(|c| c.to_string())
+                        .unwrap_or_default(),
+                    &transaction.id,
+                ])
+                .map_err(csv_error)?;
+        }
+    }
+    String::from_utf8(writer.into_inner().map_err(csv_error)?).map_err(csv_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(start: &str, account: &str) -> RequestExport {
+        RequestExport {
+            format: "csv".to_string(),
+            start: Some(start.to_string()),
+            end: None,
+            account: Some(account.to_string()),
+            journals: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_filter() {
+        let request = request("2022-10-01", "Ausgaben:Essen");
+        let filter = Filter::new(&request).unwrap();
+        assert!(filter.account("Ausgaben:Essen:Obst"));
+        assert!(!filter.account("Ausgaben:Essenz"));
+        assert!(filter.date(NaiveDate::from_ymd_opt(2022, 10, 1).unwrap()));
+        assert!(!filter.date(NaiveDate::from_ymd_opt(2022, 9, 30).unwrap()));
+        let invalid = RequestExport {
+            format: "xml".to_string(),
+            ..request.clone()
+        };
+        assert!(Filter::new(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_finance_csv() {
+        let journal = journal::parse(
+            "2022/10/02 Supermarkt, Obst
+ \t; id: obst
+ \tAusgaben:Essen:Obst\t\t12.50€
+ \tGirokonto:N2
+",
+        );
+        let transactions = journal
+            .transactions()
+            .zip(journal.transaction_ids())
+            .map(|(t, id)| FinanceTransaction {
+                id,
+                date: t.date.format("%Y/%m/%d").to_string(),
+                headline: t.headline.clone(),
+                journal: "Gesamt".to_string(),
+                postings: postings(t),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            transactions[0].postings[1].ammount,
+            Amount::parse("-12.50€")
+        );
+        assert_eq!(
+            finance_csv(&transactions).unwrap(),
+            "date,journal,headline,account,quantity,commodity,cost,id
+2022/10/02,Gesamt,\"Supermarkt, Obst\",Ausgaben:Essen:Obst,12.50,€,,obst
+2022/10/02,Gesamt,\"Supermarkt, Obst\",Girokonto:N2,-12.50,€,,obst
+"
+        );
+    }
+}
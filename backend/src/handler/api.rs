@@ -1,17 +1,22 @@
-use actix_web::{web, Result};
+use actix_web::{http::header, web, HttpResponse, Result};
 use actix_web_grants::proc_macro::has_permissions;
 use actix_web_httpauth::extractors::bearer::BearerAuth;
-use diesel::PgConnection;
+use diesel::{Connection, PgConnection};
 use log::debug;
 
-use std::fs;
-
 use crate::{
+    accounts, analysis,
     auth::{create_token, decode_jwt},
-    db::users::{check_login, insert_user},
+    budget, check,
+    configuration::{Passwords, Registration, TimeTracking},
+    db::{
+        roles, tokens,
+        users::{self, check_login, insert_user},
+    },
     errors::ServiceError,
-    models::db::{Pool, User},
-    utils,
+    export, import, journal, journals,
+    models::db::Pool,
+    recurring, report, statistics, suggestion, utils,
 };
 use shared::auth::*;
 use shared::models::*;
@@ -19,59 +24,208 @@ use shared::models::*;
 /// Handles user Login and returns JWT
 pub async fn login(
     pool: web::Data<Pool>,
+    passwords: web::Data<Passwords>,
     user_login: web::Json<UserLogin>,
 ) -> Result<web::Json<UserLoginResponse>, ServiceError> {
     debug!(
         "login function called for User: {:#?}",
         &user_login.username
     );
-    let connection: &mut PgConnection = &mut pool.get().unwrap();
-    if !check_login(connection, &user_login.username, &user_login.password)? {
+    let mut connection = pool.get()?;
+    let connection: &mut PgConnection = &mut connection;
+    if !check_login(
+        connection,
+        &user_login.username,
+        &user_login.password,
+        &passwords,
+    )? {
         return Err(ServiceError::Unauthorized);
     };
-    let permissions = Vec::from([
-        "ADMIN_ROLE".to_string(),
-        "GET_LEDGER_INFO".to_string(),
-        "SET_LEDGER_INFO".to_string(),
-    ]);
-    let token_str = create_token(user_login.username.clone(), permissions).await?;
-
-    let response = UserLoginResponse {
-        username: user_login.username.clone(),
-        token: token_str.clone(),
-    };
-    Ok(web::Json(response))
+    Ok(web::Json(
+        new_login(connection, user_login.username.clone()).await?,
+    ))
+}
+
+/// access and refresh token of a new login
+async fn new_login(
+    connection: &mut PgConnection,
+    username: String,
+) -> Result<UserLoginResponse, ServiceError> {
+    let permissions = roles::user_permissions(connection, &username)?;
+    let token_str = create_token(username.clone(), permissions).await?;
+    let refresh_token = tokens::create_refresh_token(connection, &username, None)?;
+    Ok(UserLoginResponse {
+        username,
+        token: token_str,
+        refresh_token,
+    })
 }
 
-/// interface to create new user
+/// Changes the password of the user. The other logins are revoked, the response is a new
+/// login.
+pub async fn set_password_change(
+    pool: web::Data<Pool>,
+    passwords: web::Data<Passwords>,
+    payload: web::Json<PasswordChange>,
+    credentials: BearerAuth,
+) -> Result<web::Json<UserLoginResponse>, ServiceError> {
+    let claims = decode_jwt(credentials.token()).unwrap();
+    debug!("User '{}' Change password.", &claims.username);
+    let mut connection = pool.get()?;
+    let connection: &mut PgConnection = &mut connection;
+    users::change_password(
+        connection,
+        &claims.username,
+        &payload.current_password,
+        &payload.new_password,
+        &passwords,
+    )?;
+    tokens::revoke_access_token(connection, &claims.jti, claims.exp)?;
+    Ok(web::Json(new_login(connection, claims.username).await?))
+}
+
+/// Replaces the refresh token with a new one and returns a new access token
+pub async fn refresh(
+    pool: web::Data<Pool>,
+    payload: web::Json<RefreshToken>,
+) -> Result<web::Json<UserLoginResponse>, ServiceError> {
+    let mut connection = pool.get()?;
+    let connection: &mut PgConnection = &mut connection;
+    let used = tokens::use_refresh_token(connection, &payload.refresh_token)?;
+    debug!("Refresh login of User: {:#?}", &used.username);
+    // changed roles take effect with the next refresh
+    let permissions = roles::user_permissions(connection, &used.username)?;
+    let token_str = create_token(used.username.clone(), permissions).await?;
+    let refresh_token =
+        tokens::create_refresh_token(connection, &used.username, Some(&used.family_id))?;
+    Ok(web::Json(UserLoginResponse {
+        username: used.username,
+        token: token_str,
+        refresh_token,
+    }))
+}
+
+/// Revokes the refresh token and the access token, if it is given and still valid
+pub async fn logout(
+    pool: web::Data<Pool>,
+    payload: web::Json<RefreshToken>,
+    credentials: Option<BearerAuth>,
+) -> Result<web::Json<ResponseStatus>, ServiceError> {
+    let mut connection = pool.get()?;
+    let connection: &mut PgConnection = &mut connection;
+    tokens::revoke_refresh_token(connection, &payload.refresh_token)?;
+    if let Some(claims) = credentials.and_then(|c| decode_jwt(c.token()).ok()) {
+        debug!("Logout of User: {:#?}", &claims.username);
+        tokens::revoke_access_token(connection, &claims.jti, claims.exp)?;
+    }
+    Ok(web::Json(ResponseStatus {
+        status: 0,
+        diagnostics: Vec::new(),
+    }))
+}
+
+/// interface to create new user, if the registration is open
 pub async fn create_user(
     pool: web::Data<Pool>,
+    registration: web::Data<Registration>,
+    passwords: web::Data<Passwords>,
     user_data: web::Json<NewUser>,
-) -> Result<web::Json<User>, ServiceError> {
-    let connection: &mut PgConnection = &mut pool.get().unwrap();
-    match insert_user(connection, &user_data.username, &user_data.password) {
-        Ok(u) => return Ok(web::Json(u)),
-        Err(e) => return Err(e),
+) -> Result<web::Json<UserInfo>, ServiceError> {
+    if !registration.open {
+        return Err(ServiceError::Forbidden);
+    }
+    let mut connection = pool.get()?;
+    let created = connection.transaction::<_, ServiceError, _>(|conn| {
+        insert_user(conn, &user_data.username, &user_data.password, &passwords)
+    })?;
+    Ok(web::Json(UserInfo {
+        username: created.username,
+        disabled: created.disabled,
+        roles: vec![roles::DEFAULT_ROLE.to_string()],
+    }))
+}
+
+/// Reports of the time and finance journals need the permission to read them,
+/// the music journals are covered by `GET_LEDGER_INFO`.
+fn can_read_target(permissions: &[String], target: &str) -> bool {
+    let permission = match target {
+        "timeManagment" => "GET_TIME_INFO",
+        "finance" => "GET_FINANCE_INFO",
+        _ => "GET_LEDGER_INFO",
+    };
+    permissions.iter().any(|p| p == permission)
+}
+
+/// kinds of the journals, that the permissions allow to read
+fn readable_kinds(permissions: &[String]) -> Vec<JournalKind> {
+    [
+        ("timeManagment", JournalKind::Time),
+        ("finance", JournalKind::Finance),
+        ("music", JournalKind::Music),
+    ]
+    .into_iter()
+    .filter(|(target, _)| can_read_target(permissions, target))
+    .map(|(_, kind)| kind)
+    .collect()
+}
+
+/// Time and finance journals are written with their own permission,
+/// the music journals with `SET_LEDGER_INFO`.
+fn can_write_kind(permissions: &[String], kind: JournalKind) -> bool {
+    let permission = match kind {
+        JournalKind::Time => "SET_TIME_INFO",
+        JournalKind::Finance => "SET_FINANCE_INFO",
+        JournalKind::Music => "SET_LEDGER_INFO",
     };
+    permissions.iter().any(|p| p == permission)
 }
 
-/// get Html files
+/// get Html summary, rendered from the journals
 #[has_permissions("GET_LEDGER_INFO")]
 pub async fn get_html(
     info: web::Json<HtmlSuggestion>,
     credentials: BearerAuth,
 ) -> Result<web::Json<ResponseHtml>, ServiceError> {
-    let user = decode_jwt(credentials.token()).unwrap().username;
+    let claims = decode_jwt(credentials.token()).unwrap();
+    if !can_read_target(&claims.permissions, &info.target) {
+        return Err(ServiceError::Forbidden);
+    }
+    let user = claims.username;
     debug!(
         "User '{}' Get HTML function called for target: \t {:#?} \tdate: \t {:#?} \ttimespan: \t{:#?} \tdepth: \t{:#?}",
         &user, &info.target, &info.date, &info.timespan, &info.depth
     );
-    //TODO make path more general. right now, it only works, if cargo run is executed one dir above
-    let file = fs::read_to_string(format!(
-        "./files/{}/{}/{}-{}-{}.html",
-        &user, &info.target, &info.date, &info.timespan, &info.depth
-    ))?;
-    Ok(web::Json(ResponseHtml { html: file }))
+    let report = report::ledger_report(&user, &info)?;
+    Ok(web::Json(ResponseHtml {
+        html: report::render_html(&report),
+    }))
+}
+
+/// get balance report for the selection, optional with rendered html
+#[has_permissions("GET_LEDGER_INFO")]
+pub async fn get_report(
+    info: web::Json<RequestReport>,
+    credentials: BearerAuth,
+) -> Result<web::Json<ResponseReport>, ServiceError> {
+    let claims = decode_jwt(credentials.token()).unwrap();
+    if !can_read_target(&claims.permissions, &info.selection.target) {
+        return Err(ServiceError::Forbidden);
+    }
+    let user = claims.username;
+    debug!(
+        "User '{}' Get Report for selection: {:?}",
+        &user, &info.selection
+    );
+    let report = report::ledger_report(&user, &info.selection)?;
+    Ok(web::Json(ResponseReport {
+        html: if info.html {
+            Some(report::render_html(&report))
+        } else {
+            None
+        },
+        history: report::ledger_report_history(&user, &info.selection, info.history)?,
+        report,
+    }))
 }
 
 /// get Html Suggestions
@@ -79,33 +233,36 @@ pub async fn get_html(
 pub async fn get_html_suggetstions(
     credentials: BearerAuth,
 ) -> Result<web::Json<ResponseHtmlSuggestion>, ServiceError> {
-    let user = decode_jwt(credentials.token()).unwrap().username;
-    debug!("User '{}' Get Html Suggestion.", &user);
+    let claims = decode_jwt(credentials.token()).unwrap();
+    debug!("User '{}' Get Html Suggestion.", &claims.username);
     Ok(web::Json(ResponseHtmlSuggestion {
-        suggestions: utils::html_suggestion(&user)?,
+        suggestions: report::html_suggestion(&claims.username)?
+            .into_iter()
+            .filter(|s| can_read_target(&claims.permissions, &s.target))
+            .collect(),
     }))
 }
 
 /// LEDGER TIME INTERACTION ///
 /// get Headline and Content BTreeMap from Ledger Music
-#[has_permissions("GET_LEDGER_INFO")]
+#[has_permissions("GET_TIME_INFO")]
 pub async fn get_time_suggetstions(
+    query: web::Query<RequestSuggestion>,
     credentials: BearerAuth,
 ) -> Result<web::Json<HeadlineSuggestion>, ServiceError> {
     let user = decode_jwt(credentials.token()).unwrap().username;
     debug!("User '{}' Get Ledger Time Suggestion.", &user);
     Ok(web::Json(HeadlineSuggestion {
-        suggestions: utils::ledger_time_suggestion(&user)?,
+        suggestions: suggestion::ledger_time_suggestion(&user, query.limit)?,
     }))
 }
 
-/// create new entery for time Tracking
-#[has_permissions("SET_LEDGER_INFO")]
-//TODO think of better return type
+/// create new entery for time Tracking. Returns the id of the running or created entery.
+#[has_permissions("SET_TIME_INFO")]
 pub async fn set_time_entery_start(
     new_time_entery: web::Json<StartTimeEntery>,
     credentials: BearerAuth,
-) -> Result<web::Json<ResponseStatus>, ServiceError> {
+) -> Result<web::Json<ResponseTimeEnteryStart>, ServiceError> {
     let user = decode_jwt(credentials.token()).unwrap().username;
     debug!(
         "User '{}' Set ledger time function is called with Headline: \t{:?}\t account_origin: \t{:?}\t account_origin: \t{:?}\t duration: \t{:?}\t offset: \t{:?}",
@@ -120,12 +277,12 @@ pub async fn set_time_entery_start(
             "Empty account_target provided.".to_string(),
         ));
     }
-    if new_time_entery.duration.is_none() {
+    let id = if new_time_entery.duration.is_none() {
         //start running entery, because it has not ended yet.
-        utils::ledger_start_time_entery(&user, new_time_entery.to_owned())?;
+        utils::ledger_start_time_entery(&user, new_time_entery.to_owned())?
     } else {
         //if duration is given, create the time entery.
-        utils::ledger_create_time_entery(
+        let text = utils::ledger_create_time_entery(
             &user,
             shared::models::NewTimeEntery {
                 headline: String::from(&new_time_entery.headline),
@@ -136,12 +293,19 @@ pub async fn set_time_entery_start(
                 offset: new_time_entery.offset,
             },
         )?;
+        journal::parse(&text)
+            .transaction_ids()
+            .pop()
+            .unwrap_or_default()
     };
-    Ok(web::Json(ResponseStatus { status: 0 }))
+    Ok(web::Json(ResponseTimeEnteryStart {
+        id,
+        diagnostics: check::check_written(&user, utils::PATH_TIME_SPEND),
+    }))
 }
 
 /// get all running time Enteries
-#[has_permissions("GET_LEDGER_INFO")]
+#[has_permissions("GET_TIME_INFO")]
 pub async fn get_time_entery_running(
     credentials: BearerAuth,
 ) -> Result<web::Json<ResponseRunningLedgerTimeEntery>, ServiceError> {
@@ -153,8 +317,9 @@ pub async fn get_time_entery_running(
 }
 
 /// stoping Time entery
-#[has_permissions("SET_LEDGER_INFO")]
+#[has_permissions("SET_TIME_INFO")]
 pub async fn set_time_entery_stop(
+    settings: web::Data<TimeTracking>,
     payload: web::Json<StopLedgerTimeEntery>,
     credentials: BearerAuth,
 ) -> Result<web::Json<ResponseStatus>, ServiceError> {
@@ -163,12 +328,15 @@ pub async fn set_time_entery_stop(
         "User '{}' Stop running Time Entery {:#?}",
         &user, payload.new_entery
     );
-    utils::ledger_stop_time_entery(&user, &payload)?;
-    return Ok(web::Json(ResponseStatus { status: 0 }));
+    utils::ledger_stop_time_entery(&user, &payload, settings.split_at_midnight)?;
+    return Ok(web::Json(ResponseStatus {
+        status: 0,
+        diagnostics: check::check_written(&user, utils::PATH_TIME_SPEND),
+    }));
 }
 
 /// kill Time entery
-#[has_permissions("SET_LEDGER_INFO")]
+#[has_permissions("SET_TIME_INFO")]
 pub async fn set_time_entery_kill(
     payload: web::Json<StopLedgerTimeEntery>,
     credentials: BearerAuth,
@@ -178,26 +346,89 @@ pub async fn set_time_entery_kill(
         "User '{}' Kill / Delete Time Entery {:#?}",
         &user, payload.new_entery
     );
-    utils::ledger_kill_time_entery(&user, payload.remove_line.to_owned())?;
-    return Ok(web::Json(ResponseStatus { status: 0 }));
+    utils::ledger_kill_time_entery(&user, &payload.id)?;
+    return Ok(web::Json(ResponseStatus {
+        status: 0,
+        diagnostics: check::check_written(&user, utils::PATH_TIME_SPEND),
+    }));
 }
 
-/// get history for ledger time entery
-#[has_permissions("GET_LEDGER_INFO")]
+/// update finished Time entery
+#[has_permissions("SET_TIME_INFO")]
+pub async fn set_time_entery_update(
+    payload: web::Json<TimeEnteryHistory>,
+    credentials: BearerAuth,
+) -> Result<web::Json<ResponseTimeEnteryUpdate>, ServiceError> {
+    let user = decode_jwt(credentials.token()).unwrap().username;
+    debug!("User '{}' Update Time Entery {:#?}", &user, payload);
+    let entery = utils::ledger_update_time_entery(&user, &payload)?;
+    Ok(web::Json(ResponseTimeEnteryUpdate {
+        entery,
+        diagnostics: check::check_written(&user, utils::PATH_TIME_SPEND),
+    }))
+}
+
+/// delete finished Time entery
+#[has_permissions("SET_TIME_INFO")]
+pub async fn set_time_entery_delete(
+    payload: web::Json<DeleteTimeEntery>,
+    credentials: BearerAuth,
+) -> Result<web::Json<ResponseStatus>, ServiceError> {
+    let user = decode_jwt(credentials.token()).unwrap().username;
+    debug!("User '{}' Delete Time Entery {}", &user, payload.id);
+    utils::ledger_delete_time_entery(&user, &payload.id)?;
+    Ok(web::Json(ResponseStatus {
+        status: 0,
+        diagnostics: check::check_written(&user, utils::PATH_TIME_SPEND),
+    }))
+}
+
+/// get history for ledger time entery, filtered and paged by the query
+#[has_permissions("GET_TIME_INFO")]
 pub async fn get_time_history(
+    query: web::Query<RequestTimeHistory>,
     credentials: BearerAuth,
 ) -> Result<web::Json<ResponseTimeEnteryHistory>, ServiceError> {
     let user = decode_jwt(credentials.token()).unwrap().username;
-    debug!("User '{}' Get Ledger Time History.", &user);
-    //TODO add filter for history elements. (date)
+    debug!("User '{}' Get Ledger Time History {:?}", &user, query);
+    let (history, total) = utils::filter_time_history(utils::ledger_time_history(&user)?, &query)?;
     Ok(web::Json(shared::models::ResponseTimeEnteryHistory {
-        history: utils::ledger_time_history(&user)?,
+        history,
+        total,
     }))
 }
 
+/// get overlapping enteries and untracked time of the time journal
+#[has_permissions("GET_TIME_INFO")]
+pub async fn get_time_analysis(
+    info: web::Json<RequestTimeAnalysis>,
+    credentials: BearerAuth,
+) -> Result<web::Json<ResponseTimeAnalysis>, ServiceError> {
+    let user = decode_jwt(credentials.token()).unwrap().username;
+    debug!(
+        "User '{}' Get Ledger Time Analysis from {} to {}.",
+        &user, &info.date_from, &info.date_to
+    );
+    Ok(web::Json(analysis::ledger_time_analysis(&user, &info)?))
+}
+
+/// get the minutes per account of the time journal, bucketed by day, week or month
+#[has_permissions("GET_TIME_INFO")]
+pub async fn get_time_statistics(
+    info: web::Json<RequestTimeStatistics>,
+    credentials: BearerAuth,
+) -> Result<web::Json<ResponseTimeStatistics>, ServiceError> {
+    let user = decode_jwt(credentials.token()).unwrap().username;
+    debug!(
+        "User '{}' Get Ledger Time Statistics per {} from {} to {}.",
+        &user, &info.bucket, &info.start, &info.end
+    );
+    Ok(web::Json(statistics::ledger_time_statistics(&user, &info)?))
+}
+
 /// --- LEDGER FINANCE INTERACTION --- ///
 /// create ledger finance entery
-#[has_permissions("SET_LEDGER_INFO")]
+#[has_permissions("SET_FINANCE_INFO")]
 pub async fn set_finance_entery_create(
     payload: web::Json<NewFinanceEntery>,
     credentials: BearerAuth,
@@ -208,18 +439,436 @@ pub async fn set_finance_entery_create(
         &user,
         payload.to_owned()
     );
+    let file = journals::finance_journal(&user, &payload.target_file)?.path;
     utils::ledger_create_finance_entery(&user, payload.to_owned())?;
-    return Ok(web::Json(ResponseStatus { status: 0 }));
+    return Ok(web::Json(ResponseStatus {
+        status: 0,
+        diagnostics: check::check_written(&user, &file),
+    }));
 }
 
 /// get suggestions for ledger finance entery
-#[has_permissions("GET_LEDGER_INFO")]
+#[has_permissions("GET_FINANCE_INFO")]
 pub async fn get_finance_suggestions(
+    query: web::Query<RequestSuggestion>,
     credentials: BearerAuth,
 ) -> Result<web::Json<FinanceEnterySuggestion>, ServiceError> {
     let user = decode_jwt(credentials.token()).unwrap().username;
     debug!("User '{}' Get Ledger Finance Suggestion.", &user);
     Ok(web::Json(FinanceEnterySuggestion {
-        suggestions: utils::ledger_finance_suggestion(&user)?,
+        suggestions: suggestion::ledger_finance_suggestion(&user, query.limit)?,
+    }))
+}
+
+/// --- BUDGETS --- ///
+/// get all budgets of the user
+#[has_permissions("GET_FINANCE_INFO")]
+pub async fn get_budgets(credentials: BearerAuth) -> Result<web::Json<BudgetList>, ServiceError> {
+    let user = decode_jwt(credentials.token()).unwrap().username;
+    debug!("User '{}' Get Budgets.", &user);
+    Ok(web::Json(BudgetList {
+        budgets: budget::user_budgets(&user)?,
+    }))
+}
+
+/// create or replace the budget of an account
+#[has_permissions("SET_FINANCE_INFO")]
+pub async fn set_budget(
+    payload: web::Json<Budget>,
+    credentials: BearerAuth,
+) -> Result<web::Json<BudgetList>, ServiceError> {
+    let user = decode_jwt(credentials.token()).unwrap().username;
+    debug!("User '{}' Set Budget {:#?}", &user, payload);
+    Ok(web::Json(BudgetList {
+        budgets: budget::set_budget(&user, payload.into_inner())?,
+    }))
+}
+
+/// delete the budget of an account
+#[has_permissions("SET_FINANCE_INFO")]
+pub async fn set_budget_delete(
+    payload: web::Json<DeleteBudget>,
+    credentials: BearerAuth,
+) -> Result<web::Json<BudgetList>, ServiceError> {
+    let user = decode_jwt(credentials.token()).unwrap().username;
+    debug!("User '{}' Delete Budget '{}'", &user, &payload.account);
+    Ok(web::Json(BudgetList {
+        budgets: budget::delete_budget(&user, &payload.account)?,
+    }))
+}
+
+/// compare the budgets with the finance journals
+#[has_permissions("GET_FINANCE_INFO")]
+pub async fn get_budget_report(
+    payload: web::Json<RequestBudgetReport>,
+    credentials: BearerAuth,
+) -> Result<web::Json<ResponseBudgetReport>, ServiceError> {
+    let user = decode_jwt(credentials.token()).unwrap().username;
+    debug!("User '{}' Get Budget Report {:?}", &user, payload.periods);
+    Ok(web::Json(ResponseBudgetReport {
+        budgets: budget::ledger_budget_report(&user, &payload)?,
+    }))
+}
+
+/// --- RECURRING --- ///
+/// get all recurring transactions of the user
+#[has_permissions("GET_FINANCE_INFO")]
+pub async fn get_recurring(
+    credentials: BearerAuth,
+) -> Result<web::Json<RecurringList>, ServiceError> {
+    let user = decode_jwt(credentials.token()).unwrap().username;
+    debug!("User '{}' Get Recurring Transactions.", &user);
+    Ok(web::Json(RecurringList {
+        recurring: recurring::user_recurring(&user)?,
+    }))
+}
+
+/// create a recurring transaction and write its occurrences up to today
+#[has_permissions("SET_FINANCE_INFO")]
+pub async fn set_recurring_create(
+    payload: web::Json<RecurringTransaction>,
+    credentials: BearerAuth,
+) -> Result<web::Json<RecurringList>, ServiceError> {
+    let user = decode_jwt(credentials.token()).unwrap().username;
+    debug!(
+        "User '{}' Create Recurring Transaction {:#?}",
+        &user, payload
+    );
+    let created = recurring::create_recurring(&user, payload.into_inner())?;
+    recurring::materialize(&user, &created, chrono::Local::now().date_naive())?;
+    Ok(web::Json(RecurringList {
+        recurring: recurring::user_recurring(&user)?,
+    }))
+}
+
+/// delete a recurring transaction, its written transactions are kept
+#[has_permissions("SET_FINANCE_INFO")]
+pub async fn set_recurring_delete(
+    payload: web::Json<DeleteRecurring>,
+    credentials: BearerAuth,
+) -> Result<web::Json<RecurringList>, ServiceError> {
+    let user = decode_jwt(credentials.token()).unwrap().username;
+    debug!(
+        "User '{}' Delete Recurring Transaction '{}'",
+        &user, &payload.id
+    );
+    Ok(web::Json(RecurringList {
+        recurring: recurring::delete_recurring(&user, &payload.id)?,
+    }))
+}
+
+/// occurrences of the recurring transactions in the next days
+#[has_permissions("GET_FINANCE_INFO")]
+pub async fn get_recurring_upcoming(
+    payload: web::Json<RequestUpcoming>,
+    credentials: BearerAuth,
+) -> Result<web::Json<ResponseUpcoming>, ServiceError> {
+    let user = decode_jwt(credentials.token()).unwrap().username;
+    debug!("User '{}' Get Upcoming Recurring {:?}", &user, payload.days);
+    Ok(web::Json(ResponseUpcoming {
+        occurrences: recurring::ledger_upcoming(&user, payload.days)?,
+    }))
+}
+
+/// --- EXPORT --- ///
+/// response with the CSV file as attachment
+fn csv_response(content: String, name: &str) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/csv; charset=utf-8")
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}.csv\"", name),
+        ))
+        .body(content)
+}
+
+/// export the time enteries as CSV or JSON
+#[has_permissions("GET_TIME_INFO")]
+pub async fn get_export_time(
+    payload: web::Json<RequestExport>,
+    credentials: BearerAuth,
+) -> Result<HttpResponse, ServiceError> {
+    let user = decode_jwt(credentials.token()).unwrap().username;
+    debug!("User '{}' Export Time {:?}", &user, payload);
+    let enteries = export::time_export(&user, &payload)?;
+    if payload.format == "csv" {
+        return Ok(csv_response(export::time_csv(&enteries)?, "time"));
+    }
+    Ok(HttpResponse::Ok().json(ResponseTimeEnteryHistory {
+        total: enteries.len(),
+        history: enteries,
+    }))
+}
+
+/// export the finance transactions as CSV or JSON
+#[has_permissions("GET_FINANCE_INFO")]
+pub async fn get_export_finance(
+    payload: web::Json<RequestExport>,
+    credentials: BearerAuth,
+) -> Result<HttpResponse, ServiceError> {
+    let user = decode_jwt(credentials.token()).unwrap().username;
+    debug!("User '{}' Export Finance {:?}", &user, payload);
+    let transactions = export::finance_export(&user, &payload)?;
+    if payload.format == "csv" {
+        return Ok(csv_response(export::finance_csv(&transactions)?, "finance"));
+    }
+    Ok(HttpResponse::Ok().json(ResponseFinanceExport { transactions }))
+}
+
+/// --- IMPORT --- ///
+/// get the bank profiles and import rules of the user
+#[has_permissions("GET_FINANCE_INFO")]
+pub async fn get_import_settings(
+    credentials: BearerAuth,
+) -> Result<web::Json<ImportSettings>, ServiceError> {
+    let user = decode_jwt(credentials.token()).unwrap().username;
+    debug!("User '{}' Get Import Settings.", &user);
+    Ok(web::Json(import::user_settings(&user)?))
+}
+
+/// replace the bank profiles and import rules of the user
+#[has_permissions("SET_FINANCE_INFO")]
+pub async fn set_import_settings(
+    payload: web::Json<ImportSettings>,
+    credentials: BearerAuth,
+) -> Result<web::Json<ImportSettings>, ServiceError> {
+    let user = decode_jwt(credentials.token()).unwrap().username;
+    debug!("User '{}' Set Import Settings {:#?}", &user, payload);
+    Ok(web::Json(import::set_settings(
+        &user,
+        payload.into_inner(),
+    )?))
+}
+
+/// propose the enteries of a bank statement without writing them
+#[has_permissions("GET_FINANCE_INFO")]
+pub async fn get_import_preview(
+    payload: web::Json<RequestImportPreview>,
+    credentials: BearerAuth,
+) -> Result<web::Json<ResponseImportPreview>, ServiceError> {
+    let user = decode_jwt(credentials.token()).unwrap().username;
+    debug!(
+        "User '{}' Get Import Preview with profile '{}'",
+        &user, &payload.profile
+    );
+    Ok(web::Json(ResponseImportPreview {
+        enteries: import::ledger_import_preview(
+            &user,
+            &payload.profile,
+            &payload.target_file,
+            &payload.content,
+        )?,
+    }))
+}
+
+/// write the accepted enteries of the preview
+#[has_permissions("SET_FINANCE_INFO")]
+pub async fn set_import(
+    payload: web::Json<RequestImport>,
+    credentials: BearerAuth,
+) -> Result<web::Json<ResponseStatus>, ServiceError> {
+    let user = decode_jwt(credentials.token()).unwrap().username;
+    debug!(
+        "User '{}' Import {} Enteries",
+        &user,
+        payload.enteries.len()
+    );
+    Ok(web::Json(ResponseStatus {
+        status: 0,
+        diagnostics: import::ledger_import(&user, &payload.enteries)?,
+    }))
+}
+
+/// --- CHECK --- ///
+/// validate all journals of the user
+#[has_permissions("GET_LEDGER_INFO")]
+pub async fn get_check(credentials: BearerAuth) -> Result<web::Json<ResponseCheck>, ServiceError> {
+    let claims = decode_jwt(credentials.token()).unwrap();
+    let user = claims.username;
+    debug!("User '{}' Check Journals.", &user);
+    Ok(web::Json(ResponseCheck {
+        diagnostics: check::ledger_check(&user, &readable_kinds(&claims.permissions))?,
+    }))
+}
+
+/// --- ACCOUNTS --- ///
+/// get the tree of all accounts in the time and finance journals
+#[has_permissions("GET_LEDGER_INFO")]
+pub async fn get_account_tree(
+    credentials: BearerAuth,
+) -> Result<web::Json<AccountTree>, ServiceError> {
+    let claims = decode_jwt(credentials.token()).unwrap();
+    let user = claims.username;
+    debug!("User '{}' Get Account Tree.", &user);
+    Ok(web::Json(AccountTree {
+        accounts: accounts::ledger_account_tree(&user, &readable_kinds(&claims.permissions))?,
+    }))
+}
+
+/// --- JOURNALS --- ///
+/// get all journals of the user
+#[has_permissions("GET_LEDGER_INFO")]
+pub async fn get_journals(credentials: BearerAuth) -> Result<web::Json<JournalList>, ServiceError> {
+    let claims = decode_jwt(credentials.token()).unwrap();
+    let user = claims.username;
+    debug!("User '{}' Get Journals.", &user);
+    let kinds = readable_kinds(&claims.permissions);
+    Ok(web::Json(JournalList {
+        journals: journals::user_journals(&user)?
+            .into_iter()
+            .filter(|j| kinds.contains(&j.kind))
+            .collect(),
+    }))
+}
+
+/// create a new journal for the user
+#[has_permissions("SET_LEDGER_INFO")]
+pub async fn set_journal_create(
+    payload: web::Json<JournalDefinition>,
+    credentials: BearerAuth,
+) -> Result<web::Json<JournalDefinition>, ServiceError> {
+    let claims = decode_jwt(credentials.token()).unwrap();
+    if !can_write_kind(&claims.permissions, payload.kind) {
+        return Err(ServiceError::Forbidden);
+    }
+    let user = claims.username;
+    debug!("User '{}' Create Journal {:#?}", &user, payload);
+    Ok(web::Json(journals::create_journal(
+        &user,
+        payload.into_inner(),
+    )?))
+}
+
+/// archive a journal of the user
+#[has_permissions("SET_LEDGER_INFO")]
+pub async fn set_journal_archive(
+    payload: web::Json<ArchiveJournal>,
+    credentials: BearerAuth,
+) -> Result<web::Json<ResponseStatus>, ServiceError> {
+    let claims = decode_jwt(credentials.token()).unwrap();
+    let user = claims.username;
+    debug!("User '{}' Archive Journal '{}'", &user, &payload.name);
+    // unknown journals are left to `archive_journal`, that answers with not found
+    let kind = journals::user_journals(&user)?
+        .into_iter()
+        .find(|j| j.name == payload.name && !j.archived)
+        .map(|j| j.kind);
+    if kind.is_some_and(|kind| !can_write_kind(&claims.permissions, kind)) {
+        return Err(ServiceError::Forbidden);
+    }
+    journals::archive_journal(&user, &payload.name)?;
+    Ok(web::Json(ResponseStatus::default()))
+}
+
+/// all users with their roles
+#[has_permissions("ADMIN_ROLE")]
+pub async fn get_users(
+    pool: web::Data<Pool>,
+    credentials: BearerAuth,
+) -> Result<web::Json<UserList>, ServiceError> {
+    let user = decode_jwt(credentials.token()).unwrap().username;
+    debug!("User '{}' Get Users.", &user);
+    let mut connection = pool.get()?;
+    Ok(web::Json(UserList {
+        users: users::list_users(&mut connection)?,
+    }))
+}
+
+/// creates a user with the given roles
+#[has_permissions("ADMIN_ROLE")]
+pub async fn set_user_create(
+    pool: web::Data<Pool>,
+    passwords: web::Data<Passwords>,
+    payload: web::Json<UserCreate>,
+    credentials: BearerAuth,
+) -> Result<web::Json<UserInfo>, ServiceError> {
+    let user = decode_jwt(credentials.token()).unwrap().username;
+    debug!("User '{}' Create User '{}'.", &user, &payload.username);
+    let mut connection = pool.get()?;
+    let created = connection.transaction::<_, ServiceError, _>(|conn| {
+        let created = insert_user(conn, &payload.username, &payload.password, &passwords)?;
+        if !payload.roles.is_empty() {
+            roles::set_roles(conn, &created.username, &payload.roles)?;
+        }
+        Ok(created)
+    })?;
+    let mut roles = payload.roles.clone();
+    if roles.is_empty() {
+        roles.push(roles::DEFAULT_ROLE.to_string());
+    }
+    Ok(web::Json(UserInfo {
+        username: created.username,
+        disabled: created.disabled,
+        roles,
+    }))
+}
+
+/// The admins can not lock out themselves.
+fn check_not_self(user: &str, target: &str) -> Result<(), ServiceError> {
+    match user == target {
+        true => Err(ServiceError::BadRequest(
+            "Admins can not disable or delete themselves.".to_string(),
+        )),
+        false => Ok(()),
+    }
+}
+
+#[has_permissions("ADMIN_ROLE")]
+pub async fn set_user_disable(
+    pool: web::Data<Pool>,
+    payload: web::Json<UserDisable>,
+    credentials: BearerAuth,
+) -> Result<web::Json<ResponseStatus>, ServiceError> {
+    let user = decode_jwt(credentials.token()).unwrap().username;
+    debug!(
+        "User '{}' Set User '{}' disabled: {}.",
+        &user, &payload.username, payload.disabled
+    );
+    check_not_self(&user, &payload.username)?;
+    users::set_disabled(&mut *pool.get()?, &payload.username, payload.disabled)?;
+    Ok(web::Json(ResponseStatus {
+        status: 0,
+        diagnostics: Vec::new(),
+    }))
+}
+
+#[has_permissions("ADMIN_ROLE")]
+pub async fn set_user_delete(
+    pool: web::Data<Pool>,
+    payload: web::Json<UserDelete>,
+    credentials: BearerAuth,
+) -> Result<web::Json<ResponseStatus>, ServiceError> {
+    let user = decode_jwt(credentials.token()).unwrap().username;
+    debug!("User '{}' Delete User '{}'.", &user, &payload.username);
+    check_not_self(&user, &payload.username)?;
+    users::delete_user(&mut *pool.get()?, &payload.username)?;
+    Ok(web::Json(ResponseStatus {
+        status: 0,
+        diagnostics: Vec::new(),
+    }))
+}
+
+/// sets a new password, the user has to login again
+#[has_permissions("ADMIN_ROLE")]
+pub async fn set_user_reset(
+    pool: web::Data<Pool>,
+    passwords: web::Data<Passwords>,
+    payload: web::Json<UserPasswordReset>,
+    credentials: BearerAuth,
+) -> Result<web::Json<ResponseStatus>, ServiceError> {
+    let user = decode_jwt(credentials.token()).unwrap().username;
+    debug!(
+        "User '{}' Reset password of '{}'.",
+        &user, &payload.username
+    );
+    users::reset_password(
+        &mut *pool.get()?,
+        &payload.username,
+        &payload.password,
+        &passwords,
+    )?;
+    Ok(web::Json(ResponseStatus {
+        status: 0,
+        diagnostics: Vec::new(),
     }))
 }
@@ -0,0 +1,412 @@
+//! Import of bank statements. The CSV export of a bank is read with a bank profile and every
+//! line becomes a finance entery. The target account comes from the import rules of the user
+//! or is learned from the finance suggestions.
+//! Profiles and rules are stored in `./files/<user>/import.json`.
+use chrono::NaiveDate;
+use log::debug;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::check;
+use crate::errors::ServiceError;
+use crate::journal::{self, Journal};
+use crate::journals;
+use crate::utils::{self, user_dir, user_file};
+use shared::amount::Amount;
+use shared::models::{
+    BankProfile, Diagnostic, ImportPreviewEntery, ImportRule, ImportSettings, NewFinanceEntery,
+};
+
+pub const IMPORT_FILE: &str = "import.json";
+
+/// all bank profiles and rules of the user
+pub fn user_settings(user: &str) -> Result<ImportSettings, ServiceError> {
+    let path = user_file(user, IMPORT_FILE);
+    if !Path::new(&path).exists() {
+        return Ok(ImportSettings::default());
+    }
+    let content = fs::read_to_string(path)?;
+    if content.trim().is_empty() {
+        return Ok(ImportSettings::default());
+    }
+    Ok(serde_json::from_str(&content)?)
+}
+
+fn check_settings(settings: &ImportSettings) -> Result<(), ServiceError> {
+    let invalid = |message: String| Err(ServiceError::BadRequest(message));
+    for (index, profile) in settings.profiles.iter().enumerate() {
+        if profile.name.trim().is_empty() {
+            return invalid("A bank profile needs a name.".to_string());
+        }
+        if settings.profiles[..index]
+            .iter()
+            .any(|p| p.name == profile.name)
+        {
+            return invalid(format!("The bank profile '{}' exists twice.", profile.name));
+        }
+        if profile.account.trim().is_empty() {
+            return invalid(format!(
+                "The bank profile '{}' needs an account.",
+                profile.name
+            ));
+        }
+        if !profile.delimiter.is_ascii() {
+            return invalid(format!(
+                "Invalid delimiter '{}', it has to be an ascii character.",
+                profile.delimiter
+            ));
+        }
+    }
+    for rule in &settings.rules {
+        if let Err(e) = Regex::new(&rule.pattern) {
+            return invalid(format!("Invalid pattern '{}': {}", rule.pattern, e));
+        }
+        if rule.account.trim().is_empty() {
+            return invalid(format!("The rule '{}' needs an account.", rule.pattern));
+        }
+    }
+    Ok(())
+}
+
+/// Checks and saves the bank profiles and rules. They replace the existing ones.
+pub fn set_settings(user: &str, settings: ImportSettings) -> Result<ImportSettings, ServiceError> {
+    check_settings(&settings)?;
+    fs::create_dir_all(user_dir(user))?;
+    let path = user_file(user, IMPORT_FILE);
+    journal::create_file(&path)?;
+    journal::modify_file(&path, |_| {
+        Ok((serde_json::to_string_pretty(&settings)?, settings.clone()))
+    })
+}
+
+/// One rule for every headline of the suggestions, that was booked from the bank account.
+/// Later suggestions come first, so the latest account of a headline is used.
+pub fn learned_rules(suggestions: &[NewFinanceEntery], bank_account: &str) -> Vec<ImportRule> {
+    let mut rules: Vec<ImportRule> = Vec::new();
+    for suggestion in suggestions.iter().rev() {
+        let account = if suggestion.account_origin == bank_account {
+            &suggestion.account_target
+        } else if suggestion.account_target == bank_account {
+            &suggestion.account_origin
+        } else {
+            continue;
+        };
+        if suggestion.headline.trim().is_empty()
+            || rules
+                .iter()
+                .any(|r| r.headline.as_ref() == Some(&suggestion.headline))
+        {
+            continue;
+        }
+        rules.push(ImportRule {
+            pattern: format!("(?i){}", regex::escape(suggestion.headline.trim())),
+            account: account.clone(),
+            headline: Some(suggestion.headline.clone()),
+        });
+    }
+    rules
+}
+
+/// a line of the bank statement
+#[derive(Debug, PartialEq)]
+pub struct BankLine {
+    pub line: usize,
+    pub date: NaiveDate,
+    pub payee: String,
+    pub description: String,
+    pub amount: Amount,
+}
+
+fn column(headers: &csv::StringRecord, name: &str) -> Result<usize, ServiceError> {
+    headers
+        .iter()
+        .position(|h| h.trim() == name)
+        .ok_or_else(|| ServiceError::BadRequest(format!("Missing column '{}'.", name)))
+}
+
+fn parse_amount(value: &str, profile: &BankProfile) -> Option<Amount> {
+    let value = if profile.decimal_comma {
+        value.replace('.', "").replace(',', ".")
+    } else {
+        value.replace(',', "")
+    };
+    // the notation of the bank is known, a point is the decimal mark now
+    let mut amount = Amount::parse_decimal_point(&value)?;
+    if amount.commodity.is_empty() {
+        amount.commodity = profile.commodity.clone();
+    }
+    Some(amount)
+}
+
+/// Reads the CSV export of the bank. Line numbers count from the start of the file.
+pub fn parse_statement(
+    content: &str,
+    profile: &BankProfile,
+) -> Result<Vec<BankLine>, ServiceError> {
+    let content = content.trim_start_matches('\u{feff}');
+    let data = content
+        .splitn(profile.skip_rows + 1, '\n')
+        .nth(profile.skip_rows)
+        .unwrap_or_default();
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(profile.delimiter as u8)
+        .flexible(true)
+        .from_reader(data.as_bytes());
+    let invalid = |line: usize, message: String| {
+        ServiceError::BadRequest(format!("Line {}: {}", line + profile.skip_rows, message))
+    };
+    let headers = reader
+        .headers()
+        .map_err(|e| invalid(1, e.to_string()))?
+        .clone();
+    let date_column = column(&headers, &profile.date_column)?;
+    let payee_column = column(&headers, &profile.payee_column)?;
+    let ammount_column = column(&headers, &profile.ammount_column)?;
+    let description_column = match &profile.description_column {
+        Some(name) => Some(column(&headers, name)?),
+        None => None,
+    };
+
+    let mut lines = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| {
+            let line = e.position().map(|p| p.line() as usize).unwrap_or_default();
+            invalid(line, e.to_string())
+        })?;
+        let line = record
+            .position()
+            .map(|p| p.line() as usize)
+            .unwrap_or_default();
+        if record.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+        let field = |index: usize| record.get(index).unwrap_or_default().trim();
+        let date = NaiveDate::parse_from_str(field(date_column), &profile.date_format)
+            .map_err(|_| invalid(line, format!("Invalid date '{}'.", field(date_column))))?;
+        let amount = parse_amount(field(ammount_column), profile).ok_or_else(|| {
+            invalid(
+                line,
+                format!("Invalid ammount '{}'.", field(ammount_column)),
+            )
+        })?;
+        lines.push(BankLine {
+            line: line + profile.skip_rows,
+            date,
+            payee: field(payee_column).to_string(),
+            description: description_column
+                .map(field)
+                .unwrap_or_default()
+                .to_string(),
+            amount,
+        });
+    }
+    Ok(lines)
+}
+
+/// the journal has a transaction on the date, that books the ammount to the bank account
+fn is_duplicate(journal: &Journal, bank_account: &str, line: &BankLine) -> bool {
+    journal
+        .transactions()
+        .filter(|t| t.date == line.date)
+        .any(|t| {
+            t.postings
+                .iter()
+                .zip(t.values())
+                .any(|(p, value)| p.account == bank_account && value == line.amount.quantity)
+        })
+}
+
+/// Proposes an entery for every line. The bank account is the origin, the first matching
+/// rule gives the target account. Rules are matched against payee and description.
+pub fn preview(
+    lines: &[BankLine],
+    profile: &BankProfile,
+    rules: &[ImportRule],
+    journal: &Journal,
+    target_file: &str,
+) -> Result<Vec<ImportPreviewEntery>, ServiceError> {
+    let patterns = rules
+        .iter()
+        .map(|r| Regex::new(&r.pattern))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| ServiceError::BadRequest(format!("Invalid pattern: {}", e)))?;
+    Ok(lines
+        .iter()
+        .map(|line| {
+            let text = format!("{} {}", line.payee, line.description);
+            let rule = patterns
+                .iter()
+                .position(|p| p.is_match(&text))
+                .map(|index| &rules[index]);
+            ImportPreviewEntery {
+                line: line.line,
+                payee: line.payee.clone(),
+                description: line.description.clone(),
+                entery: NewFinanceEntery {
+                    headline: rule
+                        .and_then(|r| r.headline.clone())
+                        .unwrap_or_else(|| line.payee.clone()),
+                    account_origin: profile.account.clone(),
+                    account_target: rule.map(|r| r.account.clone()).unwrap_or_default(),
+                    // the bank account gets the ammount of the statement
+                    ammount: Amount::new(-line.amount.quantity, &line.amount.commodity),
+                    date: Some(line.date.format("%Y/%m/%d").to_string()),
+                    target_file: target_file.to_string(),
+                    postings: Vec::new(),
+                },
+                rule: rule.map(|r| r.pattern.clone()),
+                duplicate: is_duplicate(journal, &profile.account, line),
+            }
+        })
+        .collect())
+}
+
+/// reads the bank statement with the profile of the user and proposes its enteries
+pub fn ledger_import_preview(
+    user: &str,
+    profile: &str,
+    target_file: &str,
+    content: &str,
+) -> Result<Vec<ImportPreviewEntery>, ServiceError> {
+    let settings = user_settings(user)?;
+    let mut profile = settings
+        .profiles
+        .into_iter()
+        .find(|p| p.name == profile)
+        .ok_or_else(|| ServiceError::NotFound(format!("No bank profile '{}'.", profile)))?;
+    let definition = journals::finance_journal(user, target_file)?;
+    if profile.commodity.is_empty() {
+        profile.commodity = definition.commodity.clone();
+    }
+    let path = user_file(user, &definition.path);
+    let journal = if Path::new(&path).exists() {
+        journal::read_file(&path)?
+    } else {
+        journal::parse("")
+    };
+    let lines = parse_statement(content, &profile)?;
+    let mut rules = settings.rules;
+    rules.extend(learned_rules(
+        &utils::ledger_finance_suggestion(user)?,
+        &profile.account,
+    ));
+    let enteries = preview(&lines, &profile, &rules, &journal, &definition.name)?;
+    debug!(
+        "Import preview for '{}' with {} enteries",
+        user,
+        enteries.len()
+    );
+    Ok(enteries)
+}
+
+/// Writes the enteries. Nothing is written, if one of them is invalid.
+pub fn ledger_import(
+    user: &str,
+    enteries: &[NewFinanceEntery],
+) -> Result<Vec<Diagnostic>, ServiceError> {
+    let mut texts: BTreeMap<String, String> = BTreeMap::new();
+    for entery in enteries {
+        if entery.account_target.trim().is_empty() || entery.account_origin.trim().is_empty() {
+            return Err(ServiceError::BadRequest(format!(
+                "The entery '{}' needs an origin and a target account.",
+                entery.headline
+            )));
+        }
+        let (definition, text) = utils::finance_entery_text(user, entery, &[])?;
+        *texts.entry(definition.path).or_default() += &text;
+    }
+    let mut diagnostics = Vec::new();
+    for (file, text) in texts {
+        journal::append_file(&user_file(user, &file), &text)?;
+        diagnostics.extend(check::check_written(user, &file));
+    }
+    debug!("User '{}' imported {} enteries", user, enteries.len());
+    Ok(diagnostics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATEMENT: &str = "Kontoauszug Girokonto
+Zeitraum: 01.10.2022 - 31.10.2022
+
+Buchungstag;Empfaenger;Verwendungszweck;Betrag
+01.10.2022;Vermieter GmbH;Miete Oktober;-450,00
+04.10.2022;REWE Markt;\"Einkauf; Karte\";-1.012,50
+05.10.2022;Arbeitgeber AG;Gehalt;2.000,00
+";
+
+    fn profile() -> BankProfile {
+        BankProfile {
+            name: "Sparkasse".to_string(),
+            account: "Girokonto:N2".to_string(),
+            delimiter: ';',
+            skip_rows: 3,
+            date_column: "Buchungstag".to_string(),
+            date_format: "%d.%m.%Y".to_string(),
+            payee_column: "Empfaenger".to_string(),
+            description_column: Some("Verwendungszweck".to_string()),
+            ammount_column: "Betrag".to_string(),
+            decimal_comma: true,
+            commodity: "€".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_statement() {
+        let lines = parse_statement(STATEMENT, &profile()).unwrap();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].line, 5);
+        assert_eq!(lines[1].description, "Einkauf; Karte");
+        assert_eq!(lines[1].amount, Amount::parse("-1012.50€").unwrap());
+        let invalid = BankProfile {
+            ammount_column: "Umsatz".to_string(),
+            ..profile()
+        };
+        assert!(matches!(
+            parse_statement(STATEMENT, &invalid),
+            Err(ServiceError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_preview() {
+        let journal = journal::parse(
+            "2022/10/01 Miete
+ \tGirokonto:N2\t\t-450€
+ \tAusgaben:Miete
+",
+        );
+        let suggestions = [NewFinanceEntery {
+            headline: "Rewe".to_string(),
+            account_origin: "Girokonto:N2".to_string(),
+            account_target: "Ausgaben:Essen".to_string(),
+            ..NewFinanceEntery::default()
+        }];
+        let mut rules = vec![ImportRule {
+            pattern: "Gehalt".to_string(),
+            account: "Einnahmen:Gehalt".to_string(),
+            headline: None,
+        }];
+        rules.extend(learned_rules(&suggestions, "Girokonto:N2"));
+        let lines = parse_statement(STATEMENT, &profile()).unwrap();
+        let enteries = preview(&lines, &profile(), &rules, &journal, "Gesamt").unwrap();
+
+        assert!(enteries[0].duplicate);
+        assert_eq!(enteries[0].rule, None);
+        assert_eq!(enteries[0].entery.account_target, "");
+        assert!(!enteries[1].duplicate);
+        assert_eq!(enteries[1].entery.headline, "Rewe");
+        assert_eq!(enteries[1].entery.account_target, "Ausgaben:Essen");
+        assert_eq!(
+            enteries[1].entery.ammount,
+            Amount::parse("1012.50€").unwrap()
+        );
+        assert_eq!(enteries[2].entery.headline, "Arbeitgeber AG");
+        assert_eq!(enteries[2].entery.account_target, "Einnahmen:Gehalt");
+        assert_eq!(enteries[2].entery.date, Some("2022/10/05".to_string()));
+    }
+}
@@ -0,0 +1,344 @@
+//! Typed representation of the ledger journals (time, finance and music).
+//! Every function that reads a `.dat` file goes through `parse` so there is only
+//! one place, that knows how a journal looks like.
+use chrono::NaiveDate;
+use shared::amount::{Amount, Decimal};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use uuid::Uuid;
+
+use crate::errors::ServiceError;
+
+mod parser;
+mod writer;
+
+pub use parser::parse;
+pub use writer::{append_file, create_file, modify_file};
+
+/// Lines of an element in the source file. Both numbers are 1-based and inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    /// comment without the leading comment char
+    pub text: String,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Posting {
+    pub account: String,
+    /// amount like `30m` or `-12.50€`. None, if the amount is elided.
+    pub amount: Option<Amount>,
+    /// absolute total cost of the amount, if it has a price (`3 ETF @ 20€`). The sign is the
+    /// one of the amount.
+    pub cost: Option<Amount>,
+    pub comment: Option<String>,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transaction {
+    pub date: NaiveDate,
+    pub headline: String,
+    pub postings: Vec<Posting>,
+    /// notes after the headline or on own lines inside the transaction
+    pub comments: Vec<Comment>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Entry {
+    Transaction(Transaction),
+    Comment(Comment),
+    /// top level lines ledger understands, but we do not use (`account`, `commodity`, ...)
+    Directive(Comment),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Journal {
+    pub lines: Vec<String>,
+    pub entries: Vec<Entry>,
+    pub errors: Vec<ParseError>,
+}
+
+impl Journal {
+    pub fn transactions(&self) -> impl Iterator<Item = &Transaction> {
+        self.entries.iter().filter_map(|e| match e {
+            Entry::Transaction(t) => Some(t),
+            _ => None,
+        })
+    }
+
+    pub fn comments(&self) -> impl Iterator<Item = &Comment> {
+        self.entries.iter().filter_map(|e| match e {
+            Entry::Comment(c) => Some(c),
+            _ => None,
+        })
+    }
+
+    /// returns the line as it stands in the file
+    pub fn line(&self, line: usize) -> &str {
+        &self.lines[line - 1]
+    }
+
+    /// returns the lines of the span as they stand in the file
+    pub fn raw(&self, span: &Span) -> String {
+        self.lines[span.start - 1..span.end].join("\n")
+    }
+
+    /// Ids of all transactions in the order of `transactions()`. Transactions without an
+    /// `id` tag, like the ones written by hand, get an id from their content.
+    pub fn transaction_ids(&self) -> Vec<String> {
+        let mut ids = IdGenerator::default();
+        self.transactions()
+            .map(|t| match t.tag(ID_TAG) {
+                Some(id) => id.to_string(),
+                None => ids.next(&self.raw(&t.span)),
+            })
+            .collect()
+    }
+
+    /// Returns the content of the journal without the lines of the span.
+    /// A blank line in front of the span is removed as well, if the span was followed by a blank line.
+    pub fn remove(&self, span: &Span) -> String {
+        let mut start = span.start - 1;
+        let blank = |line: Option<&String>| line.map(|l| l.trim().is_empty()).unwrap_or(true);
+        if start > 0 && blank(self.lines.get(start - 1)) && blank(self.lines.get(span.end)) {
+            start -= 1;
+        }
+        let mut lines = self.lines[..start].to_vec();
+        lines.extend_from_slice(&self.lines[span.end..]);
+        let mut content = lines.join("\n");
+        if !content.is_empty() {
+            content.push('\n');
+        }
+        content
+    }
+
+    /// Returns the content of the journal with the lines of the span replaced by the text.
+    pub fn replace(&self, span: &Span, text: &str) -> String {
+        let mut lines = self.lines[..span.start - 1].to_vec();
+        lines.extend(text.lines().map(|l| l.to_string()));
+        lines.extend_from_slice(&self.lines[span.end..]);
+        lines.join("\n") + "\n"
+    }
+
+    /// top level comments directly above the transaction (no blank line between)
+    pub fn leading_comments(&self, transaction: &Transaction) -> Vec<&Comment> {
+        let index = self
+            .entries
+            .partition_point(|e| e.line() < transaction.span.start);
+        let mut line = transaction.span.start;
+        let mut leading = Vec::new();
+        for entry in self.entries[..index].iter().rev() {
+            match entry {
+                Entry::Comment(c) if c.line + 1 == line => {
+                    line = c.line;
+                    leading.insert(0, c);
+                }
+                _ => break,
+            }
+        }
+        leading
+    }
+}
+
+impl Entry {
+    /// first line of the entry
+    pub fn line(&self) -> usize {
+        match self {
+            Entry::Transaction(t) => t.span.start,
+            Entry::Comment(c) | Entry::Directive(c) => c.line,
+        }
+    }
+}
+
+impl Transaction {
+    /// value of a `; key: value` tag in the comments of the transaction
+    pub fn tag(&self, key: &str) -> Option<&str> {
+        self.comments.iter().find_map(|c| {
+            let (k, v) = c.text.split_once(':')?;
+            if k.trim() == key {
+                Some(v.trim())
+            } else {
+                None
+            }
+        })
+    }
+
+    /// value of every posting. A single posting without amount gets the balancing value.
+    pub fn values(&self) -> Vec<Decimal> {
+        let values = self.postings.iter().map(|p| p.value()).collect::<Vec<_>>();
+        let elided = values.iter().filter(|v| v.is_none()).count();
+        let rest = -values.iter().flatten().sum::<Decimal>();
+        values
+            .into_iter()
+            .map(|v| v.unwrap_or(if elided == 1 { rest } else { Decimal::ZERO }))
+            .collect()
+    }
+
+    /// Amounts of every posting, grouped by commodity. Hours are counted in minutes and a
+    /// single posting without amount gets the balancing amount of every commodity.
+    pub fn amounts(&self) -> Vec<Vec<Amount>> {
+        let weights = self
+            .postings
+            .iter()
+            .map(|p| {
+                p.weight().map(|w| match w.commodity.as_str() {
+                    "h" => Amount::new(w.quantity * Decimal::from(60), "m"),
+                    _ => w,
+                })
+            })
+            .collect::<Vec<_>>();
+        let elided = weights.iter().filter(|w| w.is_none()).count();
+        let mut rest: BTreeMap<&str, Decimal> = BTreeMap::new();
+        for weight in weights.iter().flatten() {
+            *rest.entry(&weight.commodity).or_default() -= weight.quantity;
+        }
+        let rest = rest
+            .into_iter()
+            .filter(|(_, quantity)| !quantity.is_zero())
+            .map(|(commodity, quantity)| Amount::new(quantity, commodity))
+            .collect::<Vec<_>>();
+        weights
+            .iter()
+            .map(|w| match w {
+                Some(w) => vec![w.clone()],
+                None if elided == 1 => rest.clone(),
+                None => Vec::new(),
+            })
+            .collect()
+    }
+}
+
+impl Posting {
+    /// duration of a time posting in minutes. `90m` and `1.5h` are supported.
+    pub fn minutes(&self) -> Option<u32> {
+        let amount = self.amount.as_ref()?;
+        let minutes = match amount.commodity.as_str() {
+            "m" => amount.quantity,
+            "h" => amount.quantity * Decimal::from(60),
+            _ => return None,
+        };
+        minutes.round().try_into().ok()
+    }
+
+    /// the amount, that the posting adds to the balance. The cost with the sign of the amount
+    /// if the amount has a price.
+    pub fn weight(&self) -> Option<Amount> {
+        match (&self.cost, &self.amount) {
+            (Some(cost), Some(amount)) => Some(cost.with_sign_of(amount)),
+            (cost, amount) => cost.clone().or_else(|| amount.clone()),
+        }
+    }
+
+    /// quantity of the amount, or of the cost if the amount has a price
+    pub fn value(&self) -> Option<Decimal> {
+        self.weight().map(|a| a.quantity)
+    }
+}
+
+/// key of the tag, that keeps the id of a transaction
+pub const ID_TAG: &str = "id";
+
+/// `id` tag with a new id. It is written into every new transaction, so its id stays the
+/// same, when other transactions are added or removed.
+pub fn new_id_tag() -> String {
+    format!("{}: {}", ID_TAG, Uuid::new_v4().simple())
+}
+
+/// FNV-1a hash of the content as hex string. Unlike `DefaultHasher` it is the same on every
+/// build, so ids stay valid between restarts.
+pub fn content_hash(content: &str) -> String {
+    let hash = content.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// Creates ids from the content of entries without `id` tag. Identical entries get the number
+/// of their occurrence as suffix, so every entry can be addressed on its own.
+#[derive(Default)]
+pub struct IdGenerator {
+    seen: HashMap<String, usize>,
+}
+
+impl IdGenerator {
+    pub fn next(&mut self, content: &str) -> String {
+        let hash = content_hash(content);
+        let count = self.seen.entry(hash.clone()).or_insert(0);
+        *count += 1;
+        match count {
+            1 => hash,
+            n => format!("{}-{}", hash, *n - 1),
+        }
+    }
+}
+
+/// reads and parses a journal file
+pub fn read_file(path: &str) -> Result<Journal, ServiceError> {
+    Ok(parse(&fs::read_to_string(path)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JOURNAL: &str = "2022/10/10\t\t\tLunch
+ \tFreeTime
+ \tFreeTime:Food\t\t30m
+
+2022/10/10\t\t\tLunch
+ \tFreeTime
+ \tFreeTime:Food\t\t30m
+
+2022/10/11\t\t\tDinner  ; id: dinner
+ \tFreeTime
+ \tFreeTime:Food\t\t30m
+";
+
+    #[test]
+    fn test_transaction_ids() {
+        let ids = parse(JOURNAL).transaction_ids();
+        assert_eq!(ids[1], format!("{}-1", ids[0]));
+        assert_eq!(ids[2], "dinner");
+    }
+
+    #[test]
+    fn test_remove() {
+        let journal = parse(JOURNAL);
+        let content = journal.remove(&Span { start: 5, end: 7 });
+        assert_eq!(content.lines().count(), 7);
+        assert_eq!(parse(&content).transaction_ids()[1], "dinner");
+        // the blank line in front of the last transaction is removed as well
+        let content = journal.remove(&Span { start: 9, end: 11 });
+        assert!(content
+            .ends_with("30m\n\n2022/10/10\t\t\tLunch\n \tFreeTime\n \tFreeTime:Food\t\t30m\n"));
+    }
+
+    #[test]
+    fn test_replace() {
+        let journal = parse(JOURNAL);
+        let content = journal.replace(
+            &Span { start: 5, end: 7 },
+            "2022/10/10\t\t\tBreakfast\n \tFreeTime\n \tFreeTime:Food\t\t15m\n",
+        );
+        let replaced = parse(&content);
+        let transactions = replaced.transactions().collect::<Vec<_>>();
+        assert_eq!(transactions.len(), 3);
+        assert_eq!(transactions[1].headline, "Breakfast");
+        assert_eq!(transactions[1].span, Span { start: 5, end: 7 });
+        assert_eq!(replaced.transaction_ids()[2], "dinner");
+    }
+}
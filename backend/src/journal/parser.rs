@@ -0,0 +1,287 @@
+use chrono::NaiveDate;
+use shared::amount::Amount;
+
+use super::{Comment, Entry, Journal, ParseError, Posting, Span, Transaction};
+
+const COMMENT_CHARS: [char; 5] = [';', '#', '%', '|', '*'];
+const DATE_FORMATS: [&str; 3] = ["%Y/%m/%d", "%Y-%m-%d", "%Y.%m.%d"];
+
+/// Parses the content of a ledger journal.
+/// Lines, that can not be understood, are reported in `Journal.errors` and skipped.
+pub fn parse(source: &str) -> Journal {
+    let mut journal = Journal {
+        lines: source.lines().map(|l| l.to_string()).collect(),
+        ..Journal::default()
+    };
+    let mut current: Option<Transaction> = None;
+    // set, if the header of the current transaction could not be parsed
+    let mut skipping = false;
+
+    for (index, line) in source.lines().enumerate() {
+        let number = index + 1;
+        let trimmed = line.trim();
+
+        if trimmed.is_empty() {
+            close(&mut journal, &mut current);
+            skipping = false;
+        } else if line.starts_with(char::is_whitespace) {
+            if let Some(transaction) = current.as_mut() {
+                transaction.span.end = number;
+                if let Some(text) = trimmed.strip_prefix(';') {
+                    transaction.comments.push(Comment {
+                        text: text.trim().to_string(),
+                        line: number,
+                    });
+                } else {
+                    match parse_posting(trimmed, number) {
+                        Ok(posting) => transaction.postings.push(posting),
+                        Err(e) => journal.errors.push(e),
+                    }
+                }
+            } else if let Some(text) = trimmed.strip_prefix(';') {
+                journal.entries.push(Entry::Comment(Comment {
+                    text: text.trim().to_string(),
+                    line: number,
+                }));
+            } else if !skipping {
+                journal.errors.push(ParseError {
+                    line: number,
+                    message: "Posting outside of a transaction.".to_string(),
+                });
+            }
+        } else if line.starts_with(COMMENT_CHARS) {
+            close(&mut journal, &mut current);
+            skipping = false;
+            journal.entries.push(Entry::Comment(Comment {
+                text: line[1..].trim().to_string(),
+                line: number,
+            }));
+        } else if line.starts_with(|c: char| c.is_ascii_digit()) {
+            close(&mut journal, &mut current);
+            match parse_header(line, number) {
+                Ok(transaction) => {
+                    current = Some(transaction);
+                    skipping = false;
+                }
+                Err(e) => {
+                    journal.errors.push(e);
+                    skipping = true;
+                }
+            }
+        } else {
+            close(&mut journal, &mut current);
+            skipping = false;
+            journal.entries.push(Entry::Directive(Comment {
+                text: line.to_string(),
+                line: number,
+            }));
+        }
+    }
+    close(&mut journal, &mut current);
+    journal
+}
+
+fn close(journal: &mut Journal, current: &mut Option<Transaction>) {
+    if let Some(transaction) = current.take() {
+        journal.entries.push(Entry::Transaction(transaction));
+    }
+}
+
+/// splits `content  ; note` in content and note
+fn split_note(text: &str) -> (&str, Option<String>) {
+    let position = [text.find("\t;"), text.find("  ;")]
+        .into_iter()
+        .flatten()
+        .min();
+    match position {
+        Some(p) => (
+            text[..p].trim_end(),
+            Some(text[p..].trim_start()[1..].trim().to_string()),
+        ),
+        None => (text, None),
+    }
+}
+
+fn parse_date(date: &str) -> Option<NaiveDate> {
+    DATE_FORMATS
+        .iter()
+        .find_map(|f| NaiveDate::parse_from_str(date, f).ok())
+}
+
+fn parse_header(line: &str, number: usize) -> Result<Transaction, ParseError> {
+    let (content, note) = split_note(line);
+    let date_str = content
+        .split(char::is_whitespace)
+        .next()
+        .unwrap_or_default();
+    // auxiliary dates (`2022/10/10=2022/10/12`) are ignored
+    let date = match parse_date(date_str.split('=').next().unwrap_or_default()) {
+        Some(d) => d,
+        None => {
+            return Err(ParseError {
+                line: number,
+                message: format!("Invalid date '{}'.", date_str),
+            })
+        }
+    };
+    Ok(Transaction {
+        date,
+        headline: content[date_str.len()..].trim().to_string(),
+        postings: Vec::new(),
+        comments: note
+            .map(|text| Comment { text, line: number })
+            .into_iter()
+            .collect(),
+        span: Span {
+            start: number,
+            end: number,
+        },
+    })
+}
+
+/// the account ends at the first tab or at two spaces, the rest is the amount.
+/// A price (`@ 2€` per unit or `@@ 6€` in total) is stored as the absolute total cost.
+fn parse_posting(trimmed: &str, number: usize) -> Result<Posting, ParseError> {
+    let (content, comment) = split_note(trimmed);
+    let end = [content.find('\t'), content.find("  ")]
+        .into_iter()
+        .flatten()
+        .min()
+        .unwrap_or(content.len());
+    let amount = content[end..].trim();
+    let invalid = |text: &str| ParseError {
+        line: number,
+        message: format!("Invalid amount '{}'.", text),
+    };
+    let parse_amount = |text: &str| Amount::parse_decimal_point(text).ok_or_else(|| invalid(text));
+    let (amount, cost) = if amount.is_empty() {
+        (None, None)
+    } else if let Some((quantity, total)) = amount.split_once("@@") {
+        let mut total = parse_amount(total)?;
+        total.quantity = total.quantity.abs();
+        (Some(parse_amount(quantity)?), Some(total))
+    } else if let Some((quantity, price)) = amount.split_once('@') {
+        let quantity = parse_amount(quantity)?;
+        let mut price = parse_amount(price)?;
+        price.quantity = (price.quantity * quantity.quantity).abs();
+        (Some(quantity), Some(price))
+    } else {
+        (Some(parse_amount(amount)?), None)
+    };
+    Ok(Posting {
+        account: content[..end].to_string(),
+        amount,
+        cost,
+        comment,
+        line: number,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::amount::Decimal;
+
+    const TIME_JOURNAL: &str = "; 09:00 - 10:30
+2022/10/10\t\t\tCarlos is programming
+ \tFreeTime
+ \tEducationRust\t\t\t\t\t\t\t90m
+
+;600 2022/10/11\t\t\tRunning; \tFreeTime; \tEducationRust\t\t\t\t\t\t##m
+";
+
+    #[test]
+    fn test_parse_time_journal() {
+        let journal = parse(TIME_JOURNAL);
+        assert!(journal.errors.is_empty());
+        let transactions = journal.transactions().collect::<Vec<_>>();
+        assert_eq!(transactions.len(), 1);
+        let transaction = transactions[0];
+        assert_eq!(transaction.headline, "Carlos is programming");
+        assert_eq!(
+            transaction.date,
+            NaiveDate::from_ymd_opt(2022, 10, 10).unwrap()
+        );
+        assert_eq!(transaction.span, Span { start: 2, end: 4 });
+        assert_eq!(transaction.postings[0].account, "FreeTime");
+        assert_eq!(transaction.postings[0].amount, None);
+        assert_eq!(transaction.postings[1].account, "EducationRust");
+        assert_eq!(transaction.postings[1].minutes(), Some(90));
+        assert_eq!(
+            journal.leading_comments(transaction)[0].text,
+            "09:00 - 10:30"
+        );
+        assert_eq!(journal.comments().count(), 2);
+    }
+
+    #[test]
+    fn test_parse_multi_posting_with_notes() {
+        let journal = parse(
+            "2022/10/02 Supermarkt  ; receipt 42
+    Ausgaben:Essen      12.50€
+    ; only food
+    Ausgaben:Haushalt   3€  ; soap
+    Girokonto:N2
+",
+        );
+        assert!(journal.errors.is_empty());
+        let transaction = journal.transactions().next().unwrap();
+        assert_eq!(transaction.headline, "Supermarkt");
+        assert_eq!(transaction.postings.len(), 3);
+        assert_eq!(transaction.postings[0].amount, Amount::parse("12.50€"));
+        assert_eq!(transaction.postings[0].value(), Some(Decimal::new(1250, 2)));
+        assert_eq!(transaction.postings[1].comment, Some("soap".to_string()));
+        assert_eq!(transaction.postings[2].amount, None);
+        assert_eq!(transaction.comments.len(), 2);
+        assert_eq!(transaction.span, Span { start: 1, end: 5 });
+    }
+
+    #[test]
+    fn test_parse_cost() {
+        let journal = parse(
+            "2022/10/03 ETF
+ \tDepot:ETF\t\t3 ETF @ 20.10€
+ \tDepot:Gold\t\t2 g @@ 101€
+ \tGirokonto:N2\t\t-161.30€
+",
+        );
+        assert!(journal.errors.is_empty());
+        let transaction = journal.transactions().next().unwrap();
+        assert_eq!(transaction.postings[0].amount, Amount::parse("3 ETF"));
+        assert_eq!(transaction.postings[0].cost, Amount::parse("60.30€"));
+        assert_eq!(transaction.postings[1].cost, Amount::parse("101€"));
+        assert_eq!(transaction.values().iter().sum::<Decimal>(), Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_parse_cost_of_sale() {
+        let journal = parse(
+            "2022/10/04 Sell ETF
+ \tDepot:ETF\t\t-3 ETF @@ 60€
+ \tDepot:Gold\t\t-2 g @ 50.50€
+ \tGirokonto:N2\t\t161€
+",
+        );
+        assert!(journal.errors.is_empty());
+        let transaction = journal.transactions().next().unwrap();
+        assert_eq!(transaction.postings[0].cost, Amount::parse("60€"));
+        assert_eq!(transaction.postings[1].cost, Amount::parse("101€"));
+        assert_eq!(transaction.postings[0].weight(), Amount::parse("-60€"));
+        assert_eq!(
+            transaction.values(),
+            vec![Decimal::from(-60), Decimal::from(-101), Decimal::from(161)]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let journal = parse(
+            "2022/13/45 Broken\n \tFreeTime\n\n \tOrphan  1m\n\n2022/10/10 Valid\n \tFreeTime  1 2€\n",
+        );
+        assert_eq!(journal.transactions().count(), 1);
+        assert_eq!(
+            journal.errors.iter().map(|e| e.line).collect::<Vec<_>>(),
+            vec![1, 4, 7]
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use log::debug;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::errors::ServiceError;
+
+// one lock per journal file. The path contains the user, so every user has own locks.
+static FILE_LOCKS: OnceLock<Mutex<HashMap<String, Arc<Mutex<()>>>>> = OnceLock::new();
+
+fn file_lock(path: &str) -> Arc<Mutex<()>> {
+    let mut locks = FILE_LOCKS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    locks.entry(path.to_string()).or_default().clone()
+}
+
+/// Writes the content to a temp file in the same directory and renames it to the path, so the
+/// journal is either the old or the new version, even if the server crashes while writing.
+/// The old version is kept as `<path>.bak`.
+fn write_atomic(path: &str, content: &str) -> Result<(), ServiceError> {
+    let temp_path = format!("{}.tmp", path);
+    let mut temp = fs::File::create(&temp_path)?;
+    temp.write_all(content.as_bytes())?;
+    temp.sync_all()?;
+    if Path::new(path).exists() {
+        fs::copy(path, format!("{}.bak", path))?;
+    }
+    fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+/// Changes the journal file with `change` while holding the lock of the file.
+/// `change` gets the current content and returns the new content and a result.
+/// If `change` fails, the file is left untouched.
+pub fn modify_file<T>(
+    path: &str,
+    change: impl FnOnce(&str) -> Result<(String, T), ServiceError>,
+) -> Result<T, ServiceError> {
+    let lock = file_lock(path);
+    let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+    let content = fs::read_to_string(path)?;
+    let (new_content, result) = change(&content)?;
+    if new_content != content {
+        write_atomic(path, &new_content)?;
+        debug!("Wrote journal '{}'", path);
+    }
+    Ok(result)
+}
+
+/// Creates an empty file, if there is none at the path. Returns true, if the file was created.
+pub fn create_file(path: &str) -> Result<bool, ServiceError> {
+    let lock = file_lock(path);
+    let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+    if Path::new(path).exists() {
+        return Ok(false);
+    }
+    write_atomic(path, "")?;
+    debug!("Created journal '{}'", path);
+    Ok(true)
+}
+
+/// appends the text to the journal file
+pub fn append_file(path: &str, text: &str) -> Result<(), ServiceError> {
+    modify_file(path, |content| Ok((format!("{}{}", content, text), ())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_modify_file() {
+        let dir = TempDir::new().unwrap();
+        let path = format!("{}/modify.dat", dir.path().display());
+        fs::write(&path, "first\n").unwrap();
+
+        append_file(&path, "second\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first\nsecond\n");
+        assert_eq!(
+            fs::read_to_string(format!("{}.bak", path)).unwrap(),
+            "first\n"
+        );
+
+        // a failing change does not touch the file
+        let result: Result<(), _> = modify_file(&path, |_| {
+            Err(ServiceError::BadRequest("failed".to_string()))
+        });
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first\nsecond\n");
+        assert!(!Path::new(&format!("{}.tmp", path)).exists());
+    }
+
+    #[test]
+    fn test_append_file_concurrent() {
+        let dir = TempDir::new().unwrap();
+        let path = format!("{}/append.dat", dir.path().display());
+        fs::write(&path, "").unwrap();
+
+        thread::scope(|s| {
+            for i in 0..20 {
+                let path = &path;
+                s.spawn(move || append_file(path, &format!("line {}\n", i)).unwrap());
+            }
+        });
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 20);
+    }
+}
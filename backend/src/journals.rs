@@ -0,0 +1,214 @@
+//! Journal definitions of the users. They are stored in `./files/<user>/journals.json`,
+//! users without this file get the default journals.
+use log::debug;
+use std::fs;
+use std::path::Path;
+
+use crate::check;
+use crate::errors::ServiceError;
+use crate::journal;
+use crate::utils::{user_dir, user_file, PATH_MUSIC, PATH_TIME_SPEND};
+use shared::models::{JournalDefinition, JournalKind};
+
+pub const JOURNALS_FILE: &str = "journals.json";
+
+/// the journals every user had before they could be configured. First finance journal is the default.
+fn default_journals() -> Vec<JournalDefinition> {
+    let journal = |name: &str, path: &str, commodity: &str, kind| JournalDefinition {
+        name: name.to_string(),
+        path: path.to_string(),
+        commodity: commodity.to_string(),
+        kind,
+        archived: false,
+    };
+    vec![
+        journal("Time", PATH_TIME_SPEND, "m", JournalKind::Time),
+        journal("Music", PATH_MUSIC, "m", JournalKind::Music),
+        journal("Gesamt", "gesamt.dat", "€", JournalKind::Finance),
+        journal("Nachhilfe", "nachhilfe.dat", "€", JournalKind::Finance),
+        journal("Invest", "invest.dat", "€", JournalKind::Finance),
+        journal("Wohnung", "rent.dat", "€", JournalKind::Finance),
+    ]
+}
+
+fn parse_decimal_points(content: &str) -> Result<Vec<JournalDefinition>, ServiceError> {
+    if content.trim().is_empty() {
+        return Ok(default_journals());
+    }
+    Ok(serde_json::from_str(content)?)
+}
+
+/// all journals of the user, archived ones included
+pub fn user_journals(user: &str) -> Result<Vec<JournalDefinition>, ServiceError> {
+    let path = user_file(user, JOURNALS_FILE);
+    if !Path::new(&path).exists() {
+        return Ok(default_journals());
+    }
+    parse_decimal_points(&fs::read_to_string(path)?)
+}
+
+/// journals of the kind, that can be written to
+pub fn active_journals(
+    user: &str,
+    kind: JournalKind,
+) -> Result<Vec<JournalDefinition>, ServiceError> {
+    Ok(user_journals(user)?
+        .into_iter()
+        .filter(|j| j.kind == kind && !j.archived)
+        .collect())
+}
+
+/// Finance journal with the name. An empty name selects the default journal.
+pub fn finance_journal(user: &str, name: &str) -> Result<JournalDefinition, ServiceError> {
+    let journals = active_journals(user, JournalKind::Finance)?;
+    let journal = if name.is_empty() {
+        journals.into_iter().next()
+    } else {
+        journals.into_iter().find(|j| j.name == name)
+    };
+    journal.ok_or_else(|| ServiceError::BadRequest(format!("Unknown journal '{}'.", name)))
+}
+
+fn check_journal(
+    journals: &[JournalDefinition],
+    new_journal: &JournalDefinition,
+) -> Result<(), ServiceError> {
+    let invalid = |message: String| Err(ServiceError::BadRequest(message));
+    // the time and music enteries are always written to their default files
+    if new_journal.kind != JournalKind::Finance {
+        return invalid("Only finance journals can be created.".to_string());
+    }
+    if new_journal.name.trim().is_empty() || new_journal.name.trim() != new_journal.name {
+        return invalid(format!("Invalid name '{}'.", new_journal.name));
+    }
+    if !new_journal.path.ends_with(".dat")
+        || new_journal.path.starts_with('.')
+        || new_journal.path.contains(['/', '\\'])
+        || new_journal.path.contains(char::is_whitespace)
+    {
+        return invalid(format!(
+            "Invalid path '{}', it has to be a '.dat' file name.",
+            new_journal.path
+        ));
+    }
+    if new_journal.commodity.is_empty()
+        || new_journal
+            .commodity
+            .contains(|c: char| c.is_ascii_digit() || c.is_whitespace() || c == '-' || c == '.')
+    {
+        return invalid(format!("Invalid commodity '{}'.", new_journal.commodity));
+    }
+    if journals.iter().any(|j| j.name == new_journal.name) {
+        return invalid(format!(
+            "The journal '{}' exists already.",
+            new_journal.name
+        ));
+    }
+    if journals.iter().any(|j| j.path == new_journal.path) {
+        return invalid(format!(
+            "The file '{}' belongs to another journal.",
+            new_journal.path
+        ));
+    }
+    Ok(())
+}
+
+/// Adds the journal to the definitions of the user and creates its file, if it does not exist.
+pub fn create_journal(
+    user: &str,
+    new_journal: JournalDefinition,
+) -> Result<JournalDefinition, ServiceError> {
+    let new_journal = JournalDefinition {
+        archived: false,
+        ..new_journal
+    };
+    fs::create_dir_all(user_dir(user))?;
+    let path = user_file(user, JOURNALS_FILE);
+    journal::create_file(&path)?;
+    journal::modify_file(&path, |content| {
+        let mut journals = parse_decimal_points(content)?;
+        check_journal(&journals, &new_journal)?;
+        journals.push(new_journal.clone());
+        Ok((serde_json::to_string_pretty(&journals)?, ()))
+    })?;
+    journal::create_file(&user_file(user, &new_journal.path))?;
+    // the file may exist already, e.g. a journal that was written by hand
+    check::check_written(user, &new_journal.path);
+    debug!("User '{}' created journal '{}'", user, new_journal.name);
+    Ok(new_journal)
+}
+
+/// Archives the journal. Its file is kept, so it stays in the reports.
+pub fn archive_journal(user: &str, name: &str) -> Result<(), ServiceError> {
+    let path = user_file(user, JOURNALS_FILE);
+    journal::create_file(&path)?;
+    journal::modify_file(&path, |content| {
+        let mut journals = parse_decimal_points(content)?;
+        match journals.iter_mut().find(|j| j.name == name && !j.archived) {
+            Some(journal) => journal.archived = true,
+            None => {
+                return Err(ServiceError::NotFound(format!(
+                    "No active journal '{}'.",
+                    name
+                )))
+            }
+        }
+        Ok((serde_json::to_string_pretty(&journals)?, ()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dirs::UserDir;
+
+    #[test]
+    fn test_create_and_archive_journal() {
+        let user = "journals-test";
+        let _dir = UserDir::new(user);
+        assert_eq!(user_journals(user).unwrap(), default_journals());
+        assert_eq!(finance_journal(user, "").unwrap().path, "gesamt.dat");
+        assert!(matches!(
+            finance_journal(user, "Unknown"),
+            Err(ServiceError::BadRequest(_))
+        ));
+
+        let depot = JournalDefinition {
+            name: "Depot".to_string(),
+            path: "depot.dat".to_string(),
+            commodity: "ETF".to_string(),
+            kind: JournalKind::Finance,
+            archived: true,
+        };
+        let created = create_journal(user, depot.clone()).unwrap();
+        assert!(!created.archived);
+        assert!(Path::new(&user_file(user, "depot.dat")).exists());
+        assert_eq!(finance_journal(user, "Depot").unwrap(), created);
+        // names and files are unique and files stay in the directory of the user
+        assert!(create_journal(user, depot.clone()).is_err());
+        let outside = JournalDefinition {
+            name: "Outside".to_string(),
+            path: "../test/gesamt.dat".to_string(),
+            ..depot.clone()
+        };
+        assert!(create_journal(user, outside).is_err());
+        let time = JournalDefinition {
+            name: "Time".to_string(),
+            path: "time.dat".to_string(),
+            kind: JournalKind::Time,
+            ..depot.clone()
+        };
+        assert!(matches!(
+            create_journal(user, time),
+            Err(ServiceError::BadRequest(_))
+        ));
+
+        archive_journal(user, "Depot").unwrap();
+        assert!(finance_journal(user, "Depot").is_err());
+        assert!(user_journals(user).unwrap().iter().any(|j| j.archived));
+        assert!(matches!(
+            archive_journal(user, "Depot"),
+            Err(ServiceError::NotFound(_))
+        ));
+    }
+}
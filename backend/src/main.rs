@@ -10,16 +10,31 @@ use diesel::{r2d2, r2d2::ConnectionManager, PgConnection};
 use models::db::Pool;
 
 use dotenvy::dotenv;
+use log::error;
+use openssl::ssl::{SslAcceptor, SslFiletype, SslMethod};
+use std::time::Duration;
 
 use configuration::Application;
 use handler::api;
 
+mod accounts;
+mod analysis;
 mod auth;
+mod budget;
+mod check;
 mod configuration;
 mod db;
 mod errors;
+mod export;
 mod handler;
+mod import;
+mod journal;
+mod journals;
 mod models;
+mod recurring;
+mod report;
+mod statistics;
+mod suggestion;
 mod tests;
 mod utils;
 
@@ -29,14 +44,62 @@ async fn main() -> std::io::Result<()> {
     env_logger::builder().format_timestamp(None).init();
 
     let settings = Application::default();
+    // never start with a default or missing signing key
+    if let Err(e) = auth::init_keys(&settings.jwt) {
+        error!("{}", e);
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            e.to_string(),
+        ));
+    }
 
     //TODO change to RUSTLS, because it is faster and more secure
     let connection_manager = ConnectionManager::<PgConnection>::new(settings.database_url);
     let pool: Pool = r2d2::Pool::builder()
         .build(connection_manager)
         .expect("Failed to create pool.");
+    let time_tracking = web::Data::new(settings.time_tracking.clone());
+    let registration = web::Data::new(settings.registration.clone());
+    if let Some(admin) = &settings.registration.admin {
+        match pool.get() {
+            Ok(mut connection) => {
+                if let Err(e) = db::roles::grant_admin(&mut connection, admin) {
+                    error!("Could not grant the admin role to '{}': {}", admin, e);
+                }
+            }
+            Err(e) => error!("Could not grant the admin role to '{}': {}", admin, e),
+        }
+    }
+    if let Err(e) = utils::argon2(&settings.passwords) {
+        error!("{}", e);
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            e.to_string(),
+        ));
+    }
+    let passwords = web::Data::new(settings.passwords.clone());
+
+    // create the due recurring transactions at startup and then periodically
+    let recurring_interval = Duration::from_secs(settings.recurring.interval_minutes * 60);
+    actix_rt::spawn(async move {
+        let mut interval = actix_rt::time::interval(recurring_interval);
+        loop {
+            interval.tick().await;
+            match web::block(recurring::materialize_all).await {
+                Ok(Ok(_)) => (),
+                Ok(Err(e)) => error!("Could not create recurring transactions: {}", e),
+                Err(e) => error!("Could not create recurring transactions: {}", e),
+            }
+        }
+    });
 
     //add https support
+    let mut builder = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+    builder
+        .set_private_key_file("key.pem", SslFiletype::PEM)
+        .unwrap();
+    builder.set_certificate_chain_file("cert.pem").unwrap();
+
     HttpServer::new(move || {
         let cors = Cors::permissive()
             //TODO setup tight policy
@@ -51,15 +114,25 @@ async fn main() -> std::io::Result<()> {
         let auth = HttpAuthentication::bearer(auth::validator);
         App::new()
             .app_data(web::Data::new(pool.clone()))
+            .app_data(time_tracking.clone())
+            .app_data(registration.clone())
+            .app_data(passwords.clone())
             .wrap(cors)
             .service(
                 web::scope("/api")
                     .route("login.json", web::post().to(api::login))
+                    .route("refresh.json", web::post().to(api::refresh))
+                    .route("logout.json", web::post().to(api::logout))
                     .route("create_user.json", web::post().to(api::create_user))
                     .service(
                         web::scope("/auth")
                             .wrap(auth)
+                            .route(
+                                "set_password_change.json",
+                                web::post().to(api::set_password_change),
+                            )
                             .route("get_html.json", web::post().to(api::get_html))
+                            .route("get_report.json", web::post().to(api::get_report))
                             .route(
                                 "get_html_suggestions.json",
                                 web::get().to(api::get_html_suggetstions),
@@ -84,10 +157,26 @@ async fn main() -> std::io::Result<()> {
                                 "set_time_entery_kill.json",
                                 web::post().to(api::set_time_entery_kill),
                             )
+                            .route(
+                                "set_time_entery_update.json",
+                                web::post().to(api::set_time_entery_update),
+                            )
+                            .route(
+                                "set_time_entery_delete.json",
+                                web::post().to(api::set_time_entery_delete),
+                            )
                             .route(
                                 "get_time_entery_history.json",
                                 web::get().to(api::get_time_history),
                             )
+                            .route(
+                                "get_time_analysis.json",
+                                web::post().to(api::get_time_analysis),
+                            )
+                            .route(
+                                "get_time_statistics.json",
+                                web::post().to(api::get_time_statistics),
+                            )
                             .route(
                                 "get_finance_suggestions.json",
                                 web::get().to(api::get_finance_suggestions),
@@ -95,7 +184,70 @@ async fn main() -> std::io::Result<()> {
                             .route(
                                 "set_finance_entery_create.json",
                                 web::post().to(api::set_finance_entery_create),
-                            ),
+                            )
+                            .route("get_budgets.json", web::get().to(api::get_budgets))
+                            .route("set_budget.json", web::post().to(api::set_budget))
+                            .route(
+                                "set_budget_delete.json",
+                                web::post().to(api::set_budget_delete),
+                            )
+                            .route(
+                                "get_budget_report.json",
+                                web::post().to(api::get_budget_report),
+                            )
+                            .route("get_recurring.json", web::get().to(api::get_recurring))
+                            .route(
+                                "set_recurring_create.json",
+                                web::post().to(api::set_recurring_create),
+                            )
+                            .route(
+                                "set_recurring_delete.json",
+                                web::post().to(api::set_recurring_delete),
+                            )
+                            .route(
+                                "get_recurring_upcoming.json",
+                                web::post().to(api::get_recurring_upcoming),
+                            )
+                            .route("get_export_time.json", web::post().to(api::get_export_time))
+                            .route(
+                                "get_export_finance.json",
+                                web::post().to(api::get_export_finance),
+                            )
+                            .route(
+                                "get_import_settings.json",
+                                web::get().to(api::get_import_settings),
+                            )
+                            .route(
+                                "set_import_settings.json",
+                                web::post().to(api::set_import_settings),
+                            )
+                            .route(
+                                "get_import_preview.json",
+                                web::post().to(api::get_import_preview),
+                            )
+                            .route("set_import.json", web::post().to(api::set_import))
+                            .route("get_check.json", web::get().to(api::get_check))
+                            .route(
+                                "get_account_tree.json",
+                                web::get().to(api::get_account_tree),
+                            )
+                            .route("get_journals.json", web::get().to(api::get_journals))
+                            .route(
+                                "set_journal_create.json",
+                                web::post().to(api::set_journal_create),
+                            )
+                            .route(
+                                "set_journal_archive.json",
+                                web::post().to(api::set_journal_archive),
+                            )
+                            .route("get_users.json", web::get().to(api::get_users))
+                            .route("set_user_create.json", web::post().to(api::set_user_create))
+                            .route(
+                                "set_user_disable.json",
+                                web::post().to(api::set_user_disable),
+                            )
+                            .route("set_user_delete.json", web::post().to(api::set_user_delete))
+                            .route("set_user_reset.json", web::post().to(api::set_user_reset)),
                     ),
             )
     })
@@ -1,14 +1,26 @@
-use crate::models::schema::users;
+use crate::models::schema::{refresh_tokens, revoked_tokens, user_roles, users};
 use diesel::{r2d2, r2d2::ConnectionManager, PgConnection};
 use diesel::{Insertable, Queryable};
 pub type Pool = r2d2::Pool<ConnectionManager<PgConnection>>;
-use serde::Serialize;
+use std::fmt;
 
-#[derive(Queryable, Debug, Serialize)]
+#[derive(Queryable)]
 pub struct User {
     pub user_id: String,
     pub username: String,
     pub password: String,
+    pub disabled: bool,
+}
+
+// the password hash must not end up in the log
+impl fmt::Debug for User {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("User")
+            .field("user_id", &self.user_id)
+            .field("username", &self.username)
+            .field("disabled", &self.disabled)
+            .finish()
+    }
 }
 
 #[derive(Insertable)]
@@ -18,3 +30,38 @@ pub struct NewUser<'a> {
     pub username: &'a str,
     pub password: &'a str,
 }
+
+/// refresh token, of witch only the hash is stored. Every rotation of a login belongs to
+/// the same family.
+#[derive(Queryable, Debug)]
+pub struct RefreshToken {
+    pub token_hash: String,
+    pub family_id: String,
+    pub username: String,
+    pub expires_at: i64,
+    pub used: bool,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = refresh_tokens)]
+pub struct NewRefreshToken<'a> {
+    pub token_hash: &'a str,
+    pub family_id: &'a str,
+    pub username: &'a str,
+    pub expires_at: i64,
+}
+
+/// access token, that is rejected until it expires
+#[derive(Insertable)]
+#[diesel(table_name = revoked_tokens)]
+pub struct RevokedToken<'a> {
+    pub jti: &'a str,
+    pub expires_at: i64,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = user_roles)]
+pub struct UserRole<'a> {
+    pub username: &'a str,
+    pub role: &'a str,
+}
@@ -1,7 +1,59 @@
+diesel::table! {
+    refresh_tokens (token_hash) {
+        token_hash -> Varchar,
+        family_id -> Varchar,
+        username -> Varchar,
+        expires_at -> Int8,
+        used -> Bool,
+    }
+}
+
+diesel::table! {
+    revoked_tokens (jti) {
+        jti -> Varchar,
+        expires_at -> Int8,
+    }
+}
+
+diesel::table! {
+    role_permissions (role, permission) {
+        role -> Varchar,
+        permission -> Varchar,
+    }
+}
+
+diesel::table! {
+    roles (name) {
+        name -> Varchar,
+    }
+}
+
+diesel::table! {
+    user_roles (username, role) {
+        username -> Varchar,
+        role -> Varchar,
+    }
+}
+
 diesel::table! {
     users (username) {
         user_id -> Varchar,
         username -> Varchar,
         password -> Varchar,
+        disabled -> Bool,
     }
 }
+
+diesel::joinable!(refresh_tokens -> users (username));
+diesel::joinable!(role_permissions -> roles (role));
+diesel::joinable!(user_roles -> roles (role));
+diesel::joinable!(user_roles -> users (username));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    refresh_tokens,
+    revoked_tokens,
+    role_permissions,
+    roles,
+    user_roles,
+    users,
+);
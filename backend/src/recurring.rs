@@ -0,0 +1,378 @@
+//! Recurring finance transactions of the users, stored in `./files/<user>/recurring.json`.
+//! Every occurrence is written to its journal with a `; recurring: <id> <date>` tag, so it
+//! is created only once, even if the job runs again after a restart.
+use chrono::{Duration, Months, NaiveDate};
+use log::{debug, warn};
+use std::fs;
+use std::path::Path;
+use uuid::Uuid;
+
+use crate::check;
+use crate::errors::ServiceError;
+use crate::journal;
+use crate::utils::{self, user_dir, user_file, FILE_DIR};
+use shared::models::{NewFinanceEntery, Occurrence, RecurringTransaction};
+
+pub const RECURRING_FILE: &str = "recurring.json";
+pub const FREQUENCIES: [&str; 4] = ["daily", "weekly", "monthly", "yearly"];
+// tag of the created transactions
+const TAG: &str = "recurring";
+// upcoming days, if the request does not say otherwise
+const DEFAULT_UPCOMING_DAYS: u32 = 30;
+// most occurrences of one recurring transaction, that are calculated at once
+const MAX_OCCURRENCES: u32 = 5000;
+
+fn parse_recurring(content: &str) -> Result<Vec<RecurringTransaction>, ServiceError> {
+    if content.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    Ok(serde_json::from_str(content)?)
+}
+
+fn format_date(date: NaiveDate) -> String {
+    date.format("%Y/%m/%d").to_string()
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, ServiceError> {
+    NaiveDate::parse_from_str(&date.replace('-', "/"), "%Y/%m/%d")
+        .map_err(|_| ServiceError::BadRequest(format!("Invalid date '{}'.", date)))
+}
+
+/// the n-th occurrence. Monthly and yearly occurrences stay at the end of shorter months.
+fn occurrence(start: NaiveDate, frequency: &str, n: u32) -> Option<NaiveDate> {
+    match frequency {
+        "daily" => start.checked_add_signed(Duration::days(n.into())),
+        "weekly" => start.checked_add_signed(Duration::weeks(n.into())),
+        "monthly" => start.checked_add_months(Months::new(n)),
+        "yearly" => start.checked_add_months(Months::new(n.checked_mul(12)?)),
+        _ => None,
+    }
+}
+
+/// dates of the occurrences after `after` up to `until` (inclusive)
+pub fn occurrences(
+    recurring: &RecurringTransaction,
+    after: Option<NaiveDate>,
+    until: NaiveDate,
+) -> Result<Vec<NaiveDate>, ServiceError> {
+    let start = parse_date(&recurring.start)?;
+    let until = match &recurring.end {
+        Some(end) => until.min(parse_date(end)?),
+        None => until,
+    };
+    let mut dates = Vec::new();
+    for n in 0..MAX_OCCURRENCES {
+        match occurrence(start, &recurring.frequency, n) {
+            Some(date) if date <= until => {
+                if after.is_none_or(|a| date > a) {
+                    dates.push(date);
+                }
+            }
+            _ => break,
+        }
+    }
+    Ok(dates)
+}
+
+/// occurrences, that are not created yet
+fn pending(
+    recurring: &RecurringTransaction,
+    until: NaiveDate,
+) -> Result<Vec<NaiveDate>, ServiceError> {
+    let last = recurring.last.as_deref().map(parse_date).transpose()?;
+    occurrences(recurring, last, until)
+}
+
+/// all recurring transactions of the user
+pub fn user_recurring(user: &str) -> Result<Vec<RecurringTransaction>, ServiceError> {
+    let path = user_file(user, RECURRING_FILE);
+    if !Path::new(&path).exists() {
+        return Ok(Vec::new());
+    }
+    parse_recurring(&fs::read_to_string(path)?)
+}
+
+/// Checks and saves the recurring transaction. It gets a new id.
+pub fn create_recurring(
+    user: &str,
+    recurring: RecurringTransaction,
+) -> Result<RecurringTransaction, ServiceError> {
+    if !FREQUENCIES.contains(&recurring.frequency.as_str()) {
+        return Err(ServiceError::BadRequest(format!(
+            "Unknown frequency '{}'.",
+            recurring.frequency
+        )));
+    }
+    let start = parse_date(&recurring.start)?;
+    if let Some(end) = &recurring.end {
+        if parse_date(end)? < start {
+            return Err(ServiceError::BadRequest(format!(
+                "The end '{}' is before the start.",
+                end
+            )));
+        }
+    }
+    // the entery is checked like a new finance entery
+    utils::finance_entery_text(user, &recurring.entery, &[])?;
+    let recurring = RecurringTransaction {
+        id: Uuid::new_v4().to_string(),
+        entery: NewFinanceEntery {
+            date: None,
+            ..recurring.entery
+        },
+        last: None,
+        ..recurring
+    };
+    fs::create_dir_all(user_dir(user))?;
+    let path = user_file(user, RECURRING_FILE);
+    journal::create_file(&path)?;
+    journal::modify_file(&path, |content| {
+        let mut list = parse_recurring(content)?;
+        list.push(recurring.clone());
+        Ok((serde_json::to_string_pretty(&list)?, ()))
+    })?;
+    debug!(
+        "User '{}' created recurring transaction '{}'",
+        user, recurring.id
+    );
+    Ok(recurring)
+}
+
+/// Removes the recurring transaction. Its created transactions stay in the journal.
+pub fn delete_recurring(user: &str, id: &str) -> Result<Vec<RecurringTransaction>, ServiceError> {
+    let not_found = || ServiceError::NotFound(format!("No recurring transaction '{}'.", id));
+    let path = user_file(user, RECURRING_FILE);
+    if !Path::new(&path).exists() {
+        return Err(not_found());
+    }
+    journal::modify_file(&path, |content| {
+        let mut list = parse_recurring(content)?;
+        let count = list.len();
+        list.retain(|r| r.id != id);
+        if list.len() == count {
+            return Err(not_found());
+        }
+        Ok((serde_json::to_string_pretty(&list)?, list))
+    })
+}
+
+/// Writes the occurrences up to the date into the journal and returns how many were written.
+/// Occurrences, that are in the journal already, are skipped.
+pub fn materialize(
+    user: &str,
+    recurring: &RecurringTransaction,
+    today: NaiveDate,
+) -> Result<usize, ServiceError> {
+    let mut written = 0;
+    for date in pending(recurring, today)? {
+        let key = format!("{} {}", recurring.id, format_date(date));
+        let entery = NewFinanceEntery {
+            date: Some(format_date(date)),
+            ..recurring.entery.clone()
+        };
+        let (definition, text) =
+            utils::finance_entery_text(user, &entery, &[format!("{}: {}", TAG, key)])?;
+        let path = user_file(user, &definition.path);
+        journal::create_file(&path)?;
+        let created = journal::modify_file(&path, |content| {
+            if journal::parse(content)
+                .transactions()
+                .any(|t| t.tag(TAG) == Some(key.as_str()))
+            {
+                return Ok((content.to_string(), false));
+            }
+            Ok((format!("{}{}", content, text), true))
+        })?;
+        if created {
+            written += 1;
+            check::check_written(user, &definition.path);
+        }
+        // remember the date, so the journal is not searched again
+        journal::modify_file(&user_file(user, RECURRING_FILE), |content| {
+            let mut list = parse_recurring(content)?;
+            if let Some(r) = list.iter_mut().find(|r| r.id == recurring.id) {
+                r.last = Some(format_date(date));
+            }
+            Ok((serde_json::to_string_pretty(&list)?, ()))
+        })?;
+    }
+    Ok(written)
+}
+
+/// Writes the due occurrences of all recurring transactions of the user.
+/// A failing recurring transaction does not stop the others.
+pub fn materialize_user(user: &str, today: NaiveDate) -> Result<usize, ServiceError> {
+    let mut written = 0;
+    for recurring in user_recurring(user)? {
+        match materialize(user, &recurring, today) {
+            Ok(count) => written += count,
+            Err(e) => warn!(
+                "Could not create recurring transaction '{}' of user '{}': {}",
+                recurring.id, user, e
+            ),
+        }
+    }
+    if written > 0 {
+        debug!("Created {} recurring transactions for '{}'", written, user);
+    }
+    Ok(written)
+}
+
+/// Writes the due occurrences of the users. A user, whose recurring transactions can not be
+/// read, does not stop the others.
+fn materialize_users(users: &[String], today: NaiveDate) -> usize {
+    let mut written = 0;
+    for user in users {
+        match materialize_user(user, today) {
+            Ok(count) => written += count,
+            Err(e) => warn!(
+                "Could not create recurring transactions of user '{}': {}",
+                user, e
+            ),
+        }
+    }
+    written
+}
+
+/// writes the due occurrences of every user, that has recurring transactions
+pub fn materialize_all() -> Result<usize, ServiceError> {
+    let today = chrono::Local::now().date_naive();
+    let mut users = Vec::new();
+    for entry in fs::read_dir(FILE_DIR)? {
+        let entry = entry?;
+        if entry.path().join(RECURRING_FILE).exists() {
+            users.push(entry.file_name().to_string_lossy().to_string());
+        }
+    }
+    Ok(materialize_users(&users, today))
+}
+
+/// occurrences, that are not created yet, up to `days` days after the date, sorted by date
+pub fn upcoming(
+    list: &[RecurringTransaction],
+    today: NaiveDate,
+    days: u32,
+) -> Result<Vec<Occurrence>, ServiceError> {
+    let until = today + Duration::days(days.into());
+    let mut upcoming = Vec::new();
+    for recurring in list {
+        for date in pending(recurring, until)? {
+            upcoming.push(Occurrence {
+                id: recurring.id.clone(),
+                date: format_date(date),
+                headline: recurring.entery.headline.clone(),
+                target_file: recurring.entery.target_file.clone(),
+            });
+        }
+    }
+    upcoming.sort_by(|a, b| a.date.cmp(&b.date).then_with(|| a.id.cmp(&b.id)));
+    Ok(upcoming)
+}
+
+/// upcoming occurrences of all recurring transactions of the user
+pub fn ledger_upcoming(user: &str, days: Option<u32>) -> Result<Vec<Occurrence>, ServiceError> {
+    let today = chrono::Local::now().date_naive();
+    upcoming(
+        &user_recurring(user)?,
+        today,
+        days.unwrap_or(DEFAULT_UPCOMING_DAYS),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dirs::UserDir;
+
+    fn recurring(frequency: &str, start: &str, end: Option<&str>) -> RecurringTransaction {
+        RecurringTransaction {
+            id: "rent".to_string(),
+            entery: NewFinanceEntery {
+                headline: "Miete".to_string(),
+                account_origin: "Girokonto:N2".to_string(),
+                account_target: "Ausgaben:Miete".to_string(),
+                ammount: shared::amount::Amount::parse("450€").unwrap(),
+                target_file: "Gesamt".to_string(),
+                ..NewFinanceEntery::default()
+            },
+            frequency: frequency.to_string(),
+            start: start.to_string(),
+            end: end.map(|e| e.to_string()),
+            last: None,
+        }
+    }
+
+    fn date(date: &str) -> NaiveDate {
+        parse_date(date).unwrap()
+    }
+
+    #[test]
+    fn test_occurrences() {
+        let monthly = recurring("monthly", "2022/01/31", Some("2022/05/15"));
+        let dates = occurrences(&monthly, None, date("2022/12/31"))
+            .unwrap()
+            .into_iter()
+            .map(format_date)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            dates,
+            vec!["2022/01/31", "2022/02/28", "2022/03/31", "2022/04/30"]
+        );
+        let weekly = recurring("weekly", "2022-10-03", None);
+        assert_eq!(
+            occurrences(&weekly, Some(date("2022/10/03")), date("2022/10/17")).unwrap(),
+            vec![date("2022/10/10"), date("2022/10/17")]
+        );
+        assert!(occurrences(
+            &recurring("yearly", "2022/13/01", None),
+            None,
+            date("2023/01/01")
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_materialize_once() {
+        let user = "recurring-test";
+        let _dir = UserDir::new(user);
+        let created = create_recurring(user, recurring("weekly", "2022/10/03", None)).unwrap();
+        assert!(create_recurring(user, recurring("hourly", "2022/10/03", None)).is_err());
+
+        assert_eq!(materialize_user(user, date("2022/10/17")).unwrap(), 3);
+        assert_eq!(materialize_user(user, date("2022/10/17")).unwrap(), 0);
+        // a lost `last` does not create the transactions twice
+        assert_eq!(materialize(user, &created, date("2022/10/17")).unwrap(), 0);
+        let journal = journal::read_file(&user_file(user, "gesamt.dat")).unwrap();
+        assert_eq!(journal.transactions().count(), 3);
+        assert_eq!(
+            journal.transactions().last().unwrap().tag(TAG),
+            Some(format!("{} 2022/10/17", created.id).as_str())
+        );
+
+        let stored = user_recurring(user).unwrap();
+        assert_eq!(stored[0].last, Some("2022/10/17".to_string()));
+        let upcoming = upcoming(&stored, date("2022/10/17"), 14).unwrap();
+        assert_eq!(
+            upcoming.iter().map(|o| o.date.as_str()).collect::<Vec<_>>(),
+            vec!["2022/10/24", "2022/10/31"]
+        );
+        assert!(delete_recurring(user, &created.id).unwrap().is_empty());
+        assert!(matches!(
+            delete_recurring(user, &created.id),
+            Err(ServiceError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_materialize_with_broken_user() {
+        let (broken, user) = ("recurring-broken", "recurring-all");
+        let _dirs = [UserDir::new(broken), UserDir::new(user)];
+        fs::write(user_file(broken, RECURRING_FILE), "[{ not json").unwrap();
+        create_recurring(user, recurring("weekly", "2022/10/03", None)).unwrap();
+
+        let users = vec![broken.to_string(), user.to_string()];
+        assert_eq!(materialize_users(&users, date("2022/10/17")), 3);
+        let journal = journal::read_file(&user_file(user, "gesamt.dat")).unwrap();
+        assert_eq!(journal.transactions().count(), 3);
+    }
+}
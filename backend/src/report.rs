@@ -0,0 +1,458 @@
+//! Balance reports for the summary pages, computed from the journals.
+use chrono::{Datelike, Duration, NaiveDate};
+use log::debug;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use crate::errors::ServiceError;
+use crate::journal::{self, Journal};
+use crate::journals;
+use crate::utils::user_file;
+use shared::amount::{Amount, Decimal};
+use shared::models::{HtmlSuggestion, JournalKind, LedgerReport, ReportRow, TargetSuggestion};
+
+// all targets, for witch reports can be created.
+pub const TARGETS: [&str; 3] = ["timeManagment", "finance", "music"];
+pub const TIMESPANS: [&str; 4] = ["day", "week", "month", "year"];
+// most periods before the selection, that are reported at once
+const MAX_HISTORY: u32 = 24;
+
+/// Reads all existing journals of the target, archived ones included, and returns them with the
+/// commodity of the first journal.
+pub fn target_journals(user: &str, target: &str) -> Result<(Vec<Journal>, String), ServiceError> {
+    let kind = match target {
+        "timeManagment" => JournalKind::Time,
+        "music" => JournalKind::Music,
+        "finance" => JournalKind::Finance,
+        _ => {
+            return Err(ServiceError::BadRequest(format!(
+                "Unknown target '{}'.",
+                target
+            )))
+        }
+    };
+    let definitions = journals::user_journals(user)?
+        .into_iter()
+        .filter(|j| j.kind == kind)
+        .collect::<Vec<_>>();
+    let commodity = match (kind, definitions.first()) {
+        (_, Some(definition)) => definition.commodity.clone(),
+        (JournalKind::Finance, None) => "€".to_string(),
+        (_, None) => "m".to_string(),
+    };
+    let journals = definitions
+        .iter()
+        .map(|j| user_file(user, &j.path))
+        .filter(|path| Path::new(path).exists())
+        .map(|path| journal::read_file(&path))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((journals, commodity))
+}
+
+/// accepts `2022_10_18`, `2022/10/18`, `2022-10` and `2022`
+fn parse_selection_date(date: &str) -> Option<NaiveDate> {
+    let parts = date
+        .split(['_', '/', '-'])
+        .map(|p| p.parse::<u32>().ok())
+        .collect::<Option<Vec<u32>>>()?;
+    match parts.as_slice() {
+        [year] => NaiveDate::from_ymd_opt(*year as i32, 1, 1),
+        [year, month] => NaiveDate::from_ymd_opt(*year as i32, *month, 1),
+        [year, month, day] => NaiveDate::from_ymd_opt(*year as i32, *month, *day),
+        _ => None,
+    }
+}
+
+/// returns the first day of the timespan and the first day after it.
+pub fn period(date: &str, timespan: &str) -> Result<(NaiveDate, NaiveDate), ServiceError> {
+    if timespan == "all" {
+        return Ok((NaiveDate::MIN, NaiveDate::MAX));
+    }
+    let date = parse_selection_date(date)
+        .ok_or_else(|| ServiceError::BadRequest(format!("Invalid date '{}'.", date)))?;
+    let start = period_start(date, timespan)?;
+    let end = match timespan {
+        "day" => start.checked_add_signed(Duration::days(1)),
+        "week" => start.checked_add_signed(Duration::days(7)),
+        "month" => match start.month() {
+            12 => NaiveDate::from_ymd_opt(start.year() + 1, 1, 1),
+            m => NaiveDate::from_ymd_opt(start.year(), m + 1, 1),
+        },
+        _ => NaiveDate::from_ymd_opt(start.year() + 1, 1, 1),
+    }
+    .ok_or_else(|| out_of_range(date))?;
+    Ok((start, end))
+}
+
+fn out_of_range(date: NaiveDate) -> ServiceError {
+    ServiceError::BadRequest(format!(
+        "The period of '{}' is out of range.",
+        date.format("%Y/%m/%d")
+    ))
+}
+
+/// first day of the timespan, that contains the date. Weeks start on monday.
+fn period_start(date: NaiveDate, timespan: &str) -> Result<NaiveDate, ServiceError> {
+    match timespan {
+        "day" => Ok(date),
+        "week" => date
+            .checked_sub_signed(Duration::days(date.weekday().num_days_from_monday() as i64))
+            .ok_or_else(|| out_of_range(date)),
+        "month" => Ok(date.with_day(1).unwrap()),
+        "year" => Ok(NaiveDate::from_ymd_opt(date.year(), 1, 1).unwrap()),
+        _ => Err(ServiceError::BadRequest(format!(
+            "Unknown timespan '{}'.",
+            timespan
+        ))),
+    }
+}
+
+fn parse_depth(depth: &str) -> Result<Option<usize>, ServiceError> {
+    match depth {
+        "all" | "" => Ok(None),
+        d => match d.parse::<usize>() {
+            Ok(n) if n > 0 => Ok(Some(n)),
+            _ => Err(ServiceError::BadRequest(format!("Invalid depth '{}'.", d))),
+        },
+    }
+}
+
+/// Sums up all postings between start (inclusive) and end (exclusive) per account and
+/// commodity, unlike commodities are never added. Amounts without commodity are in
+/// `commodity`. Accounts are cut after `depth` levels, every parent account holds the sum of
+/// its children.
+pub fn balance(
+    journals: &[Journal],
+    start: NaiveDate,
+    end: NaiveDate,
+    depth: Option<usize>,
+    commodity: &str,
+) -> Vec<ReportRow> {
+    let mut sums: BTreeMap<(String, String), Decimal> = BTreeMap::new();
+    let transactions = journals
+        .iter()
+        .flat_map(|j| j.transactions())
+        .filter(|t| t.date >= start && t.date < end);
+    for transaction in transactions {
+        for (posting, amounts) in transaction.postings.iter().zip(transaction.amounts()) {
+            let segments = posting.account.split(':').collect::<Vec<_>>();
+            let depth = depth.unwrap_or(segments.len()).min(segments.len());
+            for amount in amounts {
+                let amount_commodity = match amount.commodity.is_empty() {
+                    true => commodity.to_string(),
+                    false => amount.commodity,
+                };
+                for d in 1..=depth {
+                    *sums
+                        .entry((segments[..d].join(":"), amount_commodity.clone()))
+                        .or_insert(Decimal::ZERO) += amount.quantity;
+                }
+            }
+        }
+    }
+    sums.into_iter()
+        .map(|((account, commodity), balance)| ReportRow {
+            depth: account.matches(':').count() as u32 + 1,
+            account,
+            balance,
+            commodity,
+        })
+        .collect()
+}
+
+/// creates the balance report for the selection
+pub fn ledger_report(user: &str, selection: &HtmlSuggestion) -> Result<LedgerReport, ServiceError> {
+    let (journals, commodity) = target_journals(user, &selection.target)?;
+    report(&journals, &commodity, selection)
+}
+
+/// Creates the reports of the `count` periods before the selection, the oldest first.
+/// The whole journal has no periods before it.
+pub fn ledger_report_history(
+    user: &str,
+    selection: &HtmlSuggestion,
+    count: u32,
+) -> Result<Vec<LedgerReport>, ServiceError> {
+    if count > MAX_HISTORY {
+        return Err(ServiceError::BadRequest(format!(
+            "At most {} periods can be reported.",
+            MAX_HISTORY
+        )));
+    }
+    if count == 0 || selection.timespan == "all" {
+        return Ok(Vec::new());
+    }
+    let (journals, commodity) = target_journals(user, &selection.target)?;
+    let mut start = period(&selection.date, &selection.timespan)?.0;
+    let mut reports = Vec::new();
+    for _ in 0..count {
+        let previous = HtmlSuggestion {
+            date: start
+                .pred_opt()
+                .ok_or_else(|| out_of_range(start))?
+                .format("%Y_%m_%d")
+                .to_string(),
+            ..selection.clone()
+        };
+        start = period(&previous.date, &previous.timespan)?.0;
+        reports.push(report(&journals, &commodity, &previous)?);
+    }
+    reports.reverse();
+    Ok(reports)
+}
+
+fn report(
+    journals: &[Journal],
+    commodity: &str,
+    selection: &HtmlSuggestion,
+) -> Result<LedgerReport, ServiceError> {
+    let (start, end) = period(&selection.date, &selection.timespan)?;
+    let depth = parse_depth(&selection.depth)?;
+    let rows = balance(journals, start, end, depth, commodity);
+
+    // for the whole journal the first and last entery define the timespan
+    let dates = journals
+        .iter()
+        .flat_map(|j| j.transactions())
+        .map(|t| t.date)
+        .filter(|d| d >= &start && d < &end)
+        .collect::<BTreeSet<_>>();
+    let (first, last) = if selection.timespan == "all" {
+        (
+            dates.first().copied().unwrap_or(start),
+            dates.last().copied().unwrap_or(start),
+        )
+    } else {
+        (start, end - Duration::days(1))
+    };
+    debug!(
+        "Created report for '{}' with {} accounts.",
+        &selection.target,
+        rows.len()
+    );
+    Ok(LedgerReport {
+        selection: selection.clone(),
+        start: first.format("%Y/%m/%d").to_string(),
+        end: last.format("%Y/%m/%d").to_string(),
+        commodity: commodity.to_string(),
+        rows,
+    })
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// renders the report as html table like the `ledger balance` output
+pub fn render_html(report: &LedgerReport) -> String {
+    let mut html = format!(
+        "<table class=\"ledger-report\">\n<caption>{} - {}</caption>\n",
+        report.start, report.end
+    );
+    for row in &report.rows {
+        let balance = Amount::new(row.balance, &row.commodity);
+        html += &format!(
+            "<tr><td style=\"text-align: right; padding-right: 2em\">{}</td><td style=\"padding-left: {}em\">{}</td></tr>\n",
+            balance,
+            (row.depth - 1) * 2,
+            escape_html(row.account.rsplit(':').next().unwrap_or_default()),
+        );
+    }
+    html += "</table>\n";
+    html
+}
+
+/// The periods and depths, for witch the journals of the user have data.
+pub fn html_suggestion(user: &str) -> Result<Vec<TargetSuggestion>, ServiceError> {
+    let mut suggestions = Vec::new();
+    for target in TARGETS {
+        let (journals, _) = target_journals(user, target)?;
+        suggestions.push(target_suggestion(target, &journals)?);
+    }
+    Ok(suggestions)
+}
+
+fn target_suggestion(target: &str, journals: &[Journal]) -> Result<TargetSuggestion, ServiceError> {
+    let transactions = journals
+        .iter()
+        .flat_map(|j| j.transactions())
+        .collect::<Vec<_>>();
+    let max_depth = transactions
+        .iter()
+        .flat_map(|t| t.postings.iter())
+        .map(|p| p.account.matches(':').count() + 1)
+        .max()
+        .unwrap_or(0);
+    let dates = transactions.iter().map(|t| t.date).collect::<BTreeSet<_>>();
+    let mut suggestion = TargetSuggestion {
+        target: target.to_string(),
+        max_depth,
+        ..TargetSuggestion::default()
+    };
+    if dates.is_empty() {
+        return Ok(suggestion);
+    }
+    for timespan in TIMESPANS {
+        let starts = dates
+            .iter()
+            .map(|d| period_start(*d, timespan))
+            .collect::<Result<BTreeSet<_>, _>>()?;
+        suggestion.dates.insert(
+            timespan.to_string(),
+            starts
+                .iter()
+                .map(|s| s.format("%Y_%m_%d").to_string())
+                .collect(),
+        );
+    }
+    Ok(suggestion)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FINANCE_JOURNAL: &str = "
+2022/10/01\t\t\tMiete
+ \tGirokonto:N2
+ \tWohnung:Miete\t\t\t\t\t\t\t500€
+
+2022/10/02\t\t\tEinkauf
+ \tAusgaben:Essen\t\t20€
+ \tAusgaben:Haushalt\t\t5.50€
+ \tGirokonto:N2
+
+2022/11/02\t\t\tEinkauf
+ \tAusgaben:Essen\t\t20€
+ \tGirokonto:N2
+";
+
+    #[test]
+    fn test_period() {
+        let day = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        assert_eq!(
+            period("2022_10_19", "week").unwrap(),
+            (day(2022, 10, 17), day(2022, 10, 24))
+        );
+        assert_eq!(
+            period("2022_12_05", "month").unwrap(),
+            (day(2022, 12, 1), day(2023, 1, 1))
+        );
+        assert_eq!(
+            period("2022", "year").unwrap(),
+            (day(2022, 1, 1), day(2023, 1, 1))
+        );
+        assert!(period("2022_13_01", "day").is_err());
+        // the periods at the end of the calendar have no end
+        for timespan in TIMESPANS {
+            assert!(matches!(
+                period("262142_12_31", timespan),
+                Err(ServiceError::BadRequest(_))
+            ));
+        }
+        assert!(period("2022_10_01", "decade").is_err());
+    }
+
+    #[test]
+    fn test_balance() {
+        let journals = vec![journal::parse(FINANCE_JOURNAL)];
+        let (start, end) = period("2022_10_01", "month").unwrap();
+        let rows = balance(&journals, start, end, Some(1), "€");
+        assert_eq!(
+            rows,
+            vec![
+                ReportRow {
+                    account: "Ausgaben".to_string(),
+                    depth: 1,
+                    balance: Decimal::new(255, 1),
+                    commodity: "€".to_string(),
+                },
+                ReportRow {
+                    account: "Girokonto".to_string(),
+                    depth: 1,
+                    balance: Decimal::new(-5255, 1),
+                    commodity: "€".to_string(),
+                },
+                ReportRow {
+                    account: "Wohnung".to_string(),
+                    depth: 1,
+                    balance: Decimal::from(500),
+                    commodity: "€".to_string(),
+                },
+            ]
+        );
+        let rows = balance(&journals, start, end, None, "€");
+        assert_eq!(rows.len(), 7);
+        assert_eq!(rows[1].account, "Ausgaben:Essen");
+        assert_eq!(rows[1].balance, Decimal::from(20));
+    }
+
+    #[test]
+    fn test_balance_per_commodity() {
+        let journals = vec![journal::parse(
+            "2022/10/03 ETF
+ \tDepot:ETF\t\t3 ETF @ 20€
+ \tDepot:Cash\t\t10 USD
+ \tGirokonto:N2
+
+2022/10/04 Lecture
+ \tFreeTime
+ \tUni:Math\t\t1.5h
+ \tUni:Physics\t\t30m
+",
+        )];
+        let (start, end) = period("2022_10_01", "month").unwrap();
+        let rows = balance(&journals, start, end, Some(1), "€")
+            .into_iter()
+            .map(|r| (r.account, Amount::new(r.balance, &r.commodity).to_string()))
+            .collect::<Vec<_>>();
+        let row = |account: &str, amount: &str| (account.to_string(), amount.to_string());
+        assert_eq!(
+            rows,
+            vec![
+                row("Depot", "10.00 USD"),
+                row("Depot", "60.00€"),
+                row("FreeTime", "-120m"),
+                row("Girokonto", "-10.00 USD"),
+                row("Girokonto", "-60.00€"),
+                row("Uni", "120m"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_html() {
+        let report = LedgerReport {
+            selection: HtmlSuggestion::default(),
+            start: "2022/10/01".to_string(),
+            end: "2022/10/31".to_string(),
+            commodity: "€".to_string(),
+            rows: vec![ReportRow {
+                account: "Ausgaben:Essen & Trinken".to_string(),
+                depth: 2,
+                balance: Decimal::from(20),
+                commodity: "€".to_string(),
+            }],
+        };
+        let html = render_html(&report);
+        assert!(html.contains("20.00€"));
+        assert!(html.contains("Essen &amp; Trinken"));
+    }
+
+    #[test]
+    fn test_html_suggestion() {
+        let journals = vec![journal::parse(FINANCE_JOURNAL)];
+        let suggestion = target_suggestion("finance", &journals).unwrap();
+        assert_eq!(suggestion.max_depth, 2);
+        assert_eq!(suggestion.depths(), vec!["all", "1", "2"]);
+        let dates = |timespan: &str| suggestion.dates[timespan].clone();
+        assert_eq!(dates("day"), vec!["2022_10_01", "2022_10_02", "2022_11_02"]);
+        assert_eq!(dates("week"), vec!["2022_09_26", "2022_10_31"]);
+        assert_eq!(dates("month"), vec!["2022_10_01", "2022_11_01"]);
+        assert_eq!(dates("year"), vec!["2022_01_01"]);
+        // a target without enteries has no periods
+        assert!(target_suggestion("music", &[]).unwrap().dates.is_empty());
+    }
+}
@@ -0,0 +1,221 @@
+//! Minutes per account and timespan of the time journal, used for the graphs.
+use chrono::{Duration, NaiveDate};
+use log::debug;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::errors::ServiceError;
+use crate::report;
+use crate::utils;
+use shared::models::{
+    RequestTimeStatistics, ResponseTimeStatistics, TimeEnteryHistory, TimeStatisticsAccount,
+    TimeStatisticsBucket, TimeStatisticsHeadline,
+};
+
+pub const BUCKETS: [&str; 3] = ["day", "week", "month"];
+// most buckets, that are returned at once
+const MAX_BUCKETS: usize = 1000;
+
+fn parse_date(date: &str) -> Result<NaiveDate, ServiceError> {
+    NaiveDate::parse_from_str(&date.replace('-', "/"), "%Y/%m/%d")
+        .map_err(|_| ServiceError::BadRequest(format!("Invalid date '{}'.", date)))
+}
+
+fn format_date(date: NaiveDate) -> String {
+    date.format("%Y/%m/%d").to_string()
+}
+
+/// the account cut after `depth` levels
+fn account_at_depth(account: &str, depth: Option<usize>) -> String {
+    match depth {
+        Some(depth) => account.split(':').take(depth).collect::<Vec<_>>().join(":"),
+        None => account.to_string(),
+    }
+}
+
+/// Sums up the minutes of the target accounts for every bucket of the selection.
+pub fn statistics(
+    history: &[TimeEnteryHistory],
+    request: &RequestTimeStatistics,
+) -> Result<ResponseTimeStatistics, ServiceError> {
+    if !BUCKETS.contains(&request.bucket.as_str()) {
+        return Err(ServiceError::BadRequest(format!(
+            "Unknown bucket '{}'.",
+            request.bucket
+        )));
+    }
+    if request.depth == Some(0) {
+        return Err(ServiceError::BadRequest("Invalid depth '0'.".to_string()));
+    }
+    let start = parse_date(&request.start)?;
+    let end = parse_date(&request.end)?;
+    if end < start {
+        return Err(ServiceError::BadRequest(
+            "The selection has to end after it starts.".to_string(),
+        ));
+    }
+
+    // first day and first day after every bucket
+    let mut periods = Vec::new();
+    let mut current = report::period(&format_date(start), &request.bucket)?;
+    while current.0 <= end {
+        if periods.len() == MAX_BUCKETS {
+            return Err(ServiceError::BadRequest(format!(
+                "The selection has to contain at most {} buckets.",
+                MAX_BUCKETS
+            )));
+        }
+        periods.push(current);
+        current = report::period(&format_date(current.1), &request.bucket)?;
+    }
+
+    let mut sums = vec![BTreeMap::<String, (u32, BTreeMap<String, u32>)>::new(); periods.len()];
+    for entery in history {
+        let date = match NaiveDate::parse_from_str(&entery.date, "%Y/%m/%d") {
+            Ok(date) if date >= start && date <= end => date,
+            _ => continue,
+        };
+        let index = periods.partition_point(|(_, next)| *next <= date);
+        let sum = sums[index]
+            .entry(account_at_depth(&entery.account_target, request.depth))
+            .or_default();
+        sum.0 += entery.duration;
+        if request.headlines {
+            *sum.1.entry(entery.headline.clone()).or_default() += entery.duration;
+        }
+    }
+
+    let accounts = sums
+        .iter()
+        .flat_map(|s| s.keys().cloned())
+        .collect::<BTreeSet<_>>();
+    let buckets = periods
+        .iter()
+        .zip(sums)
+        .map(|((first, next), sums)| TimeStatisticsBucket {
+            start: format_date(*first.max(&start)),
+            end: format_date((*next - Duration::days(1)).min(end)),
+            accounts: sums
+                .into_iter()
+                .map(|(account, (minutes, headlines))| TimeStatisticsAccount {
+                    account,
+                    minutes,
+                    headlines: headlines
+                        .into_iter()
+                        .map(|(headline, minutes)| TimeStatisticsHeadline { headline, minutes })
+                        .collect(),
+                })
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+    debug!(
+        "Time statistics with {} buckets from {} to {}",
+        buckets.len(),
+        start,
+        end
+    );
+    Ok(ResponseTimeStatistics {
+        accounts: accounts.into_iter().collect(),
+        buckets,
+    })
+}
+
+/// statistics of the time journal of the user
+pub fn ledger_time_statistics(
+    user: &str,
+    request: &RequestTimeStatistics,
+) -> Result<ResponseTimeStatistics, ServiceError> {
+    statistics(&utils::ledger_time_history(user)?, request)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entery(date: &str, account: &str, headline: &str, duration: u32) -> TimeEnteryHistory {
+        TimeEnteryHistory {
+            date: date.to_string(),
+            account_target: account.to_string(),
+            headline: headline.to_string(),
+            duration,
+            ..TimeEnteryHistory::default()
+        }
+    }
+
+    fn request(start: &str, end: &str, bucket: &str) -> RequestTimeStatistics {
+        RequestTimeStatistics {
+            start: start.to_string(),
+            end: end.to_string(),
+            bucket: bucket.to_string(),
+            ..RequestTimeStatistics::default()
+        }
+    }
+
+    #[test]
+    fn test_weekly_statistics() {
+        let history = vec![
+            entery("2022/10/09", "Uni:Math", "Exercise", 60),
+            entery("2022/10/10", "Uni:Math", "Lecture", 90),
+            entery("2022/10/11", "Uni:Physics", "Lecture", 30),
+            entery("2022/10/12", "FreeTime:Sport", "Running", 45),
+            entery("2022/10/17", "Uni:Math", "Lecture", 90),
+            entery("2022/10/20", "Uni:Math", "Lecture", 10),
+        ];
+        let mut request = request("2022/10/10", "2022/10/18", "week");
+        request.depth = Some(1);
+        let statistics = statistics(&history, &request).unwrap();
+        assert_eq!(statistics.accounts, vec!["FreeTime", "Uni"]);
+        assert_eq!(statistics.buckets.len(), 2);
+        let week = &statistics.buckets[0];
+        assert_eq!(week.start, "2022/10/10");
+        assert_eq!(week.end, "2022/10/16");
+        assert_eq!(
+            week.accounts
+                .iter()
+                .map(|a| (a.account.as_str(), a.minutes))
+                .collect::<Vec<_>>(),
+            vec![("FreeTime", 45), ("Uni", 120)]
+        );
+        assert!(week.accounts[1].headlines.is_empty());
+        assert_eq!(statistics.buckets[1].start, "2022/10/17");
+        assert_eq!(statistics.buckets[1].end, "2022/10/18");
+        assert_eq!(statistics.buckets[1].accounts[0].minutes, 90);
+    }
+
+    #[test]
+    fn test_headlines_and_empty_buckets() {
+        let history = vec![
+            entery("2022/10/10", "Uni:Math", "Lecture", 90),
+            entery("2022/10/10", "Uni:Math", "Exercise", 60),
+            entery("2022/10/10", "Uni:Math", "Lecture", 30),
+        ];
+        let mut request = request("2022/10/09", "2022/10/11", "day");
+        request.headlines = true;
+        let statistics = statistics(&history, &request).unwrap();
+        assert_eq!(statistics.buckets.len(), 3);
+        assert!(statistics.buckets[0].accounts.is_empty());
+        assert_eq!(
+            statistics.buckets[1].accounts,
+            vec![TimeStatisticsAccount {
+                account: "Uni:Math".to_string(),
+                minutes: 180,
+                headlines: vec![
+                    TimeStatisticsHeadline {
+                        headline: "Exercise".to_string(),
+                        minutes: 60,
+                    },
+                    TimeStatisticsHeadline {
+                        headline: "Lecture".to_string(),
+                        minutes: 120,
+                    },
+                ],
+            }]
+        );
+
+        let mut invalid = request.clone();
+        invalid.bucket = "year".to_string();
+        assert!(super::statistics(&history, &invalid).is_err());
+        invalid.bucket = "day".to_string();
+        invalid.depth = Some(0);
+        assert!(super::statistics(&history, &invalid).is_err());
+    }
+}
@@ -0,0 +1,202 @@
+//! Ranking of the suggestions. Every use of a suggestion counts with a weight, that halves
+//! every `HALF_LIFE_DAYS` days, so often and recently used suggestions come first.
+//! Time suggestions weigh more, if they were used at the same time of day or weekday.
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime};
+use log::debug;
+use std::collections::BTreeMap;
+
+use crate::errors::ServiceError;
+use crate::utils;
+use shared::models::{NewFinanceEntery, TimeEnteryHistory, TimeEnterySuggestion};
+
+// suggestions, that are returned if the request does not say otherwise
+pub const DEFAULT_COUNT: usize = 50;
+const HALF_LIFE_DAYS: f64 = 30.0;
+// additional weight of a use, that started at the current time of day. It gets smaller
+// until the use is `HOUR_RANGE` hours away.
+const HOUR_AFFINITY: f64 = 1.0;
+const HOUR_RANGE: f64 = 3.0;
+// additional weight of a use on the current weekday
+const WEEKDAY_AFFINITY: f64 = 0.5;
+
+/// weight of a use at the date, 1 for today
+fn recency(date: NaiveDate, today: NaiveDate) -> f64 {
+    let age = (today - date).num_days().max(0) as f64;
+    0.5_f64.powf(age / HALF_LIFE_DAYS)
+}
+
+/// factor for uses at a similar time of day and on the same weekday
+fn affinity(date: NaiveDate, start: Option<NaiveTime>, now: NaiveDateTime) -> f64 {
+    let mut factor = 1.0;
+    if let Some(start) = start {
+        let minutes = (start - now.time()).num_minutes().abs();
+        let hours = minutes.min(24 * 60 - minutes) as f64 / 60.0;
+        factor += HOUR_AFFINITY * (1.0 - hours / HOUR_RANGE).max(0.0);
+    }
+    if date.weekday() == now.weekday() {
+        factor += WEEKDAY_AFFINITY;
+    }
+    factor
+}
+
+/// Collapses the uses with the same key and returns the `count` best suggestions, best first.
+/// A collapsed suggestion is the most recent use.
+fn rank<K: Ord, T>(uses: impl IntoIterator<Item = (K, NaiveDate, T, f64)>, count: usize) -> Vec<T> {
+    let mut candidates: BTreeMap<K, (NaiveDate, T, f64)> = BTreeMap::new();
+    for (key, date, suggestion, weight) in uses {
+        match candidates.get_mut(&key) {
+            Some(candidate) => {
+                if date >= candidate.0 {
+                    candidate.0 = date;
+                    candidate.1 = suggestion;
+                }
+                candidate.2 += weight;
+            }
+            None => {
+                candidates.insert(key, (date, suggestion, weight));
+            }
+        }
+    }
+    let mut ranked = candidates.into_values().collect::<Vec<_>>();
+    ranked.sort_by(|a, b| b.2.total_cmp(&a.2).then_with(|| b.0.cmp(&a.0)));
+    ranked.into_iter().take(count).map(|(_, s, _)| s).collect()
+}
+
+/// ranked headlines and accounts of the time history
+pub fn rank_time(
+    history: &[TimeEnteryHistory],
+    now: NaiveDateTime,
+    count: usize,
+) -> Vec<TimeEnterySuggestion> {
+    let uses = history.iter().filter_map(|entery| {
+        let date = NaiveDate::parse_from_str(&entery.date, "%Y/%m/%d").ok()?;
+        let start = entery
+            .timespan
+            .get(..5)
+            .and_then(|s| NaiveTime::parse_from_str(s, "%H:%M").ok());
+        let weight = recency(date, now.date()) * affinity(date, start, now);
+        let suggestion = TimeEnterySuggestion {
+            headline: entery.headline.clone(),
+            account_target: entery.account_target.clone(),
+        };
+        Some((
+            (entery.headline.clone(), entery.account_target.clone()),
+            date,
+            suggestion,
+            weight,
+        ))
+    });
+    rank(uses, count)
+}
+
+/// Ranked finance suggestions. Suggestions, that only differ in their ammount, are collapsed.
+pub fn rank_finance(
+    candidates: Vec<(NaiveDate, NewFinanceEntery)>,
+    today: NaiveDate,
+    count: usize,
+) -> Vec<NewFinanceEntery> {
+    let uses = candidates.into_iter().map(|(date, suggestion)| {
+        let key = (
+            suggestion.headline.clone(),
+            suggestion.account_origin.clone(),
+            suggestion.account_target.clone(),
+            suggestion.target_file.clone(),
+        );
+        (key, date, suggestion, recency(date, today))
+    });
+    rank(uses, count)
+}
+
+/// the best time suggestions for now
+pub fn ledger_time_suggestion(
+    user: &str,
+    count: Option<usize>,
+) -> Result<Vec<TimeEnterySuggestion>, ServiceError> {
+    let history = utils::ledger_time_history(user)?;
+    let suggestions = rank_time(
+        &history,
+        chrono::Local::now().naive_local(),
+        count.unwrap_or(DEFAULT_COUNT),
+    );
+    debug!("Ranked {} time enteries for '{}'", history.len(), user);
+    Ok(suggestions)
+}
+
+/// the best finance suggestions for today
+pub fn ledger_finance_suggestion(
+    user: &str,
+    count: Option<usize>,
+) -> Result<Vec<NewFinanceEntery>, ServiceError> {
+    Ok(rank_finance(
+        utils::finance_suggestion_candidates(user)?,
+        chrono::Local::now().date_naive(),
+        count.unwrap_or(DEFAULT_COUNT),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared::amount::Amount;
+
+    fn entery(date: &str, timespan: &str, headline: &str) -> TimeEnteryHistory {
+        TimeEnteryHistory {
+            date: date.to_string(),
+            timespan: timespan.to_string(),
+            headline: headline.to_string(),
+            account_target: "Education".to_string(),
+            ..TimeEnteryHistory::default()
+        }
+    }
+
+    #[test]
+    fn test_rank_time() {
+        // Tuesday morning
+        let now = NaiveDate::from_ymd_opt(2022, 10, 18)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap();
+        let history = vec![
+            entery("2022/10/17", "20:00 - 21:00", "Reading"),
+            entery("2022/10/16", "20:00 - 21:00", "Reading"),
+            entery("2022/10/11", "09:00 - 10:00", "Rust"),
+            entery("2022/10/11", "09:30 - 10:00", "Rust"),
+            entery("2022/01/04", "09:00 - 10:00", "Old"),
+        ];
+        let ranked = rank_time(&history, now, 10);
+        let headlines = ranked
+            .iter()
+            .map(|s| s.headline.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(headlines, vec!["Rust", "Reading", "Old"]);
+        assert_eq!(rank_time(&history, now, 1).len(), 1);
+
+        // in the evening the evening entery comes first
+        let evening = now.date().and_hms_opt(20, 0, 0).unwrap();
+        assert_eq!(rank_time(&history, evening, 1)[0].headline, "Reading");
+    }
+
+    #[test]
+    fn test_rank_finance() {
+        let suggestion = |headline: &str, ammount: &str| NewFinanceEntery {
+            headline: headline.to_string(),
+            account_origin: "Girokonto:N2".to_string(),
+            account_target: "Ausgaben:Essen".to_string(),
+            ammount: Amount::parse(ammount).unwrap(),
+            target_file: "Gesamt".to_string(),
+            ..NewFinanceEntery::default()
+        };
+        let date = |d: &str| NaiveDate::parse_from_str(d, "%Y/%m/%d").unwrap();
+        let candidates = vec![
+            (date("2022/10/01"), suggestion("Supermarkt", "12€")),
+            (date("2022/10/10"), suggestion("Supermarkt", "20€")),
+            (date("2022/10/05"), suggestion("Supermarkt", "8€")),
+            (date("2022/10/15"), suggestion("Restaurant", "30€")),
+        ];
+        let ranked = rank_finance(candidates, date("2022/10/18"), 10);
+        assert_eq!(ranked.len(), 2);
+        // collapsed to the most recent ammount
+        assert_eq!(ranked[0], suggestion("Supermarkt", "20€"));
+        assert_eq!(ranked[1].headline, "Restaurant");
+    }
+}
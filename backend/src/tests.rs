@@ -5,28 +5,93 @@ mod unit_tests {
     use actix_web_httpauth::middleware::HttpAuthentication;
     use diesel::{r2d2, r2d2::ConnectionManager, PgConnection};
     use dotenvy::dotenv;
-    use std::fs;
-    use std::io::Write;
+    use std::sync::OnceLock;
 
-    use crate::auth::{create_token, validator};
-    use crate::configuration::Application;
+    use crate::auth::{self, decode_jwt, validator};
+    use crate::configuration::{Application, Jwt, Passwords, Registration, TimeTracking};
     use crate::db;
+    use crate::errors::ServiceError;
     use crate::handler::*;
+    use crate::journal;
     use crate::models;
     use crate::utils;
-    use shared::auth::UserLogin;
+    use shared::auth::*;
     use shared::models::*;
 
     pub const FILE_DIR: &str = "./files";
-    pub const PATH_TIME_SPEND: &str = "time_spend.dat";
     // all finance files. First one is the default
     pub const PATH_FINANCE_FILES: [&'static str; 4] =
         ["nachhilfe.dat", "invest.dat", "rent.dat", "gesamt.dat"];
 
     pub const TEST_USER: &str = "test";
 
+    /// The signing key of the tests, the server loads it from the configuration.
+    fn init_keys() {
+        // the keys can only be loaded once, all tests use the same
+        let _ = auth::init_keys(&Jwt {
+            algorithm: "HS256".to_string(),
+            key_id: "test".to_string(),
+            secret: Some("a-secret-only-used-by-the-unit-tests".to_string()),
+            private_key_file: None,
+            public_key_file: None,
+            previous_keys: Vec::new(),
+        });
+    }
+
+    async fn create_token(
+        username: String,
+        permissions: Vec<String>,
+    ) -> Result<String, actix_web::Error> {
+        init_keys();
+        auth::create_token(username, permissions).await
+    }
+
+    /// one pool for all tests, the validator checks the revocation list in the database
+    fn test_pool() -> models::db::Pool {
+        static POOL: OnceLock<models::db::Pool> = OnceLock::new();
+        POOL.get_or_init(|| {
+            dotenv().ok();
+            let settings = Application::default();
+            let connection_manager = ConnectionManager::<PgConnection>::new(settings.database_url);
+            r2d2::Pool::builder()
+                .max_size(4)
+                .build(connection_manager)
+                .expect("Failed to create pool.")
+        })
+        .clone()
+    }
+
+    /// removes the finance transactions with the headline, that a test wrote
+    fn remove_transactions(file: &str, headline: &str) {
+        journal::modify_file(&format!("{}/{}/{}", FILE_DIR, TEST_USER, file), |content| {
+            let mut content = content.to_string();
+            loop {
+                let ledger = journal::parse(&content);
+                let span = ledger
+                    .transactions()
+                    .find(|t| t.headline == headline)
+                    .map(|t| t.span);
+                match span {
+                    Some(span) => content = ledger.remove(&span),
+                    None => return Ok((content, ())),
+                }
+            }
+        })
+        .unwrap();
+    }
+
+    /// removes the finished time enteries with the headline, that a test wrote
+    fn remove_time_enteries(headline: &str) {
+        for entery in utils::ledger_time_history(TEST_USER).unwrap() {
+            if entery.headline == headline {
+                utils::ledger_delete_time_entery(TEST_USER, &entery.id).unwrap();
+            }
+        }
+    }
+
     #[actix_web::test]
     async fn test_login() {
+        init_keys();
         dotenv().ok();
         let settings = Application::default();
         let connection_manager = ConnectionManager::<PgConnection>::new(settings.database_url);
@@ -36,6 +101,7 @@ mod unit_tests {
         let app = test::init_service(
             App::new()
                 .app_data(web::Data::new(pool.clone()))
+                .app_data(web::Data::new(Passwords::default()))
                 .route("/", web::post().to(api::login)),
         )
         .await;
@@ -46,30 +112,99 @@ mod unit_tests {
                 password: "12345678".to_owned(),
             })
             .to_request();
-        let resp = test::call_service(&app, req).await;
-        println!("{:?}", resp);
-        assert!(resp.status().is_success());
-
-        println!("Test if response was korrekt.");
-        let body_bytes = to_bytes(resp.into_body()).await.unwrap();
-        let token_str = create_token(
-            TEST_USER.to_string(),
+        let resp: UserLoginResponse = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(resp.username, TEST_USER);
+        assert!(!resp.refresh_token.is_empty());
+        let claims = decode_jwt(&resp.token).unwrap();
+        assert_eq!(claims.username, TEST_USER);
+        assert_eq!(
+            claims.permissions,
             Vec::from([
-                "ADMIN_ROLE".to_string(),
+                "GET_FINANCE_INFO".to_string(),
                 "GET_LEDGER_INFO".to_string(),
+                "GET_TIME_INFO".to_string(),
+                "SET_FINANCE_INFO".to_string(),
                 "SET_LEDGER_INFO".to_string(),
-            ]),
+                "SET_TIME_INFO".to_string(),
+            ])
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_refresh_and_logout() {
+        init_keys();
+        let auth = HttpAuthentication::bearer(validator);
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_pool()))
+                .app_data(web::Data::new(Passwords::default()))
+                .route("/login", web::post().to(api::login))
+                .route("/refresh", web::post().to(api::refresh))
+                .route("/logout", web::post().to(api::logout))
+                .service(
+                    web::scope("/auth")
+                        .wrap(auth)
+                        .route("/", web::get().to(api::get_journals)),
+                ),
         )
-        .await
-        .expect("Failed to unwrap Token");
+        .await;
+        let req = test::TestRequest::post()
+            .uri("/login")
+            .set_json(&UserLogin {
+                username: TEST_USER.to_owned(),
+                password: "12345678".to_owned(),
+            })
+            .to_request();
+        let login: UserLoginResponse = test::call_and_read_body_json(&app, req).await;
+        let refresh = |token: &str| {
+            test::TestRequest::post()
+                .uri("/refresh")
+                .set_json(&RefreshToken {
+                    refresh_token: token.to_string(),
+                })
+                .to_request()
+        };
 
-        assert_eq!(
-            body_bytes,
-            web::Bytes::from(format!(
-                r##"{{"username":"{}","token":"{}"}}"##,
-                TEST_USER, token_str
-            ))
-        );
+        // every refresh token can only be used once
+        let refreshed: UserLoginResponse =
+            test::call_and_read_body_json(&app, refresh(&login.refresh_token)).await;
+        assert_ne!(refreshed.refresh_token, login.refresh_token);
+        let resp = test::call_service(&app, refresh(&login.refresh_token)).await;
+        assert_eq!(resp.status(), 401);
+        // the reuse revoked the whole login
+        let resp = test::call_service(&app, refresh(&refreshed.refresh_token)).await;
+        assert_eq!(resp.status(), 401);
+
+        let req = test::TestRequest::post()
+            .uri("/login")
+            .set_json(&UserLogin {
+                username: TEST_USER.to_owned(),
+                password: "12345678".to_owned(),
+            })
+            .to_request();
+        let login: UserLoginResponse = test::call_and_read_body_json(&app, req).await;
+        let get_journals = |token: &str| {
+            test::TestRequest::get()
+                .uri("/auth/")
+                .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
+                .to_request()
+        };
+        let resp = test::call_service(&app, get_journals(&login.token)).await;
+        assert!(resp.status().is_success());
+        let req = test::TestRequest::post()
+            .uri("/logout")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", login.token)))
+            .set_json(&RefreshToken {
+                refresh_token: login.refresh_token.clone(),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        // the revoked access token is rejected by the validator
+        let resp = test::call_service(&app, get_journals(&login.token)).await;
+        assert_eq!(resp.status(), 401);
+        let resp = test::call_service(&app, refresh(&login.refresh_token)).await;
+        assert_eq!(resp.status(), 401);
     }
 
     #[actix_web::test]
@@ -85,10 +220,15 @@ mod unit_tests {
         let app = test::init_service(
             App::new()
                 .app_data(web::Data::new(pool.clone()))
+                .app_data(web::Data::new(Registration {
+                    open: true,
+                    admin: None,
+                }))
+                .app_data(web::Data::new(Passwords::default()))
                 .route("/", web::post().to(api::create_user)),
         )
         .await;
-        db::users::delete_user(connection, "create-test");
+        let _ = db::users::delete_user(connection, "create-test");
         let req = test::TestRequest::post()
             .uri("/")
             .set_json(&NewUser {
@@ -102,21 +242,44 @@ mod unit_tests {
 
         println!("Test if response was korrekt.");
         let body_bytes = to_bytes(resp.into_body()).await.unwrap();
-        // get userid
-        let result = db::users::get_user(connection, "create-test").unwrap();
-        db::users::delete_user(connection, "create-test");
+        db::users::delete_user(connection, "create-test").unwrap();
+        // the password hash is not part of the response
         assert_eq!(
             body_bytes,
-            web::Bytes::from(format!(
-                r##"{{"user_id":"{}","username":"create-test","password":"{}"}}"##,
-                result.user_id, result.password,
-            ))
+            web::Bytes::from(r##"{"username":"create-test","disabled":false,"roles":["editor"]}"##)
         );
     }
 
     #[actix_web::test]
-    async fn test_get_html() {
-        let token_str = create_token(
+    async fn test_closed_registration() {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_pool()))
+                .app_data(web::Data::new(Registration {
+                    open: false,
+                    admin: None,
+                }))
+                .app_data(web::Data::new(Passwords::default()))
+                .route("/", web::post().to(api::create_user)),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri("/")
+            .set_json(&NewUser {
+                username: "closed-test".to_owned(),
+                password: "12345678".to_owned(),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 403);
+    }
+
+    #[actix_web::test]
+    async fn test_admin_users() {
+        let admin = create_token(TEST_USER.to_string(), Vec::from(["ADMIN_ROLE".to_string()]))
+            .await
+            .expect("Failed to unwrap Token");
+        let viewer = create_token(
             TEST_USER.to_string(),
             Vec::from(["GET_LEDGER_INFO".to_string()]),
         )
@@ -124,20 +287,444 @@ mod unit_tests {
         .expect("Failed to unwrap Token");
 
         let auth = HttpAuthentication::bearer(validator);
-        let app = test::init_service(App::new().wrap(auth).route(
-            "/get_{target}/depth_{depth}/timeframe_{timeframe}/timepoint_{timepoint}.json",
-            web::get().to(api::get_html),
-        ))
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_pool()))
+                .app_data(web::Data::new(Passwords::default()))
+                .route("/login", web::post().to(api::login))
+                .service(
+                    web::scope("/auth")
+                        .wrap(auth)
+                        .route("/users", web::get().to(api::get_users))
+                        .route("/create", web::post().to(api::set_user_create))
+                        .route("/disable", web::post().to(api::set_user_disable))
+                        .route("/delete", web::post().to(api::set_user_delete))
+                        .route("/reset", web::post().to(api::set_user_reset)),
+                ),
+        )
+        .await;
+        let post = |uri: &str, token: &str, body: serde_json::Value| {
+            test::TestRequest::post()
+                .uri(uri)
+                .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
+                .set_json(body)
+                .to_request()
+        };
+        let login = |password: &str| {
+            test::TestRequest::post()
+                .uri("/login")
+                .set_json(&UserLogin {
+                    username: "admin-test".to_owned(),
+                    password: password.to_owned(),
+                })
+                .to_request()
+        };
+        let _ = db::users::delete_user(&mut test_pool().get().unwrap(), "admin-test");
+
+        // only admins manage the users
+        let resp = test::call_service(
+            &app,
+            post(
+                "/auth/delete",
+                &viewer,
+                serde_json::json!({"username": "admin-test"}),
+            ),
+        )
         .await;
+        assert_eq!(resp.status(), 403);
+
+        let resp = test::call_service(
+            &app,
+            post(
+                "/auth/create",
+                &admin,
+                serde_json::json!({"username": "admin-test", "password": "12345678", "roles": ["unknown"]}),
+            ),
+        )
+        .await;
+        assert_eq!(resp.status(), 400);
+        let created: UserInfo = test::call_and_read_body_json(
+            &app,
+            post(
+                "/auth/create",
+                &admin,
+                serde_json::json!({"username": "admin-test", "password": "12345678", "roles": ["viewer"]}),
+            ),
+        )
+        .await;
+        assert_eq!(created.roles, vec!["viewer"]);
+
         let req = test::TestRequest::get()
-            .uri("/get_music/depth_1/timeframe_all/timepoint_0.json")
+            .uri("/auth/users")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", admin)))
+            .to_request();
+        let list: UserList = test::call_and_read_body_json(&app, req).await;
+        assert!(list.users.contains(&UserInfo {
+            username: "admin-test".to_string(),
+            disabled: false,
+            roles: vec!["viewer".to_string()],
+        }));
+
+        // disabled users can not login
+        let resp = test::call_service(
+            &app,
+            post(
+                "/auth/disable",
+                &admin,
+                serde_json::json!({"username": "admin-test", "disabled": true}),
+            ),
+        )
+        .await;
+        assert!(resp.status().is_success());
+        let resp = test::call_service(&app, login("12345678")).await;
+        assert_eq!(resp.status(), 401);
+        let resp = test::call_service(
+            &app,
+            post(
+                "/auth/disable",
+                &admin,
+                serde_json::json!({"username": "admin-test", "disabled": false}),
+            ),
+        )
+        .await;
+        assert!(resp.status().is_success());
+
+        let resp = test::call_service(
+            &app,
+            post(
+                "/auth/reset",
+                &admin,
+                serde_json::json!({"username": "admin-test", "password": "87654321"}),
+            ),
+        )
+        .await;
+        assert!(resp.status().is_success());
+        let resp = test::call_service(&app, login("12345678")).await;
+        assert_eq!(resp.status(), 401);
+        let resp = test::call_service(&app, login("87654321")).await;
+        assert!(resp.status().is_success());
+
+        // admins can not delete themselves
+        let resp = test::call_service(
+            &app,
+            post(
+                "/auth/delete",
+                &admin,
+                serde_json::json!({"username": TEST_USER}),
+            ),
+        )
+        .await;
+        assert_eq!(resp.status(), 400);
+        let resp = test::call_service(
+            &app,
+            post(
+                "/auth/delete",
+                &admin,
+                serde_json::json!({"username": "admin-test"}),
+            ),
+        )
+        .await;
+        assert!(resp.status().is_success());
+        let resp = test::call_service(
+            &app,
+            post(
+                "/auth/delete",
+                &admin,
+                serde_json::json!({"username": "admin-test"}),
+            ),
+        )
+        .await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_password_change() {
+        init_keys();
+        let passwords = Passwords {
+            min_length: 10,
+            require_digit: true,
+            ..Passwords::default()
+        };
+        let pool = test_pool();
+        let _ = db::users::delete_user(&mut pool.get().unwrap(), "password-test");
+        db::users::insert_user(
+            &mut pool.get().unwrap(),
+            "password-test",
+            "password-1234",
+            &passwords,
+        )
+        .unwrap();
+        // weak passwords are rejected
+        assert!(db::users::insert_user(
+            &mut pool.get().unwrap(),
+            "password-weak",
+            "12345678",
+            &passwords
+        )
+        .is_err());
+
+        let auth = HttpAuthentication::bearer(validator);
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(pool.clone()))
+                .app_data(web::Data::new(passwords))
+                .route("/login", web::post().to(api::login))
+                .route("/refresh", web::post().to(api::refresh))
+                .service(
+                    web::scope("/auth")
+                        .wrap(auth)
+                        .route("/", web::post().to(api::set_password_change)),
+                ),
+        )
+        .await;
+        let login = |password: &str| {
+            test::TestRequest::post()
+                .uri("/login")
+                .set_json(&UserLogin {
+                    username: "password-test".to_owned(),
+                    password: password.to_owned(),
+                })
+                .to_request()
+        };
+        let change = |token: &str, current: &str, new: &str| {
+            test::TestRequest::post()
+                .uri("/auth/")
+                .insert_header((AUTHORIZATION, format!("Bearer {}", token)))
+                .set_json(&PasswordChange {
+                    current_password: current.to_owned(),
+                    new_password: new.to_owned(),
+                })
+                .to_request()
+        };
+        let first: UserLoginResponse =
+            test::call_and_read_body_json(&app, login("password-1234")).await;
+
+        let resp = test::call_service(
+            &app,
+            change(&first.token, "wrong-password", "password-5678"),
+        )
+        .await;
+        assert_eq!(resp.status(), 401);
+        let resp = test::call_service(&app, change(&first.token, "password-1234", "short1")).await;
+        assert_eq!(resp.status(), 400);
+        let changed: UserLoginResponse = test::call_and_read_body_json(
+            &app,
+            change(&first.token, "password-1234", "password-5678"),
+        )
+        .await;
+
+        // the other logins are revoked
+        let resp = test::call_service(
+            &app,
+            test::TestRequest::post()
+                .uri("/refresh")
+                .set_json(&RefreshToken {
+                    refresh_token: first.refresh_token,
+                })
+                .to_request(),
+        )
+        .await;
+        assert_eq!(resp.status(), 401);
+        let resp =
+            test::call_service(&app, change(&first.token, "password-5678", "password-9012")).await;
+        assert_eq!(resp.status(), 401);
+        let resp = test::call_service(&app, login("password-1234")).await;
+        assert_eq!(resp.status(), 401);
+        let resp = test::call_service(&app, login("password-5678")).await;
+        assert!(resp.status().is_success());
+        assert!(!changed.token.is_empty());
+
+        db::users::delete_user(&mut pool.get().unwrap(), "password-test").unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_grant_admin() {
+        let pool = test_pool();
+        let connection = &mut pool.get().unwrap();
+        let _ = db::users::delete_user(connection, "grant-test");
+        db::users::insert_user(
+            connection,
+            "grant-test",
+            "password-1234",
+            &Passwords::default(),
+        )
+        .unwrap();
+        let permissions = db::roles::user_permissions(connection, "grant-test").unwrap();
+        assert!(!permissions.contains(&"ADMIN_ROLE".to_string()));
+
+        db::roles::grant_admin(connection, "grant-test").unwrap();
+        let permissions = db::roles::user_permissions(connection, "grant-test").unwrap();
+        assert!(permissions.contains(&"ADMIN_ROLE".to_string()));
+        assert!(matches!(
+            db::roles::grant_admin(connection, "grant-test-unknown"),
+            Err(ServiceError::NotFound(_))
+        ));
+        db::users::delete_user(connection, "grant-test").unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_password_rehash_at_login() {
+        let mut passwords = Passwords::default();
+        let pool = test_pool();
+        let connection = &mut pool.get().unwrap();
+        let _ = db::users::delete_user(connection, "rehash-test");
+        db::users::insert_user(connection, "rehash-test", "password-1234", &passwords).unwrap();
+        let old_hash = db::users::get_user(connection, "rehash-test")
+            .unwrap()
+            .password;
+
+        passwords.argon2_iterations += 1;
+        assert!(
+            db::users::check_login(connection, "rehash-test", "password-1234", &passwords).unwrap()
+        );
+        let new_hash = db::users::get_user(connection, "rehash-test")
+            .unwrap()
+            .password;
+        assert_ne!(old_hash, new_hash);
+        assert!(!utils::needs_rehash(&new_hash, &passwords).unwrap());
+        // a wrong password does not change the hash
+        passwords.argon2_iterations += 1;
+        assert!(
+            !db::users::check_login(connection, "rehash-test", "wrong-password", &passwords)
+                .unwrap()
+        );
+        assert_eq!(
+            db::users::get_user(connection, "rehash-test")
+                .unwrap()
+                .password,
+            new_hash
+        );
+        db::users::delete_user(connection, "rehash-test").unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_get_html() {
+        let token_str = create_token(
+            TEST_USER.to_string(),
+            Vec::from(["GET_LEDGER_INFO".to_string(), "GET_TIME_INFO".to_string()]),
+        )
+        .await
+        .expect("Failed to unwrap Token");
+
+        let auth = HttpAuthentication::bearer(validator);
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_pool()))
+                .wrap(auth)
+                .route("/", web::post().to(api::get_html)),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri("/")
             .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .set_json(&HtmlSuggestion {
+                target: "timeManagment".to_string(),
+                date: "2022_10_10".to_string(),
+                timespan: "month".to_string(),
+                depth: "all".to_string(),
+            })
             .to_request();
         let resp = test::call_service(&app, req).await;
         println!("Valid Request {:?}", resp);
         assert!(resp.status().is_success());
     }
 
+    #[actix_web::test]
+    async fn test_get_report() {
+        let token_str = create_token(
+            TEST_USER.to_string(),
+            Vec::from([
+                "GET_LEDGER_INFO".to_string(),
+                "GET_FINANCE_INFO".to_string(),
+            ]),
+        )
+        .await
+        .expect("Failed to unwrap Token");
+
+        let auth = HttpAuthentication::bearer(validator);
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_pool()))
+                .wrap(auth)
+                .route("/", web::post().to(api::get_report)),
+        )
+        .await;
+        let mut request = RequestReport {
+            selection: HtmlSuggestion {
+                target: "finance".to_string(),
+                date: "2022_10_01".to_string(),
+                timespan: "year".to_string(),
+                depth: "1".to_string(),
+            },
+            html: false,
+            history: 2,
+        };
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .set_json(&request)
+            .to_request();
+        let resp: ResponseReport = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.html.is_none());
+        assert_eq!(resp.report.start, "2022/01/01");
+        assert_eq!(resp.report.end, "2022/12/31");
+        assert_eq!(resp.history.len(), 2);
+        assert_eq!(resp.history[0].start, "2020/01/01");
+        assert_eq!(resp.history[1].end, "2021/12/31");
+
+        request.selection.timespan = "decade".to_string();
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .set_json(&request)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_forbidden_report() {
+        let token_str = create_token(
+            TEST_USER.to_string(),
+            Vec::from(["GET_LEDGER_INFO".to_string(), "GET_TIME_INFO".to_string()]),
+        )
+        .await
+        .expect("Failed to unwrap Token");
+
+        let auth = HttpAuthentication::bearer(validator);
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_pool()))
+                .wrap(auth)
+                .route("/", web::post().to(api::get_report))
+                .route("/budgets", web::post().to(api::get_budgets)),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .set_json(&RequestReport {
+                selection: HtmlSuggestion {
+                    target: "finance".to_string(),
+                    date: "2022_10_01".to_string(),
+                    timespan: "year".to_string(),
+                    depth: "1".to_string(),
+                },
+                html: false,
+                history: 0,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 403);
+
+        let req = test::TestRequest::post()
+            .uri("/budgets")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 403);
+    }
+
     #[actix_web::test]
     async fn test_get_html_suggestion() {
         let token_str = create_token(
@@ -150,6 +737,7 @@ mod unit_tests {
         let auth = HttpAuthentication::bearer(validator);
         let app = test::init_service(
             App::new()
+                .app_data(web::Data::new(test_pool()))
                 .wrap(auth)
                 .route("/", web::get().to(api::get_html_suggetstions)),
         )
@@ -166,7 +754,7 @@ mod unit_tests {
     async fn test_get_time_suggestion() {
         let token_str = create_token(
             TEST_USER.to_string(),
-            Vec::from(["GET_LEDGER_INFO".to_string()]),
+            Vec::from(["GET_TIME_INFO".to_string()]),
         )
         .await
         .expect("Failed to unwrap Token");
@@ -174,6 +762,7 @@ mod unit_tests {
         let auth = HttpAuthentication::bearer(validator);
         let app = test::init_service(
             App::new()
+                .app_data(web::Data::new(test_pool()))
                 .wrap(auth)
                 .route("/", web::get().to(api::get_time_suggetstions)),
         )
@@ -185,18 +774,25 @@ mod unit_tests {
         let resp = test::call_service(&app, req).await;
         println!("Valid Request {:?}", resp);
         assert!(resp.status().is_success());
+
+        let req = test::TestRequest::get()
+            .uri("/?limit=3")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .to_request();
+        let resp: HeadlineSuggestion = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.suggestions.len() <= 3);
     }
 
     #[actix_web::test]
     async fn test_set_time_start() {
         let token_str = create_token(
             TEST_USER.to_string(),
-            Vec::from(["SET_LEDGER_INFO".to_string()]),
+            Vec::from(["SET_TIME_INFO".to_string()]),
         )
         .await
         .expect("Failed to unwrap Token");
         let start_entery = shared::models::StartTimeEntery {
-            headline: "Carlos is programming".to_owned(),
+            headline: "Carlos is starting".to_owned(),
             account_origin: "FreeTime".to_owned(),
             account_target: "EducationRust".to_owned(),
             duration: None,
@@ -207,6 +803,7 @@ mod unit_tests {
         let auth = HttpAuthentication::bearer(validator);
         let app = test::init_service(
             App::new()
+                .app_data(web::Data::new(test_pool()))
                 .wrap(auth)
                 .route("/", web::post().to(api::set_time_entery_start)),
         )
@@ -216,29 +813,35 @@ mod unit_tests {
             .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
             .set_json(&start_entery)
             .to_request();
-        let resp = test::call_service(&app, req).await;
-        println!("Valid Request {:?}", resp);
-        assert!(resp.status().is_success());
-        let remove_line = utils::ledger_start_time_entery(TEST_USER, start_entery).unwrap();
+        let resp: ResponseTimeEnteryStart = test::call_and_read_body_json(&app, req).await;
+        // the returned id addresses the started entery
+        let running = utils::ledger_get_running_time_entery(TEST_USER).unwrap();
+        assert_eq!(running[&resp.id].headline, start_entery.headline);
 
         //remove added line
-        let ledger =
-            fs::read_to_string(format!("{}/{}/{}", FILE_DIR, TEST_USER, PATH_TIME_SPEND)).unwrap();
-        fs::File::create(format!("{}/{}/{}", FILE_DIR, TEST_USER, PATH_TIME_SPEND))
-            .unwrap()
-            .write(
-                ledger
-                    .replace(&format!("{}\n", &remove_line), "")
-                    .as_bytes(),
-            )
-            .unwrap();
+        utils::ledger_kill_time_entery(TEST_USER, &resp.id).unwrap();
+
+        // an entery with duration is written finished
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .set_json(&StartTimeEntery {
+                duration: Some(10),
+                ..start_entery.clone()
+            })
+            .to_request();
+        let resp: ResponseTimeEnteryStart = test::call_and_read_body_json(&app, req).await;
+        let history = utils::ledger_time_history(TEST_USER).unwrap();
+        let created = history.iter().find(|e| e.id == resp.id).unwrap();
+        assert_eq!(created.headline, start_entery.headline);
+        utils::ledger_delete_time_entery(TEST_USER, &resp.id).unwrap();
     }
 
     #[actix_web::test]
     async fn test_get_time_running() {
         let token_str = create_token(
             TEST_USER.to_string(),
-            Vec::from(["GET_LEDGER_INFO".to_string()]),
+            Vec::from(["GET_TIME_INFO".to_string()]),
         )
         .await
         .expect("Failed to unwrap Token");
@@ -246,6 +849,7 @@ mod unit_tests {
         let auth = HttpAuthentication::bearer(validator);
         let app = test::init_service(
             App::new()
+                .app_data(web::Data::new(test_pool()))
                 .wrap(auth)
                 .route("/", web::get().to(api::get_time_entery_running)),
         )
@@ -264,30 +868,32 @@ mod unit_tests {
     async fn test_set_time_stop() {
         let token_str = create_token(
             TEST_USER.to_string(),
-            Vec::from(["SET_LEDGER_INFO".to_string()]),
+            Vec::from(["SET_TIME_INFO".to_string()]),
         )
         .await
         .expect("Failed to unwrap Token");
         let start_entery = shared::models::StartTimeEntery {
-            headline: "Carlos is programming".to_owned(),
+            headline: "Carlos is stopping".to_owned(),
             account_origin: "FreeTime".to_owned(),
             account_target: "EducationRust".to_owned(),
             duration: None,
             date: None,
             offset: None,
         };
-        let remove_line = utils::ledger_start_time_entery(TEST_USER, start_entery).unwrap();
+        let id = utils::ledger_start_time_entery(TEST_USER, start_entery).unwrap();
         //TODO find error
         let new_entery = utils::ledger_get_running_time_entery(TEST_USER)
             .unwrap()
-            .get(&remove_line)
+            .get(&id)
             .unwrap()
             .clone();
 
         let auth = HttpAuthentication::bearer(validator);
         let app = test::init_service(
             App::new()
+                .app_data(web::Data::new(test_pool()))
                 .wrap(auth)
+                .app_data(web::Data::new(TimeTracking::default()))
                 .route("/", web::post().to(api::set_time_entery_stop)),
         )
         .await;
@@ -295,7 +901,7 @@ mod unit_tests {
             .uri("/")
             .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
             .set_json(&StopLedgerTimeEntery {
-                remove_line: remove_line.clone(),
+                id: id.clone(),
                 new_entery: new_entery.clone(),
             })
             .to_request();
@@ -303,42 +909,202 @@ mod unit_tests {
         println!("Valid Request {:?}", resp);
         assert!(resp.status().is_success());
 
-        //remove added line
-        let ledger =
-            fs::read_to_string(format!("{}/{}/{}", FILE_DIR, TEST_USER, PATH_TIME_SPEND)).unwrap();
-        fs::File::create(format!("{}/{}/{}", FILE_DIR, TEST_USER, PATH_TIME_SPEND))
-            .unwrap()
-            .write(
-                ledger
-                    .replace(
-                        &utils::ledger_create_time_entery(TEST_USER, new_entery).unwrap(),
-                        "",
-                    )
-                    .as_bytes(),
-            )
-            .unwrap();
+        // the running entery is gone
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .set_json(&StopLedgerTimeEntery {
+                id,
+                new_entery: new_entery.clone(),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+
+        remove_time_enteries("Carlos is stopping");
     }
 
     #[actix_web::test]
-    async fn test_set_finance_create() {
+    async fn test_set_time_update_and_delete() {
         let token_str = create_token(
             TEST_USER.to_string(),
-            Vec::from(["SET_LEDGER_INFO".to_string()]),
+            Vec::from(["SET_TIME_INFO".to_string()]),
         )
         .await
         .expect("Failed to unwrap Token");
-        let new_entery = shared::models::NewFinanceEntery {
-            headline: "Carlos is programming".to_owned(),
-            account_origin: "FreeTime".to_owned(),
-            account_target: "Girokonto:N2".to_owned(),
-            ammount: 10 as f32,
+        utils::ledger_create_time_entery(
+            TEST_USER,
+            NewTimeEntery {
+                headline: "Carlos is editing".to_owned(),
+                account_origin: "FreeTime".to_owned(),
+                account_target: "EducationRust".to_owned(),
+                duration: 10,
+                date: Some("2022/10/06".to_string()),
+                offset: Some(-60 * 24),
+            },
+        )
+        .unwrap();
+        let entery = utils::ledger_time_history(TEST_USER)
+            .unwrap()
+            .into_iter()
+            .find(|e| e.headline == "Carlos is editing")
+            .unwrap();
+
+        let auth = HttpAuthentication::bearer(validator);
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_pool()))
+                .wrap(auth)
+                .route("/update", web::post().to(api::set_time_entery_update))
+                .route("/delete", web::post().to(api::set_time_entery_delete)),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri("/update")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .set_json(&TimeEnteryHistory {
+                headline: "Carlos was editing".to_string(),
+                ..entery.clone()
+            })
+            .to_request();
+        let resp: ResponseTimeEnteryUpdate = test::call_and_read_body_json(&app, req).await;
+        let updated = resp.entery;
+        assert_eq!(updated.headline, "Carlos was editing");
+        // the id tag is kept
+        assert_eq!(updated.id, entery.id);
+        assert!(resp.diagnostics.is_empty());
+
+        let req = test::TestRequest::post()
+            .uri("/delete")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .set_json(&DeleteTimeEntery {
+                id: updated.id.clone(),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+
+        let req = test::TestRequest::post()
+            .uri("/delete")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .set_json(&DeleteTimeEntery { id: updated.id })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_get_time_analysis() {
+        let token_str = create_token(
+            TEST_USER.to_string(),
+            Vec::from(["GET_TIME_INFO".to_string()]),
+        )
+        .await
+        .expect("Failed to unwrap Token");
+        let auth = HttpAuthentication::bearer(validator);
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_pool()))
+                .wrap(auth)
+                .route("/", web::post().to(api::get_time_analysis)),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .set_json(&RequestTimeAnalysis {
+                date_from: "2022/10/10".to_string(),
+                date_to: "2022/10/11".to_string(),
+                filler_account: Some("FreeTime".to_string()),
+                ..RequestTimeAnalysis::default()
+            })
+            .to_request();
+        let analysis: ResponseTimeAnalysis = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(analysis.days.len(), 2);
+        assert!(!analysis.fillers.is_empty());
+
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .set_json(&RequestTimeAnalysis {
+                date_from: "2022/10/10".to_string(),
+                date_to: "yesterday".to_string(),
+                ..RequestTimeAnalysis::default()
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_get_time_statistics() {
+        let token_str = create_token(
+            TEST_USER.to_string(),
+            Vec::from(["GET_TIME_INFO".to_string()]),
+        )
+        .await
+        .expect("Failed to unwrap Token");
+        let auth = HttpAuthentication::bearer(validator);
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_pool()))
+                .wrap(auth)
+                .route("/", web::post().to(api::get_time_statistics)),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .set_json(&RequestTimeStatistics {
+                start: "2022/10/01".to_string(),
+                end: "2022/10/31".to_string(),
+                bucket: "week".to_string(),
+                depth: Some(1),
+                headlines: true,
+            })
+            .to_request();
+        let statistics: ResponseTimeStatistics = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(statistics.buckets.len(), 6);
+        assert_eq!(statistics.buckets[0].start, "2022/10/01");
+        assert_eq!(statistics.buckets[5].end, "2022/10/31");
+        assert!(statistics.accounts.iter().all(|a| !a.contains(':')));
+
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .set_json(&RequestTimeStatistics {
+                start: "2022/10/01".to_string(),
+                end: "2022/10/31".to_string(),
+                bucket: "decade".to_string(),
+                ..RequestTimeStatistics::default()
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_set_finance_create() {
+        let token_str = create_token(
+            TEST_USER.to_string(),
+            Vec::from(["SET_FINANCE_INFO".to_string()]),
+        )
+        .await
+        .expect("Failed to unwrap Token");
+        let new_entery = shared::models::NewFinanceEntery {
+            headline: "Carlos is creating".to_owned(),
+            account_origin: "FreeTime".to_owned(),
+            account_target: "Girokonto:N2".to_owned(),
+            ammount: shared::amount::Amount::parse("10€").unwrap(),
             date: None,
-            target_file: "Finance".to_string(),
+            target_file: "Gesamt".to_string(),
+            postings: Vec::new(),
         };
 
         let auth = HttpAuthentication::bearer(validator);
         let app = test::init_service(
             App::new()
+                .app_data(web::Data::new(test_pool()))
                 .wrap(auth)
                 .route("/", web::post().to(api::set_finance_entery_create)),
         )
@@ -351,21 +1117,328 @@ mod unit_tests {
         let resp = test::call_service(&app, req).await;
         println!("Valid Request {:?}", resp);
         assert!(resp.status().is_success());
-        let remove_line = utils::ledger_create_finance_entery(TEST_USER, new_entery).unwrap();
+        remove_transactions(PATH_FINANCE_FILES[3], "Carlos is creating");
+    }
 
-        //remove added line
-        let ledger = fs::read_to_string(format!(
-            "{}/{}/{}",
-            FILE_DIR, TEST_USER, PATH_FINANCE_FILES[0]
-        ))
-        .unwrap();
-        fs::File::create(format!(
-            "{}/{}/{}",
-            FILE_DIR, TEST_USER, PATH_FINANCE_FILES[0]
-        ))
-        .unwrap()
-        .write(ledger.replace(&format!("{}", &remove_line), "").as_bytes())
-        .unwrap();
+    #[actix_web::test]
+    async fn test_get_budget_report() {
+        let token_str = create_token(
+            TEST_USER.to_string(),
+            Vec::from(["GET_FINANCE_INFO".to_string()]),
+        )
+        .await
+        .expect("Failed to unwrap Token");
+
+        let auth = HttpAuthentication::bearer(validator);
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_pool()))
+                .wrap(auth)
+                .route("/", web::post().to(api::get_budget_report)),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .set_json(&RequestBudgetReport { periods: Some(3) })
+            .to_request();
+        let resp: ResponseBudgetReport = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.budgets.iter().all(|b| b.periods.len() <= 3));
+    }
+
+    #[actix_web::test]
+    async fn test_get_recurring_upcoming() {
+        let token_str = create_token(
+            TEST_USER.to_string(),
+            Vec::from(["GET_FINANCE_INFO".to_string()]),
+        )
+        .await
+        .expect("Failed to unwrap Token");
+
+        let auth = HttpAuthentication::bearer(validator);
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_pool()))
+                .wrap(auth)
+                .route("/", web::post().to(api::get_recurring_upcoming)),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .set_json(&RequestUpcoming { days: Some(14) })
+            .to_request();
+        let resp: ResponseUpcoming = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.occurrences.windows(2).all(|o| o[0].date <= o[1].date));
+    }
+
+    #[actix_web::test]
+    async fn test_get_time_history() {
+        let token_str = create_token(
+            TEST_USER.to_string(),
+            Vec::from(["GET_TIME_INFO".to_string()]),
+        )
+        .await
+        .expect("Failed to unwrap Token");
+
+        let auth = HttpAuthentication::bearer(validator);
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_pool()))
+                .wrap(auth)
+                .route("/", web::get().to(api::get_time_history)),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri("/?sort=desc&limit=5&offset=0")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .to_request();
+        let resp: ResponseTimeEnteryHistory = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.history.len() <= 5);
+        assert!(resp.history.len() <= resp.total);
+        assert!(resp.history.windows(2).all(|e| e[0].date >= e[1].date));
+
+        let req = test::TestRequest::get()
+            .uri("/?sort=random")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_get_export_finance() {
+        let token_str = create_token(
+            TEST_USER.to_string(),
+            Vec::from(["GET_FINANCE_INFO".to_string()]),
+        )
+        .await
+        .expect("Failed to unwrap Token");
+
+        let auth = HttpAuthentication::bearer(validator);
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_pool()))
+                .wrap(auth)
+                .route("/", web::post().to(api::get_export_finance)),
+        )
+        .await;
+        let mut request = RequestExport {
+            format: "csv".to_string(),
+            start: Some("2022/01/01".to_string()),
+            end: None,
+            account: None,
+            journals: Vec::new(),
+        };
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .set_json(&request)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        let body_bytes = to_bytes(resp.into_body()).await.unwrap();
+        assert!(body_bytes.starts_with(b"date,journal,headline,account"));
+
+        request.format = "json".to_string();
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .set_json(&request)
+            .to_request();
+        let resp: ResponseFinanceExport = test::call_and_read_body_json(&app, req).await;
+        assert!(resp
+            .transactions
+            .iter()
+            .all(|t| t.date.as_str() >= "2022/01/01"));
+
+        request.journals = vec!["Unknown".to_string()];
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .set_json(&request)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_get_import_preview() {
+        let token_str = create_token(
+            TEST_USER.to_string(),
+            Vec::from(["GET_FINANCE_INFO".to_string()]),
+        )
+        .await
+        .expect("Failed to unwrap Token");
+
+        let auth = HttpAuthentication::bearer(validator);
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_pool()))
+                .wrap(auth)
+                .route("/", web::post().to(api::get_import_preview)),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .set_json(&RequestImportPreview {
+                profile: "Unknown Bank".to_string(),
+                target_file: "Gesamt".to_string(),
+                content: "Datum;Betrag\n".to_string(),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_get_check() {
+        let token_str = create_token(
+            TEST_USER.to_string(),
+            Vec::from([
+                "GET_FINANCE_INFO".to_string(),
+                "GET_LEDGER_INFO".to_string(),
+                "GET_TIME_INFO".to_string(),
+            ]),
+        )
+        .await
+        .expect("Failed to unwrap Token");
+
+        let auth = HttpAuthentication::bearer(validator);
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_pool()))
+                .wrap(auth)
+                .route("/", web::get().to(api::get_check)),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri("/")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .to_request();
+        let resp: ResponseCheck = test::call_and_read_body_json(&app, req).await;
+        assert!(resp
+            .diagnostics
+            .iter()
+            .all(|d| d.severity != Severity::Error));
+    }
+
+    #[actix_web::test]
+    async fn test_get_account_tree() {
+        let token_str = create_token(
+            TEST_USER.to_string(),
+            Vec::from([
+                "GET_FINANCE_INFO".to_string(),
+                "GET_LEDGER_INFO".to_string(),
+                "GET_TIME_INFO".to_string(),
+            ]),
+        )
+        .await
+        .expect("Failed to unwrap Token");
+
+        let auth = HttpAuthentication::bearer(validator);
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_pool()))
+                .wrap(auth)
+                .route("/", web::get().to(api::get_account_tree)),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri("/")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .to_request();
+        let resp: AccountTree = test::call_and_read_body_json(&app, req).await;
+        assert!(!resp.accounts.is_empty());
+        assert!(resp
+            .accounts
+            .iter()
+            .all(|n| n.usage >= n.children.iter().map(|c| c.usage).sum()));
+
+        // the time and finance journals need their own permission
+        let token_str = create_token(
+            TEST_USER.to_string(),
+            Vec::from(["GET_LEDGER_INFO".to_string()]),
+        )
+        .await
+        .expect("Failed to unwrap Token");
+        let req = test::TestRequest::get()
+            .uri("/")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .to_request();
+        let resp: AccountTree = test::call_and_read_body_json(&app, req).await;
+        assert!(resp.accounts.is_empty());
+    }
+
+    #[actix_web::test]
+    async fn test_get_and_archive_journals() {
+        let token_str = create_token(
+            TEST_USER.to_string(),
+            Vec::from([
+                "GET_FINANCE_INFO".to_string(),
+                "GET_LEDGER_INFO".to_string(),
+                "SET_LEDGER_INFO".to_string(),
+            ]),
+        )
+        .await
+        .expect("Failed to unwrap Token");
+
+        let auth = HttpAuthentication::bearer(validator);
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(test_pool()))
+                .wrap(auth)
+                .route("/get", web::get().to(api::get_journals))
+                .route("/create", web::post().to(api::set_journal_create))
+                .route("/archive", web::post().to(api::set_journal_archive)),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri("/get")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .to_request();
+        let resp: JournalList = test::call_and_read_body_json(&app, req).await;
+        assert!(resp
+            .journals
+            .iter()
+            .any(|j| j.name == "Gesamt" && j.kind == JournalKind::Finance));
+        assert!(resp.journals.iter().all(|j| j.kind != JournalKind::Time));
+
+        // finance journals are only created and archived with `SET_FINANCE_INFO`
+        let req = test::TestRequest::post()
+            .uri("/create")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .set_json(&JournalDefinition {
+                name: "Forbidden".to_string(),
+                path: "forbidden.dat".to_string(),
+                commodity: "€".to_string(),
+                kind: JournalKind::Finance,
+                archived: false,
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 403);
+        let req = test::TestRequest::post()
+            .uri("/archive")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .set_json(&ArchiveJournal {
+                name: "Gesamt".to_string(),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 403);
+
+        let req = test::TestRequest::post()
+            .uri("/archive")
+            .insert_header((AUTHORIZATION, format!("Bearer {}", token_str)))
+            .set_json(&ArchiveJournal {
+                name: "Unknown".to_string(),
+            })
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        println!("Invalid Request {:?}", resp);
+        assert_eq!(resp.status(), 404);
     }
     //TODO add finance test
     //TODO add history test.
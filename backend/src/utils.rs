@@ -1,36 +1,151 @@
 use argon2::{
     password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
-    Argon2,
+    Algorithm, Argon2, Params, Version,
 };
 use chrono::*;
-use glob::glob;
 use log::debug;
 use regex::Regex;
+use shared::models::JournalKind;
 use std::collections::BTreeMap;
-use std::fs;
-use std::io::Write;
-use std::iter::zip;
-use std::path::Path;
 
+use crate::accounts;
+use crate::check;
+use crate::configuration::Passwords;
 use crate::errors::ServiceError;
+use crate::journal;
+use crate::journals;
 
 pub const FILE_DIR: &str = "./files";
 pub const PATH_TIME_SPEND: &str = "time_spend.dat";
-// all finance files. First one is the default
-pub const PATH_FINANCE_FILES: [&'static str; 4] =
-    ["gesamt.dat", "nachhilfe.dat", "invest.dat", "rent.dat"];
-// the display names of finance files. They are matched by index with the PATH_FINANCE_FILES.
-pub const NAME_FINANCE: [&'static str; 4] = ["Gesamt", "Nachhilfe", "Invest", "Wohnung"];
-
-///Hashes password with the same settings that are used in data table
-pub fn hash_password(password: &str) -> Result<String, ServiceError> {
+pub const PATH_MUSIC: &str = "music.dat";
+
+/// directory with the files of the user
+pub fn user_dir(user: &str) -> String {
+    #[cfg(test)]
+    if let Some(dir) = test_dirs::get(user) {
+        return dir;
+    }
+    format!("{}/{}", FILE_DIR, user)
+}
+
+/// path of a file in the directory of the user
+pub fn user_file(user: &str, file: &str) -> String {
+    format!("{}/{}", user_dir(user), file)
+}
+
+/// Temporary directories of the test users, so the tests do not write into `FILE_DIR`.
+#[cfg(test)]
+pub mod test_dirs {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use tempfile::TempDir;
+
+    static DIRS: Mutex<Option<HashMap<String, String>>> = Mutex::new(None);
+
+    pub fn get(user: &str) -> Option<String> {
+        DIRS.lock().unwrap().as_ref()?.get(user).cloned()
+    }
+
+    /// The files of the user are written into an empty temporary directory, until it is dropped.
+    pub struct UserDir {
+        user: String,
+        _dir: TempDir,
+    }
+
+    impl UserDir {
+        pub fn new(user: &str) -> UserDir {
+            let dir = TempDir::new().unwrap();
+            DIRS.lock()
+                .unwrap()
+                .get_or_insert_with(HashMap::new)
+                .insert(user.to_string(), dir.path().to_string_lossy().to_string());
+            UserDir {
+                user: user.to_string(),
+                _dir: dir,
+            }
+        }
+    }
+
+    impl Drop for UserDir {
+        fn drop(&mut self) {
+            if let Some(dirs) = DIRS.lock().unwrap().as_mut() {
+                dirs.remove(&self.user);
+            }
+        }
+    }
+}
+
+/// Argon2id with the configured parameters
+pub fn argon2(settings: &Passwords) -> Result<Argon2<'static>, ServiceError> {
+    let params = Params::new(
+        settings.argon2_memory_kib,
+        settings.argon2_iterations,
+        settings.argon2_parallelism,
+        None,
+    )
+    .map_err(|e| ServiceError::InternalServerError(format!("Invalid Argon2 parameters: {}", e)))?;
+    Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+}
+
+///Hashes password with the configured settings
+pub fn hash_password(password: &str, settings: &Passwords) -> Result<String, ServiceError> {
     let salt = SaltString::generate(&mut OsRng);
-    let argon2 = Argon2::default();
-    Ok(argon2
+    Ok(argon2(settings)?
         .hash_password(password.as_bytes(), &salt)?
         .to_string())
 }
 
+/// The hash was created with other parameters than the configured ones.
+pub fn needs_rehash(password_hash: &str, settings: &Passwords) -> Result<bool, ServiceError> {
+    let parsed_hash = PasswordHash::new(password_hash)?;
+    let params = Params::try_from(&parsed_hash)?;
+    Ok(parsed_hash.algorithm != Algorithm::Argon2id.ident()
+        || parsed_hash.version != Some(Version::V0x13.into())
+        || params.m_cost() != settings.argon2_memory_kib
+        || params.t_cost() != settings.argon2_iterations
+        || params.p_cost() != settings.argon2_parallelism)
+}
+
+/// Checks a new password against the configured rules. The error lists every broken rule.
+pub fn check_password_policy(password: &str, settings: &Passwords) -> Result<(), ServiceError> {
+    let mut broken = Vec::new();
+    if password.chars().count() < settings.min_length {
+        broken.push(format!("at least {} characters", settings.min_length));
+    }
+    let rules = [
+        (
+            settings.require_lowercase,
+            "a lowercase letter",
+            char::is_lowercase as fn(char) -> bool,
+        ),
+        (
+            settings.require_uppercase,
+            "an uppercase letter",
+            char::is_uppercase,
+        ),
+        (settings.require_digit, "a digit", |c: char| {
+            c.is_ascii_digit()
+        }),
+        (
+            settings.require_special,
+            "a special character",
+            |c: char| !c.is_alphanumeric(),
+        ),
+    ];
+    for (required, rule, matches) in rules {
+        if required && !password.chars().any(matches) {
+            broken.push(rule.to_string());
+        }
+    }
+    match broken.is_empty() {
+        true => Ok(()),
+        false => Err(ServiceError::BadRequest(format!(
+            "The password needs {}.",
+            broken.join(", ")
+        ))),
+    }
+}
+
 ///Verifies Password hash
 pub fn verify(password_hash: &str, password: &str) -> Result<bool, ServiceError> {
     let parsed_hash = PasswordHash::new(&password_hash)?;
@@ -40,162 +155,237 @@ pub fn verify(password_hash: &str, password: &str) -> Result<bool, ServiceError>
     }
 }
 
-//TODO just get suggestion for one target die (timeManagment or so)
-/// get html file suggestions
-pub fn html_suggestion(user: &str) -> Result<Vec<shared::models::HtmlSuggestion>, ServiceError> {
-    let mut content_html = Vec::new();
-    let files = format!("./files/{}/*/*.html", &user);
-
-    let get_date = Regex::new(r"-.*$").unwrap();
-    let get_depth = Regex::new(r"^.*-").unwrap();
-    let get_category = Regex::new(r"^.*/").unwrap();
-
-    for path in glob(&files).unwrap().filter_map(Result::ok) {
-        let file = Path::new(&path.display().to_string())
-            .file_stem()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_string();
-        let date = get_date.replace(&file, "").to_string();
-        let depth = get_depth.replace(&file, "").to_string();
-        let category = get_category
-            .replace(
-                &path
-                    .display()
-                    .to_string()
-                    .replace(&format!("/{}.html", file), "")
-                    .to_string(),
-                "",
-            )
-            .to_string();
-        let content = shared::models::HtmlSuggestion {
-            target: category,
-            timespan: file
-                .replace(&format!("{}-", &date), "")
-                .replace(&format!("-{}", &depth), ""),
-            date,
-            depth,
-        };
-        content_html.push(content);
-    }
-    Ok(content_html)
+/// Finished time entery of the time journal.
+struct HistoryEntery<'a> {
+    id: String,
+    /// lines of the entery including the timespan comment
+    span: journal::Span,
+    transaction: &'a journal::Transaction,
+    timespan: Option<&'a journal::Comment>,
 }
 
-/// converts the ledger file for Time tracking and extracts the Heandline and the target account
-pub fn ledger_time_suggestion(
-    user: &str,
-) -> Result<Vec<shared::models::TimeEnterySuggestion>, ServiceError> {
-    let mut suggestion = Vec::new();
-
-    let ledger = fs::read_to_string(format!("{}/{}/{}", FILE_DIR, &user, PATH_TIME_SPEND))?;
-    let mut pos: i32 = 0; //log line number of entery
-    let mut headline: String = "".to_string(); //temp store of headline
-
-    //checks if the line is the beginning if a new entery
-    let check_beginning = Regex::new(r"^\d{4}/\d{2}/\d{2}").unwrap();
-    let replace_date = Regex::new(r"^\d{4}/\d{2}/\d{2}[ ]*[\t]*[ ]*").unwrap();
-    let remove_time = Regex::new(r"[\s]*[\t]*\d{1, 3}[\.]?\d{0,2}[m,h]").unwrap();
-    let remove_first_tab = Regex::new(r"[\s]*\t").unwrap();
-    let mut tracking: bool = false;
-    for line in ledger.lines() {
-        if check_beginning.is_match(line) {
-            pos = 0;
-            tracking = true;
-            headline = replace_date.replace(line, "").to_string();
-        } else if pos == 1 && tracking {
-            pos = 0;
-            tracking = false;
-            if headline == "" {
-                debug!("No headline{:?}", line);
+fn time_history_enteries(ledger: &journal::Journal) -> Vec<HistoryEntery<'_>> {
+    let check_timespan = Regex::new(r"^\d{2}:\d{2} - \d{2}:\d{2}").unwrap();
+    ledger
+        .transactions()
+        .zip(ledger.transaction_ids())
+        .filter(|(t, _)| t.postings.len() > 1)
+        .map(|(transaction, id)| {
+            let timespan = ledger
+                .leading_comments(transaction)
+                .into_iter()
+                .find(|c| check_timespan.is_match(&c.text));
+            HistoryEntery {
+                id,
+                span: journal::Span {
+                    start: timespan.map(|c| c.line).unwrap_or(transaction.span.start),
+                    end: transaction.span.end,
+                },
+                transaction,
+                timespan,
             }
-            let account_target = remove_first_tab
-                .replace_all(&remove_time.replace(line, "").to_string(), "")
-                .to_string();
-            suggestion.push(shared::models::TimeEnterySuggestion {
-                headline: headline.clone(),
-                account_target,
-            });
-        } else {
-            pos += 1;
+        })
+        .collect()
+}
+
+impl HistoryEntery<'_> {
+    fn to_history(&self) -> shared::models::TimeEnteryHistory {
+        let postings = &self.transaction.postings;
+        shared::models::TimeEnteryHistory {
+            id: self.id.clone(),
+            date: self.transaction.date.format("%Y/%m/%d").to_string(),
+            timespan: self.timespan.map(|c| c.text.clone()).unwrap_or_default(),
+            headline: self.transaction.headline.clone(),
+            account_origin: postings.first().unwrap().account.clone(),
+            account_target: postings.last().unwrap().account.clone(),
+            duration: postings.iter().find_map(|p| p.minutes()).unwrap_or(0),
         }
     }
-    Ok(suggestion)
+}
+
+/// all finished time enteries of the time journal
+pub fn time_history(ledger: &journal::Journal) -> Vec<shared::models::TimeEnteryHistory> {
+    time_history_enteries(ledger)
+        .iter()
+        .map(|e| e.to_history())
+        .collect()
 }
 
 /// get the n last time enteries
 pub fn ledger_time_history(
     user: &str,
 ) -> Result<Vec<shared::models::TimeEnteryHistory>, ServiceError> {
-    let mut history = Vec::new();
-    let ledger = fs::read_to_string(format!("{}/{}/{}", FILE_DIR, &user, PATH_TIME_SPEND))?;
-    let mut pos: i32 = 0; //log line number of entery
-    let mut headline: String = "".to_string(); //temp store of headline
-    let mut date: String = "".to_string(); //temp store of date
-    let mut timespan: String = "".to_string(); //temp store of timespan
-    let mut remove_entery: String = "".to_string(); //temp store of timespan
-    let mut duration = 0;
-
-    //checks if the line is the beginning if a new entery
-    let check_beginning = Regex::new(r"^\d{4}/\d{2}/\d{2}").unwrap();
-    let check_timespan = Regex::new(r"^; \d{2}:\d{2} - \d{2}:\d{2}").unwrap();
-    let replace_date = Regex::new(r"^\d{4}/\d{2}/\d{2}[ ]*[\t]*[ ]*").unwrap();
-    let get_timespan = Regex::new(r"^; ").unwrap();
-    let get_date = Regex::new(r"^\d{4}/\d{2}/\d{2}").unwrap();
-    let get_duration = Regex::new(r"[-]*\d{1,3}[m, h]+").unwrap();
-    let remove_time = Regex::new(r"[\s]*[\t]*\d{1, 3}[\.]?\d{0,2}[m,h]").unwrap();
-    let remove_first_tab = Regex::new(r"[\s]*\t").unwrap();
-    let mut tracking: bool = false;
-    for line in ledger.lines() {
-        //TODO only date date, if it is one line befor headline.
-        if check_timespan.is_match(line) {
-            remove_entery = format!("\n{}\n", line);
-            timespan = get_timespan.replace(line, "").to_string();
+    let ledger = journal::read_file(&user_file(user, PATH_TIME_SPEND))?;
+    Ok(time_history(&ledger))
+}
+
+/// Filters, sorts and pages the time history. Returns the selected page and the number of
+/// matching enteries.
+pub fn filter_time_history(
+    history: Vec<shared::models::TimeEnteryHistory>,
+    request: &shared::models::RequestTimeHistory,
+) -> Result<(Vec<shared::models::TimeEnteryHistory>, usize), ServiceError> {
+    let parse = |date: &Option<String>| match date {
+        Some(d) => NaiveDate::parse_from_str(&d.replace('-', "/"), "%Y/%m/%d")
+            .map(|d| Some(d.format("%Y/%m/%d").to_string()))
+            .map_err(|_| ServiceError::BadRequest(format!("Invalid date '{}'.", d))),
+        None => Ok(None),
+    };
+    // dates are compared as `%Y/%m/%d` strings
+    let (start, end) = (parse(&request.start)?, parse(&request.end)?);
+    let account = request.account.as_deref().filter(|a| !a.is_empty());
+    let words = request
+        .search
+        .as_deref()
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(|w| w.to_lowercase())
+        .collect::<Vec<_>>();
+    let mut history = history
+        .into_iter()
+        .filter(|e| start.as_ref().is_none_or(|s| &e.date >= s))
+        .filter(|e| end.as_ref().is_none_or(|d| &e.date <= d))
+        .filter(|e| {
+            account.is_none_or(|a| {
+                accounts::is_below(&e.account_origin, a) || accounts::is_below(&e.account_target, a)
+            })
+        })
+        .filter(|e| {
+            let headline = e.headline.to_lowercase();
+            words.iter().all(|w| headline.contains(w))
+        })
+        .collect::<Vec<_>>();
+    match request.sort.as_deref() {
+        None => (),
+        Some("asc") => history.sort_by(|a, b| (&a.date, &a.timespan).cmp(&(&b.date, &b.timespan))),
+        Some("desc") => history.sort_by(|a, b| (&b.date, &b.timespan).cmp(&(&a.date, &a.timespan))),
+        Some(sort) => {
+            return Err(ServiceError::BadRequest(format!(
+                "Unknown sort '{}'.",
+                sort
+            )))
         }
-        if check_beginning.is_match(line) {
-            remove_entery += &format!("{}\n", line);
-            pos = 0;
-            tracking = true;
-            date = match get_date.find(&line) {
-                Some(t) => t.as_str().to_string(),
-                _ => "0000/00/00".to_string(),
-            };
-            headline = replace_date.replace(line, "").to_string();
-        } else if pos == 0 && tracking {
-            remove_entery += &format!("{}\n", line);
-            pos += 1;
-            match get_duration.find(&line) {
-                Some(e) => duration = e.as_str().replace("m", "").parse::<u32>().unwrap_or(0),
-                None => (),
-            };
-        } else if pos == 1 && tracking {
-            pos = 0;
-            tracking = false;
-            if headline == "" {
-                debug!("No headline{:?}", line);
+    }
+    let total = history.len();
+    let page = history
+        .into_iter()
+        .skip(request.offset.unwrap_or(0))
+        .take(request.limit.unwrap_or(usize::MAX))
+        .collect();
+    Ok((page, total))
+}
+
+/// Overwrites the finished time entery with the id by the given values.
+/// Notes of the entery are kept. Returns the entery with its new id.
+pub fn ledger_update_time_entery(
+    user: &str,
+    entery: &shared::models::TimeEnteryHistory,
+) -> Result<shared::models::TimeEnteryHistory, ServiceError> {
+    if entery.account_origin.is_empty() || entery.account_target.is_empty() {
+        return Err(ServiceError::BadRequest(
+            "Empty account provided.".to_string(),
+        ));
+    }
+    // a line break would start a new transaction in the journal
+    if [
+        &entery.headline,
+        &entery.account_origin,
+        &entery.account_target,
+    ]
+    .iter()
+    .any(|text| text.contains(['\n', '\r']))
+    {
+        return Err(ServiceError::BadRequest(
+            "Line breaks are not allowed in time enteries.".to_string(),
+        ));
+    }
+    let check_timespan = Regex::new(r"^\d{2}:\d{2} - \d{2}:\d{2}$").unwrap();
+    if !entery.timespan.is_empty() && !check_timespan.is_match(&entery.timespan) {
+        return Err(ServiceError::BadRequest(format!(
+            "Invalid timespan '{}'.",
+            entery.timespan
+        )));
+    }
+    let date = NaiveDate::parse_from_str(&entery.date.replace('-', "/"), "%Y/%m/%d")
+        .map_err(|_| ServiceError::BadRequest(format!("Invalid date '{}'.", entery.date)))?;
+
+    journal::modify_file(&user_file(user, PATH_TIME_SPEND), |content| {
+        let ledger = journal::parse(content);
+        let old = time_history_enteries(&ledger)
+            .into_iter()
+            .find(|e| e.id == entery.id)
+            .ok_or_else(|| {
+                ServiceError::NotFound(format!("No time entery with id '{}'.", entery.id))
+            })?;
+        let transaction = old.transaction;
+        // only the origin and the target posting are edited, further postings would get lost
+        if transaction.postings.len() != 2 {
+            return Err(ServiceError::BadRequest(format!(
+                "The time entery '{}' has more than two postings and can only be edited in the journal.",
+                entery.id
+            )));
+        }
+        // the note behind the headline and comment lines (like the `id` tag) stay. Enteries
+        // without id get one, so the returned id stays valid.
+        let headline = match transaction
+            .comments
+            .iter()
+            .find(|c| c.line == transaction.span.start)
+        {
+            Some(note) => format!("{}  ; {}", entery.headline, note.text),
+            None => entery.headline.clone(),
+        };
+        let comments = match transaction.tag(journal::ID_TAG) {
+            Some(_) => Vec::new(),
+            None => vec![journal::new_id_tag()],
+        };
+        let mut lines = format_time_entery(
+            &entery.timespan,
+            &date.format("%Y/%m/%d").to_string(),
+            &headline,
+            &entery.account_origin,
+            &entery.account_target,
+            entery.duration,
+            &comments,
+        )
+        .lines()
+        .map(|l| l.to_string())
+        .collect::<Vec<_>>();
+        let first_posting = lines.len() - 2;
+        for (line, posting) in lines[first_posting..].iter_mut().zip(&transaction.postings) {
+            if let Some(comment) = &posting.comment {
+                line.push_str(&format!("  ; {}", comment));
             }
-            remove_entery += &format!("{}", line);
-            let account_target = remove_first_tab
-                .replace_all(&remove_time.replace(line, "").to_string(), "")
-                .to_string();
-            match get_duration.find(&line) {
-                Some(e) => duration = e.as_str().replace("m", "").parse::<u32>().unwrap_or(0),
-                None => (),
-            };
-            history.push(shared::models::TimeEnteryHistory {
-                remove_entery: remove_entery.clone(),
-                date: date.clone(),
-                timespan: timespan.clone(),
-                headline: headline.clone(),
-                account_target,
-                duration,
-            });
-            remove_entery = "".to_string();
-        } else {
-            pos += 1;
         }
-    }
-    Ok(history)
+        let header = lines.len() - 3 - comments.len();
+        for comment in transaction.comments.iter().rev() {
+            if comment.line != transaction.span.start {
+                lines.insert(header + 1, ledger.line(comment.line).to_string());
+            }
+        }
+
+        let content = ledger.replace(&old.span, &lines.join("\n"));
+        let ledger = journal::parse(&content);
+        let updated = time_history_enteries(&ledger)
+            .into_iter()
+            .find(|e| e.span.start == old.span.start)
+            .map(|e| e.to_history())
+            .ok_or_else(|| ServiceError::BadRequest("Invalid time entery.".to_string()))?;
+        Ok((content, updated))
+    })
+}
+
+/// Removes a finished time entery
+pub fn ledger_delete_time_entery(user: &str, id: &str) -> Result<(), ServiceError> {
+    journal::modify_file(&user_file(user, PATH_TIME_SPEND), |content| {
+        let ledger = journal::parse(content);
+        let span = time_history_enteries(&ledger)
+            .into_iter()
+            .find(|e| e.id == id)
+            .map(|e| e.span)
+            .ok_or_else(|| ServiceError::NotFound(format!("No time entery with id '{}'.", id)))?;
+        Ok((ledger.remove(&span), ()))
+    })
 }
 
 /// Starts time Entery in ledger time File.
@@ -203,49 +393,77 @@ pub fn ledger_start_time_entery(
     user: &str,
     start_entery: shared::models::StartTimeEntery,
 ) -> Result<String, ServiceError> {
-    println!("{:?}", start_entery.headline);
-    let dt = chrono::Local::now();
-    let minutes_count = (i64::from(dt.hour() * 60 + dt.minute())
-        + i64::from(start_entery.offset.unwrap_or(0))
-        + 24 * 60)
-        % (24 * 60);
-    let chrono_date = chrono::Local::now();
-    let date = format!(
-        "{:?}/{:02}/{:02}",
-        chrono_date.year(),
-        chrono_date.month(),
-        chrono_date.day()
-    );
-
+    let start = Local::now().fixed_offset()
+        + Duration::minutes(i64::from(start_entery.offset.unwrap_or(0)));
     let entery = &format!(
-        ";{} {}\t\t\t{}; \t{}; \t{}\t\t\t\t\t\t##m",
-        &minutes_count.to_string(),
-        &date.to_string(),
+        ";{}\t\t\t{}; \t{}; \t{}\t\t\t\t\t\t##m",
+        start.to_rfc3339_opts(SecondsFormat::Secs, false),
         start_entery.headline,
         start_entery.account_origin,
         start_entery.account_target,
     );
 
-    //TODO find a way how to close the file again
-    println!("{}", format!("{}/{}/{}", FILE_DIR, &user, PATH_TIME_SPEND));
-    fs::OpenOptions::new()
-        .append(true)
-        .open(format!("{}/{}/{}", FILE_DIR, &user, PATH_TIME_SPEND))?
-        .write_all(format!("{}\n", entery).as_bytes())?;
-    return Ok(entery.to_string());
+    let path = user_file(user, PATH_TIME_SPEND);
+    journal::modify_file(&path, |content| {
+        let content = format!("{}{}\n", content, entery);
+        // the new entery is the last one with this content
+        let ledger = journal::parse(&content);
+        let id = running_time_enteries(&ledger)
+            .into_iter()
+            .rev()
+            .find(|e| ledger.line(e.line) == entery)
+            .map(|e| e.id)
+            .ok_or_else(|| {
+                ServiceError::InternalServerError("Started entery not found.".to_string())
+            })?;
+        Ok((content, id))
+    })
+}
+
+/// Removes a running or finished time entery
+pub fn ledger_kill_time_entery(user: &str, id: &str) -> Result<(), ServiceError> {
+    journal::modify_file(&user_file(user, PATH_TIME_SPEND), |content| {
+        let ledger = journal::parse(content);
+        let span = running_time_enteries(&ledger)
+            .into_iter()
+            .find(|e| e.id == id)
+            .map(|e| journal::Span {
+                start: e.line,
+                end: e.line,
+            })
+            .or_else(|| {
+                time_history_enteries(&ledger)
+                    .into_iter()
+                    .find(|e| e.id == id)
+                    .map(|e| e.span)
+            })
+            .ok_or_else(|| ServiceError::NotFound(format!("No time entery with id '{}'.", id)))?;
+        Ok((ledger.remove(&span), ()))
+    })
+}
+
+/// Started time entery of the time journal.
+struct RunningEntery {
+    id: String,
+    line: usize,
+    start: DateTime<FixedOffset>,
+    entery: shared::models::NewTimeEntery,
 }
 
-///Remove started time File
-pub fn ledger_kill_time_entery(user: &str, remove_line: String) -> Result<String, ServiceError> {
-    let ledger = fs::read_to_string(format!("{}/{}/{}", FILE_DIR, &user, PATH_TIME_SPEND))?;
-    fs::File::create(format!("{}/{}/{}", FILE_DIR, &user, PATH_TIME_SPEND))
-        .unwrap()
-        .write(
-            ledger
-                .replace(&format!("{}\n", &remove_line), "")
-                .as_bytes(),
-        )?;
-    Ok(remove_line)
+fn running_time_enteries(ledger: &journal::Journal) -> Vec<RunningEntery> {
+    let mut ids = journal::IdGenerator::default();
+    ledger
+        .comments()
+        .filter_map(|comment| {
+            let (start, entery) = parse_running_time_entery(&comment.text)?;
+            Some(RunningEntery {
+                id: ids.next(ledger.line(comment.line)),
+                line: comment.line,
+                start,
+                entery,
+            })
+        })
+        .collect()
 }
 
 /// It returns all found started enterys in the ledger file for time_spend.
@@ -253,68 +471,139 @@ pub fn ledger_get_running_time_entery(
     user: &str,
 ) -> Result<BTreeMap<String, shared::models::NewTimeEntery>, ServiceError> {
     let mut response = BTreeMap::new();
-    let ledger = fs::read_to_string(format!("{}/{}/{}", FILE_DIR, &user, PATH_TIME_SPEND))?;
-    let stop_minute: u32 = chrono::Local::now().hour() * 60 + chrono::Local::now().minute();
-
-    let get_started_enteries = Regex::new(r"^;[0-9]").unwrap();
-    let get_start_minute = Regex::new(r"[0-9]+ ").unwrap();
-    let new_line = Regex::new(r";").unwrap();
-    let get_content = Regex::new(r"\d{4}.*").unwrap();
-    let clean_account_origin = Regex::new(r"^ \t").unwrap();
-    let clean_account_target = Regex::new(r"[ ]*[\t]+[ ,#,m]*").unwrap();
-    let get_date = Regex::new(r"\d{4}/\d{2}/\d{2}").unwrap();
-    for line in ledger.lines() {
-        if get_started_enteries.is_match(line) {
-            let start_minute_str = get_start_minute.find(&line).unwrap().as_str();
-            let content_raw = get_content.find(&line).unwrap().as_str();
-            let content = new_line.replace_all(content_raw, "\n").to_string(); // replace ; with \n
-            let start_minute: u32 = start_minute_str.trim().parse().unwrap();
-            let mut offset = 0;
-            if start_minute > stop_minute {
-                offset += 60 * 24;
-            }
-            let duration = offset + stop_minute - start_minute;
-            let content_vec = content.split("\n").collect::<Vec<&str>>();
-            println!("vec {:?}", content_vec);
-            let new_entery = shared::models::NewTimeEntery {
-                headline: content_vec[0]
-                    .to_string()
-                    .split("\t")
-                    .collect::<Vec<&str>>()[3]
-                    .to_string(),
-                account_origin: clean_account_origin
-                    .replace(&content_vec[1].to_string(), "")
-                    .to_string(),
-                account_target: clean_account_target
-                    .replace_all(&content_vec[2].to_string(), "")
-                    .to_string(),
-                duration,
-                date: Some(get_date.find(&content_vec[0]).unwrap().as_str().to_string()),
-                offset: None,
-            };
-            response.insert(line.to_string(), new_entery);
-        }
+    let ledger = journal::read_file(&user_file(user, PATH_TIME_SPEND))?;
+    let now = Local::now().fixed_offset();
+
+    for running in running_time_enteries(&ledger) {
+        let new_entery = shared::models::NewTimeEntery {
+            duration: (now - running.start).num_minutes().max(0) as u32,
+            ..running.entery
+        };
+        response.insert(running.id, new_entery);
     }
     debug!("Found running Enteries: {:#?}", response);
     Ok(response)
 }
 
-/// This function create a new time entery and removes the given line.
+/// parses the comment of a running entery: `{start}\t\t\t{headline}; \t{origin}; \t{target}\t##m`,
+/// where start is a RFC 3339 timestamp. Enteries of the old format `{minutes} {date}` are read
+/// in the local timezone. Returns the start and the entery without duration.
+fn parse_running_time_entery(
+    text: &str,
+) -> Option<(DateTime<FixedOffset>, shared::models::NewTimeEntery)> {
+    let mut parts = text.split(';');
+    let (start, content) = parts.next()?.split_once(char::is_whitespace)?;
+    let (start, headline) = match DateTime::parse_from_rfc3339(start) {
+        Ok(start) => (start, content),
+        Err(_) => {
+            let start_minute = start.parse::<u32>().ok()?;
+            let (date, headline) = content.split_once(char::is_whitespace)?;
+            let start = NaiveDate::parse_from_str(date, "%Y/%m/%d")
+                .ok()?
+                .and_hms_opt(start_minute / 60, start_minute % 60, 0)?;
+            (
+                Local.from_local_datetime(&start).earliest()?.fixed_offset(),
+                headline,
+            )
+        }
+    };
+    let account_origin = parts.next()?.trim();
+    let account_target = parts.next()?.trim().trim_end_matches("##m").trim_end();
+    Some((
+        start,
+        shared::models::NewTimeEntery {
+            headline: headline.trim().to_string(),
+            account_origin: account_origin.to_string(),
+            account_target: account_target.to_string(),
+            duration: 0,
+            date: Some(start.format("%Y/%m/%d").to_string()),
+            offset: None,
+        },
+    ))
+}
+
+/// Cuts the time between start and stop at every midnight (local time).
+fn split_at_midnight(
+    start: DateTime<FixedOffset>,
+    stop: DateTime<FixedOffset>,
+) -> Vec<(DateTime<FixedOffset>, DateTime<FixedOffset>)> {
+    let mut parts = Vec::new();
+    let mut current = start;
+    while current.with_timezone(&Local).date_naive() < stop.with_timezone(&Local).date_naive() {
+        let next_day = current.with_timezone(&Local).date_naive() + Duration::days(1);
+        let midnight = match Local
+            .from_local_datetime(&next_day.and_hms_opt(0, 0, 0).unwrap())
+            .earliest()
+        {
+            Some(m) => m.fixed_offset(),
+            None => break,
+        };
+        parts.push((current, midnight));
+        current = midnight;
+    }
+    if current < stop || parts.is_empty() {
+        parts.push((current, stop));
+    }
+    parts
+}
+
+/// This function create a new time entery and removes the running entery.
+/// The entery ends now, moved by the offset of the new entery. If `split_days` is set, an entery
+/// running over midnight is saved as one entery per day, otherwise as one entery with the whole
+/// duration.
 pub fn ledger_stop_time_entery(
     user: &str,
     info: &shared::models::StopLedgerTimeEntery,
+    split_days: bool,
 ) -> Result<(), ServiceError> {
-    let ledger = fs::read_to_string(format!("{}/{}/{}", FILE_DIR, &user, PATH_TIME_SPEND))?;
-    fs::File::create(format!("{}/{}/{}", FILE_DIR, &user, PATH_TIME_SPEND))?.write(
-        ledger
-            .replace(&format!("{}\n", &info.remove_line), "")
-            .as_bytes(),
-    )?;
-    let mut create_entery = info.new_entery.clone();
-    create_entery.duration =
-        (create_entery.duration as i32 + create_entery.offset.unwrap_or(0)) as u32;
-    ledger_create_time_entery(&user, create_entery)?;
-    Ok(())
+    let stop = Local::now().fixed_offset()
+        + Duration::minutes(i64::from(info.new_entery.offset.unwrap_or(0)));
+    // removing the running entery and adding the finished one is one write
+    journal::modify_file(&user_file(user, PATH_TIME_SPEND), |content| {
+        let ledger = journal::parse(content);
+        let running = running_time_enteries(&ledger)
+            .into_iter()
+            .find(|e| e.id == info.id)
+            .ok_or_else(|| {
+                ServiceError::NotFound(format!("No running time entery with id '{}'.", info.id))
+            })?;
+        if stop < running.start {
+            return Err(ServiceError::BadRequest(
+                "The entery can not end before it started.".to_string(),
+            ));
+        }
+        // whole minutes, so the durations of the parts add up
+        let start = running.start.with_second(0).unwrap();
+        let stop = stop.with_second(0).unwrap().with_nanosecond(0).unwrap();
+        let parts = if split_days {
+            split_at_midnight(start, stop)
+        } else {
+            vec![(start, stop)]
+        };
+        let mut content = ledger.remove(&journal::Span {
+            start: running.line,
+            end: running.line,
+        });
+        for (start, end) in parts {
+            // a part ending at midnight ends at 24:00 of its day
+            let end_time = if end.date_naive() > start.date_naive() && split_days {
+                "24:00".to_string()
+            } else {
+                end.format("%H:%M").to_string()
+            };
+            content += "\n";
+            content += &format_time_entery(
+                &format!("{} - {}", start.format("%H:%M"), end_time),
+                &start.format("%Y/%m/%d").to_string(),
+                &info.new_entery.headline,
+                &info.new_entery.account_origin,
+                &info.new_entery.account_target,
+                (end - start).num_minutes() as u32,
+                &[journal::new_id_tag()],
+            );
+        }
+        Ok((content, ()))
+    })
 }
 
 ///Creates a new time Entery
@@ -322,6 +611,13 @@ pub fn ledger_create_time_entery(
     user: &str,
     start_entery: shared::models::NewTimeEntery,
 ) -> Result<String, ServiceError> {
+    let entery = time_entery_text(&start_entery);
+    journal::append_file(&user_file(user, PATH_TIME_SPEND), &entery)?;
+    Ok(entery)
+}
+
+/// formats a finished time entery as it is written to the journal
+fn time_entery_text(start_entery: &shared::models::NewTimeEntery) -> String {
     let offset_end = start_entery.offset.unwrap_or(0);
     let chrono_date = chrono::Local::now();
     let stop_minute: i64 =
@@ -336,7 +632,7 @@ pub fn ledger_create_time_entery(
     );
     let start_minute: i64 = (stop_minute - start_entery.duration as i64 + 24 * 60) % (24 * 60); //TODO adjust
     let time_span = format!(
-        "; {:02}:{:02} - {:02}:{:02}",
+        "{:02}:{:02} - {:02}:{:02}",
         start_minute / 60,
         start_minute % 60,
         stop_minute / 60,
@@ -346,40 +642,70 @@ pub fn ledger_create_time_entery(
         Some(d) => d,
         None => &date_now,
     };
+    format!(
+        "\n{}",
+        format_time_entery(
+            &time_span,
+            date,
+            &start_entery.headline,
+            &start_entery.account_origin,
+            &start_entery.account_target,
+            start_entery.duration,
+            &[journal::new_id_tag()],
+        )
+    )
+}
 
+/// lines of a finished time entery. The timespan comment is left out, if it is empty.
+/// The comments are written below the headline.
+fn format_time_entery(
+    timespan: &str,
+    date: &str,
+    headline: &str,
+    account_origin: &str,
+    account_target: &str,
+    duration: u32,
+    comments: &[String],
+) -> String {
     // calculate number of tabs
-    let tab_count = if (start_entery.account_target.chars().count() / 4) < 11 {
-        start_entery.account_target.chars().count() / 4
+    let tab_count = if (account_target.chars().count() / 4) < 11 {
+        account_target.chars().count() / 4
     } else {
         10
     };
     let tabs = "\t".repeat(11 - tab_count);
-    let entery = &format!(
-        "\n{}\n{}\t\t\t{}\n \t{}\n \t{}{}{}m\n",
-        time_span,
-        date,
-        start_entery.headline,
-        start_entery.account_origin,
-        start_entery.account_target,
-        tabs,
-        start_entery.duration,
-    );
-    fs::OpenOptions::new()
-        .append(true)
-        .open(format!("{}/{}/{}", FILE_DIR, &user, PATH_TIME_SPEND))?
-        .write_all(entery.as_bytes())?;
-    Ok(entery.to_string())
+    let timespan = if timespan.is_empty() {
+        String::new()
+    } else {
+        format!("; {}\n", timespan)
+    };
+    let comments = comments
+        .iter()
+        .map(|c| format!(" \t; {}\n", c))
+        .collect::<String>();
+    format!(
+        "{}{}\t\t\t{}\n{} \t{}\n \t{}{}{}m\n",
+        timespan, date, headline, comments, account_origin, account_target, tabs, duration,
+    )
 }
 
 pub fn ledger_create_finance_entery(
     user: &str,
     new_entery: shared::models::NewFinanceEntery,
 ) -> Result<String, ServiceError> {
-    let pos = NAME_FINANCE
-        .iter()
-        .position(|f| f.to_string() == new_entery.target_file)
-        .unwrap_or(0);
-    let path = PATH_FINANCE_FILES[pos];
+    let (finance_journal, entery) = finance_entery_text(user, &new_entery, &[])?;
+    journal::append_file(&user_file(user, &finance_journal.path), &entery)?;
+    Ok(entery)
+}
+
+/// Checks the entery and returns its journal and its text. The comments are written below
+/// the headline.
+pub fn finance_entery_text(
+    user: &str,
+    new_entery: &shared::models::NewFinanceEntery,
+    comments: &[String],
+) -> Result<(shared::models::JournalDefinition, String), ServiceError> {
+    let finance_journal = journals::finance_journal(user, &new_entery.target_file)?;
     let chrono_date = chrono::Local::now();
     let date_now = format!(
         "{:?}/{:02}/{:02}",
@@ -391,125 +717,228 @@ pub fn ledger_create_finance_entery(
         Some(d) => d,
         None => &date_now,
     };
-    // calculate number of tabs
-    let tab_count = if (new_entery.account_target.chars().count() / 4) < 11 {
-        new_entery.account_target.chars().count() / 4
-    } else {
-        1
-    };
-    let tabs = "\t".repeat(11 - tab_count);
+    let mut postings = finance_postings(new_entery);
+    // ammounts without commodity are in the commodity of the journal
+    for ammount in postings.iter_mut().filter_map(|p| p.ammount.as_mut()) {
+        if ammount.commodity.is_empty() {
+            ammount.commodity = finance_journal.commodity.clone();
+        }
+    }
+    check_finance_balance(&postings)?;
 
-    let entery = &format!(
-        "\n{}\t\t\t{}\n \t{}\n \t{}{}{}€\n",
-        date,
-        &new_entery.headline,
-        &new_entery.account_origin,
-        &new_entery.account_target,
-        tabs,
-        &new_entery.ammount,
-    );
-    fs::OpenOptions::new()
-        .append(true)
-        .open(format!("{}/{}/{}", FILE_DIR, &user, path))?
-        .write_all(entery.as_bytes())?;
-    Ok(entery.to_string())
+    let mut entery = format!("\n{}\t\t\t{}\n", date, &new_entery.headline);
+    entery += &format!(" \t; {}\n", journal::new_id_tag());
+    for comment in comments {
+        entery += &format!(" \t; {}\n", comment);
+    }
+    for posting in &postings {
+        entery += &match &posting.ammount {
+            Some(ammount) => {
+                // calculate number of tabs
+                let tab_count = if (posting.account.chars().count() / 4) < 11 {
+                    posting.account.chars().count() / 4
+                } else {
+                    1
+                };
+                let tabs = "\t".repeat(11 - tab_count);
+                match &posting.cost {
+                    // ledger only accepts positive costs, the sign is the one of the ammount
+                    Some(cost) => format!(
+                        " \t{}{}{} @@ {}\n",
+                        posting.account,
+                        tabs,
+                        ammount,
+                        shared::amount::Amount::new(cost.quantity.abs(), &cost.commodity)
+                    ),
+                    None => format!(" \t{}{}{}\n", posting.account, tabs, ammount),
+                }
+            }
+            None => format!(" \t{}\n", posting.account),
+        };
+    }
+    Ok((finance_journal, entery))
+}
+
+/// the postings of the entery. Without explicit postings, the target gets the ammount and the
+/// origin the balancing value.
+fn finance_postings(
+    new_entery: &shared::models::NewFinanceEntery,
+) -> Vec<shared::models::FinancePosting> {
+    if !new_entery.postings.is_empty() {
+        return new_entery.postings.clone();
+    }
+    vec![
+        shared::models::FinancePosting {
+            account: new_entery.account_origin.clone(),
+            ammount: None,
+            cost: None,
+        },
+        shared::models::FinancePosting {
+            account: new_entery.account_target.clone(),
+            ammount: Some(new_entery.ammount.clone()),
+            cost: None,
+        },
+    ]
+}
+
+/// Checks, that the postings balance in every commodity. Postings with a cost count in the
+/// commodity of the cost. One posting may have no ammount.
+fn check_finance_balance(postings: &[shared::models::FinancePosting]) -> Result<(), ServiceError> {
+    if postings.len() < 2 {
+        return Err(ServiceError::BadRequest(
+            "An entery needs at least two postings.".to_string(),
+        ));
+    }
+    if let Some(posting) = postings.iter().find(|p| {
+        p.account.trim().is_empty() || p.account.contains(['\t', '\n']) || p.account.contains("  ")
+    }) {
+        return Err(ServiceError::BadRequest(format!(
+            "Invalid account '{}'.",
+            posting.account
+        )));
+    }
+    let elided = postings.iter().filter(|p| p.ammount.is_none()).count();
+    let difference =
+        check::unbalanced(postings.iter().filter_map(|p| match (&p.cost, &p.ammount) {
+            (Some(cost), Some(ammount)) => Some(cost.with_sign_of(ammount)),
+            (cost, ammount) => cost.clone().or_else(|| ammount.clone()),
+        }));
+    match (elided, difference) {
+        (0, Some(difference)) => Err(ServiceError::BadRequest(format!(
+            "The postings do not balance, the difference is {}.",
+            difference
+        ))),
+        (0 | 1, _) => Ok(()),
+        _ => Err(ServiceError::BadRequest(
+            "Only one posting may have no ammount.".to_string(),
+        )),
+    }
 }
 
 /// converts the ledger file for Finance tracking and extracts the Heandline and ammount, target
 /// and origin account
-//TODO sort by date
 pub fn ledger_finance_suggestion(
     user: &str,
 ) -> Result<Vec<shared::models::NewFinanceEntery>, ServiceError> {
     let mut content_finance = Vec::new();
+    for (_, content) in finance_suggestion_candidates(user)? {
+        //check if entery exists in vec
+        if !content_finance.contains(&content) {
+            content_finance.push(content);
+        }
+    }
+    Ok(content_finance)
+}
 
-    let mut pos: i32 = 0; //log line number of entery
-    let mut headline: String = "".to_string(); //temp store of headline
-    let mut account_origin: String = "".to_string(); //temp store of headline
-
-    //checks if the line is the beginning if a new entery
-    let check_beginning = Regex::new(r"^\d{4}/\d{2}/\d{2}").unwrap();
-    let replace_date = Regex::new(r"^\d{4}/\d{2}/\d{2}[ ]*[\t]*[\s]*").unwrap();
-    let get_account = Regex::new(r"[\s, \t]*(-)?\d{1, 4}[\.]?\d{0,2}€").unwrap();
-    let remove_first_tab = Regex::new(r"^[\s, \t]*").unwrap();
-    let remove_last_tab = Regex::new(r"[\s, \t]*$").unwrap();
-    let get_ammount = Regex::new(r"[-]*\d{0,6}[.]*\d{1,6}€").unwrap();
-    let mut tracking: bool = false;
-    let mut ammount = 0.0;
-    let mut content;
-
-    for (file, target_file) in zip(PATH_FINANCE_FILES, NAME_FINANCE) {
-        let ledger = fs::read_to_string(format!("{}/{}/{}", FILE_DIR, &user, file))?;
-        //TODO add multi line enteryies
-        for line in ledger.lines() {
-            if check_beginning.is_match(line) {
-                pos = 0;
-                tracking = true;
-                headline = replace_date.replace(line, "").to_string();
-            } else if pos == 0 && tracking {
-                account_origin = remove_last_tab
-                    .replace(
-                        &remove_first_tab
-                            .replace(&get_account.replace(line, "").to_string(), "")
-                            .to_string(),
-                        "",
-                    )
-                    .to_string();
-                pos += 1;
-                match get_ammount.find(&line) {
-                    Some(e) => ammount = e.as_str().replace("€", "").parse::<f32>().unwrap_or(0.0),
-                    None => (),
-                };
-            } else if pos == 1 && tracking {
-                match get_ammount.find(&line) {
-                    Some(e) => ammount = e.as_str().replace("€", "").parse::<f32>().unwrap_or(0.0),
-                    None => (),
-                };
-                pos = 0;
-                tracking = false;
-                content = shared::models::NewFinanceEntery {
-                    headline: headline.clone(),
-                    account_target: remove_last_tab
-                        .replace(
-                            &remove_first_tab
-                                .replace(&get_account.replace(line, "").to_string(), "")
-                                .to_string(),
-                            "",
-                        )
-                        .to_string(),
-                    account_origin: account_origin.clone(),
+/// Every use of a finance suggestion with its date, in the order of the journals.
+pub fn finance_suggestion_candidates(
+    user: &str,
+) -> Result<Vec<(NaiveDate, shared::models::NewFinanceEntery)>, ServiceError> {
+    let mut candidates = Vec::new();
+
+    for finance_journal in journals::active_journals(user, JournalKind::Finance)? {
+        let ledger = journal::read_file(&user_file(user, &finance_journal.path))?;
+        for transaction in ledger.transactions() {
+            let (origin, targets) = match transaction.postings.split_first() {
+                Some((origin, targets)) => (origin, targets),
+                None => continue,
+            };
+            // a split entery can be repeated as whole
+            let split = if transaction.postings.len() > 2 {
+                transaction
+                    .postings
+                    .iter()
+                    .map(|p| shared::models::FinancePosting {
+                        account: p.account.clone(),
+                        ammount: p.amount.clone(),
+                        cost: p.cost.clone(),
+                    })
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            // every further posting is a suggestion with the first posting as origin
+            for target in targets {
+                let content = shared::models::NewFinanceEntery {
+                    headline: transaction.headline.clone(),
+                    account_target: target.account.clone(),
+                    account_origin: origin.account.clone(),
                     date: None,
-                    ammount,
-                    target_file: target_file.to_string(),
+                    ammount: target
+                        .amount
+                        .clone()
+                        .or_else(|| origin.amount.clone())
+                        .unwrap_or_default(),
+                    target_file: finance_journal.name.clone(),
+                    postings: split.clone(),
                 };
-                //check if entery exists in vec
-                //TODO dont push, if just ammount is different
-                if !content_finance.contains(&content) {
-                    content_finance.push(content);
-                }
-            } else {
-                pos += 1;
+                candidates.push((transaction.date, content));
             }
         }
     }
-    Ok(content_finance)
+    Ok(candidates)
 }
 
 #[cfg(test)]
 mod tests {
-    use shared::models::NewTimeEntery;
+    use shared::amount::{Amount, Decimal};
+    use shared::models::{JournalKind, NewTimeEntery};
+    use std::fs;
+    use std::thread;
 
     use super::*;
 
     pub const TEST_USER: &str = "test";
 
+    fn password_settings() -> Passwords {
+        Passwords {
+            min_length: 8,
+            require_lowercase: true,
+            require_uppercase: true,
+            require_digit: true,
+            require_special: false,
+            argon2_memory_kib: Params::DEFAULT_M_COST,
+            argon2_iterations: Params::DEFAULT_T_COST,
+            argon2_parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+
     #[actix_web::test]
     async fn test_password_hash_and_verify() {
         let pwd = "jkl";
-        let pwd_hash = &hash_password(pwd).unwrap();
+        let pwd_hash = &hash_password(pwd, &password_settings()).unwrap();
         assert!(verify(pwd_hash, pwd).unwrap());
     }
 
+    #[test]
+    fn test_password_rehash() {
+        let mut settings = password_settings();
+        let pwd_hash = hash_password("jkl", &settings).unwrap();
+        assert!(!needs_rehash(&pwd_hash, &settings).unwrap());
+        settings.argon2_iterations += 1;
+        assert!(needs_rehash(&pwd_hash, &settings).unwrap());
+        // the old hash is still verified with its own parameters
+        assert!(verify(&pwd_hash, "jkl").unwrap());
+        let argon2i = Argon2::new(Algorithm::Argon2i, Version::V0x13, Params::default())
+            .hash_password(b"jkl", &SaltString::generate(&mut OsRng))
+            .unwrap()
+            .to_string();
+        assert!(needs_rehash(&argon2i, &password_settings()).unwrap());
+    }
+
+    #[test]
+    fn test_password_policy() {
+        let settings = password_settings();
+        assert!(check_password_policy("Secret123", &settings).is_ok());
+        match check_password_policy("secret", &settings) {
+            Err(ServiceError::BadRequest(message)) => assert_eq!(
+                message,
+                "The password needs at least 8 characters, an uppercase letter, a digit."
+            ),
+            _ => panic!("the password breaks the policy"),
+        }
+    }
+
     #[actix_web::test]
     async fn test_ledger_create_time_entery() {
         let new_entery = NewTimeEntery {
@@ -520,22 +949,23 @@ mod tests {
             date: Some("2022/10/10".to_string()),
             offset: None,
         };
-        assert!(ledger_create_time_entery(&TEST_USER, new_entery.clone()).is_ok());
+        let first = ledger_create_time_entery(TEST_USER, new_entery.clone()).unwrap();
+        let second = ledger_create_time_entery(TEST_USER, new_entery).unwrap();
+        // equal enteries get their own id
+        let id = |text: &str| {
+            journal::parse(text)
+                .transactions()
+                .next()
+                .and_then(|t| t.tag(journal::ID_TAG).map(|id| id.to_string()))
+                .unwrap()
+        };
+        assert_ne!(id(&first), id(&second));
 
-        //remove added line
-        let ledger =
-            fs::read_to_string(format!("{}/{}/{}", FILE_DIR, &TEST_USER, PATH_TIME_SPEND)).unwrap();
-        fs::File::create(format!("{}/{}/{}", FILE_DIR, &TEST_USER, PATH_TIME_SPEND))
-            .unwrap()
-            .write(
-                ledger
-                    .replace(
-                        &ledger_create_time_entery(&TEST_USER, new_entery).unwrap(),
-                        "",
-                    )
-                    .as_bytes(),
-            )
-            .unwrap();
+        //remove added lines
+        journal::modify_file(&user_file(TEST_USER, PATH_TIME_SPEND), |ledger| {
+            Ok((ledger.replace(&first, "").replace(&second, ""), ()))
+        })
+        .unwrap();
     }
 
     #[actix_web::test]
@@ -548,25 +978,147 @@ mod tests {
             date: None,
             offset: None,
         };
-        let remove_line = ledger_start_time_entery(&TEST_USER, start_entery).unwrap();
-        println!("{}", &remove_line);
+        let id = ledger_start_time_entery(TEST_USER, start_entery).unwrap();
+        println!("{}", &id);
         //TODO find error
-        assert!(ledger_get_running_time_entery(&TEST_USER)
+        assert!(ledger_get_running_time_entery(TEST_USER)
             .unwrap()
-            .get(&remove_line)
-            .is_some());
+            .contains_key(&id));
 
         //remove added line
-        let ledger =
-            fs::read_to_string(format!("{}/{}/{}", FILE_DIR, &TEST_USER, PATH_TIME_SPEND)).unwrap();
-        fs::File::create(format!("{}/{}/{}", FILE_DIR, &TEST_USER, PATH_TIME_SPEND))
+        ledger_kill_time_entery(TEST_USER, &id).unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_concurrent_start_stop_time_entery() {
+        let user = "concurrency-test";
+        let _dir = test_dirs::UserDir::new(user);
+        fs::write(user_file(user, PATH_TIME_SPEND), "").unwrap();
+
+        thread::scope(|s| {
+            for i in 0..10 {
+                s.spawn(move || {
+                    let start_entery = shared::models::StartTimeEntery {
+                        headline: format!("Parallel {}", i),
+                        account_origin: "FreeTime".to_owned(),
+                        account_target: "EducationRust".to_owned(),
+                        duration: None,
+                        date: None,
+                        offset: None,
+                    };
+                    let id = ledger_start_time_entery(user, start_entery).unwrap();
+                    let running = ledger_get_running_time_entery(user).unwrap();
+                    let info = shared::models::StopLedgerTimeEntery {
+                        new_entery: running[&id].clone(),
+                        id,
+                    };
+                    ledger_stop_time_entery(user, &info, false).unwrap();
+                });
+            }
+        });
+
+        // every entery is stopped exactly once and the journal is still valid
+        let ledger = journal::read_file(&user_file(user, PATH_TIME_SPEND)).unwrap();
+        assert!(ledger.errors.is_empty());
+        assert!(ledger_get_running_time_entery(user).unwrap().is_empty());
+        let mut headlines = ledger_time_history(user)
             .unwrap()
-            .write(
-                ledger
-                    .replace(&format!("{}\n", &remove_line), "")
-                    .as_bytes(),
-            )
+            .into_iter()
+            .map(|e| e.headline)
+            .collect::<Vec<_>>();
+        headlines.sort();
+        assert_eq!(
+            headlines,
+            (0..10)
+                .map(|i| format!("Parallel {}", i))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_parse_running_time_entery() {
+        let (start, entery) = parse_running_time_entery(
+            "2022-10-11T23:30:00+02:00\t\t\tCarlos is programming; \tFreeTime; \tEducationRust\t\t\t\t\t\t##m",
+        )
+        .unwrap();
+        assert_eq!(
+            start,
+            DateTime::parse_from_rfc3339("2022-10-11T23:30:00+02:00").unwrap()
+        );
+        assert_eq!(entery.headline, "Carlos is programming");
+        assert_eq!(entery.account_origin, "FreeTime");
+        assert_eq!(entery.account_target, "EducationRust");
+        assert_eq!(entery.date, Some("2022/10/11".to_string()));
+
+        // old format with minutes since midnight
+        let (start, entery) = parse_running_time_entery(
+            "600 2022/10/11\t\t\tCarlos is programming; \tFreeTime; \tEducationRust\t\t\t\t\t\t##m",
+        )
+        .unwrap();
+        assert_eq!(start.with_timezone(&Local).hour(), 10);
+        assert_eq!(entery.headline, "Carlos is programming");
+        assert_eq!(entery.date, Some("2022/10/11".to_string()));
+        assert!(parse_running_time_entery("just a comment").is_none());
+    }
+
+    #[actix_web::test]
+    async fn test_split_at_midnight() {
+        let local = |d: u32, h: u32, m: u32| {
+            Local
+                .with_ymd_and_hms(2022, 10, d, h, m, 0)
+                .unwrap()
+                .fixed_offset()
+        };
+        let parts = split_at_midnight(local(10, 22, 0), local(12, 1, 30));
+        assert_eq!(
+            parts,
+            vec![
+                (local(10, 22, 0), local(11, 0, 0)),
+                (local(11, 0, 0), local(12, 0, 0)),
+                (local(12, 0, 0), local(12, 1, 30)),
+            ]
+        );
+        assert_eq!(
+            split_at_midnight(local(10, 8, 0), local(10, 9, 0)),
+            vec![(local(10, 8, 0), local(10, 9, 0))]
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_stop_multi_day_time_entery() {
+        let user = "multi-day-test";
+        let _dir = test_dirs::UserDir::new(user);
+        let start = Local::now().fixed_offset() - Duration::days(2) - Duration::minutes(30);
+        let running = format!(
+            ";{}\t\t\tWeekend; \tFreeTime; \tSleep\t\t\t\t\t\t##m\n",
+            start.to_rfc3339_opts(SecondsFormat::Secs, false)
+        );
+
+        // one entery with the whole duration
+        fs::write(user_file(user, PATH_TIME_SPEND), &running).unwrap();
+        let (id, new_entery) = ledger_get_running_time_entery(user)
+            .unwrap()
+            .into_iter()
+            .next()
             .unwrap();
+        assert!(new_entery.duration >= 2 * 24 * 60 + 30);
+        let info = shared::models::StopLedgerTimeEntery { id, new_entery };
+        ledger_stop_time_entery(user, &info, false).unwrap();
+        let history = ledger_time_history(user).unwrap();
+        assert_eq!(history.len(), 1);
+        assert!(history[0].duration >= 2 * 24 * 60 + 30);
+        assert_eq!(history[0].date, start.format("%Y/%m/%d").to_string());
+
+        // one entery per day
+        fs::write(user_file(user, PATH_TIME_SPEND), &running).unwrap();
+        ledger_stop_time_entery(user, &info, true).unwrap();
+        let history = ledger_time_history(user).unwrap();
+        assert_eq!(history.len(), 3);
+        assert!(history[0].timespan.ends_with("- 24:00"));
+        assert!(history[1].timespan == "00:00 - 24:00");
+        assert!(history[2].timespan.starts_with("00:00 -"));
+        let total = history.iter().map(|e| e.duration).sum::<u32>();
+        assert!(total >= 2 * 24 * 60 + 30);
     }
 
     #[actix_web::test]
@@ -579,9 +1131,199 @@ mod tests {
             date: None,
             offset: None,
         };
-        let remove_line = ledger_start_time_entery(&TEST_USER, start_entery).unwrap();
-        //TODO find error
-        assert!(ledger_kill_time_entery(&TEST_USER, remove_line).is_ok());
+        let id = ledger_start_time_entery(TEST_USER, start_entery).unwrap();
+        assert!(ledger_kill_time_entery(TEST_USER, &id).is_ok());
+        assert!(matches!(
+            ledger_kill_time_entery(TEST_USER, &id),
+            Err(ServiceError::NotFound(_))
+        ));
+    }
+
+    #[actix_web::test]
+    async fn test_ledger_kill_identical_time_enteries() {
+        let new_entery = NewTimeEntery {
+            headline: "Identical entery".to_owned(),
+            account_origin: "FreeTime".to_owned(),
+            account_target: "EducationRust".to_owned(),
+            duration: 10,
+            date: Some("2022/10/09".to_string()),
+            offset: Some(-60 * 24),
+        };
+        ledger_create_time_entery(TEST_USER, new_entery.clone()).unwrap();
+        ledger_create_time_entery(TEST_USER, new_entery).unwrap();
+        let ids = ledger_time_history(TEST_USER)
+            .unwrap()
+            .into_iter()
+            .filter(|e| e.headline == "Identical entery")
+            .map(|e| e.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids.len(), 2);
+        assert_ne!(ids[0], ids[1]);
+
+        // only the first entery is removed and the second one keeps its id
+        ledger_kill_time_entery(TEST_USER, &ids[0]).unwrap();
+        let remaining = ledger_time_history(TEST_USER)
+            .unwrap()
+            .into_iter()
+            .filter(|e| e.headline == "Identical entery")
+            .map(|e| e.id)
+            .collect::<Vec<_>>();
+        assert_eq!(remaining, vec![ids[1].clone()]);
+        ledger_kill_time_entery(TEST_USER, &ids[1]).unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_ledger_update_and_delete_time_entery() {
+        let new_entery = NewTimeEntery {
+            headline: "Entery to update".to_owned(),
+            account_origin: "FreeTime".to_owned(),
+            account_target: "EducationRust".to_owned(),
+            duration: 10,
+            date: Some("2022/10/08".to_string()),
+            offset: Some(-60 * 24),
+        };
+        ledger_create_time_entery(TEST_USER, new_entery).unwrap();
+        let entery = ledger_time_history(TEST_USER)
+            .unwrap()
+            .into_iter()
+            .find(|e| e.headline == "Entery to update")
+            .unwrap();
+
+        let changed = shared::models::TimeEnteryHistory {
+            headline: "Updated entery".to_string(),
+            account_target: "EducationRust:Backend".to_string(),
+            date: "2022-10-07".to_string(),
+            timespan: "08:00 - 09:30".to_string(),
+            duration: 90,
+            ..entery.clone()
+        };
+        let updated = ledger_update_time_entery(TEST_USER, &changed).unwrap();
+        assert_eq!(updated.id, entery.id);
+        assert_eq!(updated.date, "2022/10/07");
+        let history = ledger_time_history(TEST_USER).unwrap();
+        let stored = history.iter().find(|e| e.id == updated.id).unwrap();
+        assert_eq!(stored.headline, "Updated entery");
+        assert_eq!(stored.account_target, "EducationRust:Backend");
+        assert_eq!(stored.timespan, "08:00 - 09:30");
+        assert_eq!(stored.duration, 90);
+
+        // the id stays valid and invalid values are rejected
+        assert!(ledger_update_time_entery(TEST_USER, &changed).is_ok());
+        let invalid = shared::models::TimeEnteryHistory {
+            timespan: "8 - 9".to_string(),
+            ..updated.clone()
+        };
+        assert!(matches!(
+            ledger_update_time_entery(TEST_USER, &invalid),
+            Err(ServiceError::BadRequest(_))
+        ));
+
+        ledger_delete_time_entery(TEST_USER, &updated.id).unwrap();
+        assert!(matches!(
+            ledger_delete_time_entery(TEST_USER, &updated.id),
+            Err(ServiceError::NotFound(_))
+        ));
+    }
+
+    #[actix_web::test]
+    async fn test_ledger_update_time_entery_without_id() {
+        // enteries written by hand have no id tag
+        let text = format_time_entery(
+            "",
+            "2022/10/06",
+            "Entery without id",
+            "FreeTime",
+            "EducationRust",
+            10,
+            &[],
+        );
+        journal::append_file(
+            &user_file(TEST_USER, PATH_TIME_SPEND),
+            &format!("\n{}", text),
+        )
+        .unwrap();
+        let entery = ledger_time_history(TEST_USER)
+            .unwrap()
+            .into_iter()
+            .find(|e| e.headline == "Entery without id")
+            .unwrap();
+        let changed = shared::models::TimeEnteryHistory {
+            duration: 20,
+            ..entery.clone()
+        };
+        // the updated entery gets an id tag, that stays the same
+        let updated = ledger_update_time_entery(TEST_USER, &changed).unwrap();
+        assert_ne!(updated.id, entery.id);
+        let changed = shared::models::TimeEnteryHistory {
+            duration: 30,
+            ..updated.clone()
+        };
+        assert_eq!(
+            ledger_update_time_entery(TEST_USER, &changed).unwrap().id,
+            updated.id
+        );
+        ledger_delete_time_entery(TEST_USER, &updated.id).unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_ledger_update_time_entery_keeps_postings() {
+        let user = "test_update_postings";
+        let _dir = test_dirs::UserDir::new(user);
+        let path = user_file(user, PATH_TIME_SPEND);
+        journal::create_file(&path).unwrap();
+        journal::append_file(
+            &path,
+            "2022/10/06 Commented entery
+ \t; id: commented
+ \tFreeTime  ; tired
+ \tEducationRust\t\t10m  ; chapter 3
+
+2022/10/07 Split entery
+ \t; id: split
+ \tFreeTime
+ \tEducationRust\t\t10m
+ \tEducationGo\t\t10m
+",
+        )
+        .unwrap();
+        let history = ledger_time_history(user).unwrap();
+        let entery = history.iter().find(|e| e.id == "commented").unwrap();
+        let changed = shared::models::TimeEnteryHistory {
+            duration: 20,
+            ..entery.clone()
+        };
+        ledger_update_time_entery(user, &changed).unwrap();
+        let ledger = journal::read_file(&path).unwrap();
+        let transaction = ledger.transactions().next().unwrap();
+        assert_eq!(transaction.postings[0].comment, Some("tired".to_string()));
+        assert_eq!(
+            transaction.postings[1].comment,
+            Some("chapter 3".to_string())
+        );
+        assert_eq!(transaction.postings[1].minutes(), Some(20));
+
+        // enteries with more postings and line breaks, that start new transactions, are rejected
+        let split = history.iter().find(|e| e.id == "split").unwrap();
+        assert!(matches!(
+            ledger_update_time_entery(user, split),
+            Err(ServiceError::BadRequest(_))
+        ));
+        let injected = shared::models::TimeEnteryHistory {
+            headline: "Injected\n\n2022/10/08 Other".to_string(),
+            ..entery.clone()
+        };
+        assert!(matches!(
+            ledger_update_time_entery(user, &injected),
+            Err(ServiceError::BadRequest(_))
+        ));
+        let injected = shared::models::TimeEnteryHistory {
+            account_target: "EducationRust\n \tOther".to_string(),
+            ..entery.clone()
+        };
+        assert!(matches!(
+            ledger_update_time_entery(user, &injected),
+            Err(ServiceError::BadRequest(_))
+        ));
     }
 
     #[actix_web::test]
@@ -590,42 +1332,143 @@ mod tests {
             headline: "Carlos is programming".to_owned(),
             account_origin: "FreeTime".to_owned(),
             account_target: "Girokonto:N2".to_owned(),
-            ammount: 10 as f32,
+            ammount: Amount::parse("10").unwrap(),
             date: None,
-            target_file: "Finance".to_string(),
+            target_file: "Gesamt".to_string(),
+            postings: Vec::new(),
         };
         let mut remove_line = ledger_create_finance_entery(&TEST_USER, new_entery.clone()).unwrap();
         for _i in 1..1 {
             new_entery.account_target += "6";
             remove_line = ledger_create_finance_entery(&TEST_USER, new_entery.clone()).unwrap();
         }
-        //TODO find error
-        assert!(ledger_kill_time_entery(&TEST_USER, remove_line.clone()).is_ok());
+        // the ammount gets the commodity of the journal
+        assert!(remove_line.ends_with("10.00€\n"));
         //remove added line
-        let ledger = fs::read_to_string(format!(
-            "{}/{}/{}",
-            FILE_DIR, &TEST_USER, PATH_FINANCE_FILES[0]
-        ))
+        journal::modify_file(&user_file(TEST_USER, "gesamt.dat"), |ledger| {
+            Ok((ledger.replace(&remove_line, ""), ()))
+        })
         .unwrap();
-        fs::File::create(format!(
-            "{}/{}/{}",
-            FILE_DIR, &TEST_USER, PATH_FINANCE_FILES[0]
-        ))
-        .unwrap()
-        .write(ledger.replace(&format!("{}", &remove_line), "").as_bytes())
+
+        // unknown journals are rejected
+        new_entery.target_file = "Finance".to_string();
+        assert!(matches!(
+            ledger_create_finance_entery(TEST_USER, new_entery),
+            Err(ServiceError::BadRequest(_))
+        ));
+    }
+
+    #[actix_web::test]
+    async fn test_ledger_create_split_finance_entery() {
+        let posting = |account: &str, ammount: Option<&str>| shared::models::FinancePosting {
+            account: account.to_string(),
+            ammount: ammount.and_then(Amount::parse),
+            cost: None,
+        };
+        let mut new_entery = shared::models::NewFinanceEntery {
+            headline: "Split receipt".to_owned(),
+            target_file: "Gesamt".to_string(),
+            date: Some("2022/10/05".to_string()),
+            postings: vec![
+                posting("Girokonto:N2", None),
+                posting("Ausgaben:Essen", Some("12.50€")),
+                posting("Ausgaben:Haushalt", Some("3€")),
+            ],
+            ..Default::default()
+        };
+        let remove_line = ledger_create_finance_entery(TEST_USER, new_entery.clone()).unwrap();
+        let ledger = journal::parse(&remove_line);
+        let transaction = ledger.transactions().next().unwrap();
+        assert_eq!(transaction.postings.len(), 3);
+        assert_eq!(
+            transaction.values(),
+            vec![
+                Decimal::new(-1550, 2),
+                Decimal::new(1250, 2),
+                Decimal::from(3)
+            ]
+        );
+        assert!(remove_line.contains("Ausgaben:Essen\t\t\t\t\t\t\t\t12.50€\n"));
+        let suggestion = ledger_finance_suggestion(TEST_USER)
+            .unwrap()
+            .into_iter()
+            .find(|s| s.headline == "Split receipt")
+            .unwrap();
+        assert_eq!(suggestion.postings, new_entery.postings);
+        journal::modify_file(&user_file(TEST_USER, "gesamt.dat"), |ledger| {
+            Ok((ledger.replace(&remove_line, ""), ()))
+        })
         .unwrap();
+
+        // unbalanced and ambiguous enteries are rejected
+        new_entery.postings[0].ammount = Amount::parse("-15.00€");
+        assert!(matches!(
+            ledger_create_finance_entery(TEST_USER, new_entery.clone()),
+            Err(ServiceError::BadRequest(_))
+        ));
+        // every commodity has to balance on its own
+        new_entery.postings[0].ammount = Amount::parse("-15.50 USD");
+        assert!(matches!(
+            ledger_create_finance_entery(TEST_USER, new_entery.clone()),
+            Err(ServiceError::BadRequest(_))
+        ));
+        new_entery.postings[0].ammount = None;
+        new_entery.postings[1].ammount = None;
+        assert!(matches!(
+            ledger_create_finance_entery(TEST_USER, new_entery),
+            Err(ServiceError::BadRequest(_))
+        ));
+    }
+
+    #[actix_web::test]
+    async fn test_finance_entery_text_sale_with_cost() {
+        let new_entery = shared::models::NewFinanceEntery {
+            headline: "Sell ETF".to_owned(),
+            target_file: "Gesamt".to_string(),
+            date: Some("2022/10/06".to_string()),
+            postings: vec![
+                shared::models::FinancePosting {
+                    account: "Depot:ETF".to_string(),
+                    ammount: Amount::parse("-3 ETF"),
+                    // the sign of the cost is the one of the ammount
+                    cost: Amount::parse("-60.30€"),
+                },
+                shared::models::FinancePosting {
+                    account: "Girokonto:N2".to_string(),
+                    ammount: Amount::parse("60.30€"),
+                    cost: None,
+                },
+            ],
+            ..Default::default()
+        };
+        let (_, entery) = finance_entery_text(TEST_USER, &new_entery, &[]).unwrap();
+        assert!(entery.contains("-3 ETF @@ 60.30€\n"));
+        let ledger = journal::parse(&entery);
+        assert!(ledger.errors.is_empty());
+        let transaction = ledger.transactions().next().unwrap();
+        assert!(transaction.tag(journal::ID_TAG).is_some());
+        assert_eq!(transaction.postings[0].cost, Amount::parse("60.30€"));
+        assert_eq!(
+            transaction.values(),
+            vec![Decimal::new(-6030, 2), Decimal::new(6030, 2)]
+        );
+        assert!(
+            check::unbalanced(transaction.postings.iter().filter_map(|p| p.weight())).is_none()
+        );
     }
 
     #[actix_web::test]
     async fn test_ledger_suggestion_finance_entery() {
         let suggestion = ledger_finance_suggestion(&TEST_USER);
-        //println!("{:#?}", suggestion.as_ref().unwrap());
-        for ent in suggestion.as_ref().unwrap() {
-            if ent.account_target.contains(char::is_whitespace) {
-                println!("{:#?}", ent);
-            }
-        }
         assert!(suggestion.is_ok());
+        // the journals of the suggestions are valid
+        let kinds = [JournalKind::Time, JournalKind::Finance, JournalKind::Music];
+        let errors = check::ledger_check(TEST_USER, &kinds)
+            .unwrap()
+            .into_iter()
+            .filter(|d| d.severity == shared::models::Severity::Error)
+            .collect::<Vec<_>>();
+        assert!(errors.is_empty(), "{:#?}", errors);
     }
 
     #[actix_web::test]
@@ -635,10 +1478,45 @@ mod tests {
         assert!(suggestion.is_ok());
     }
 
-    #[actix_web::test]
-    async fn test_html_suggestion() {
-        let suggestion = html_suggestion(&"Carlos");
-        println!("{:#?}", suggestion.as_ref().unwrap());
-        assert!(suggestion.is_ok());
+    #[test]
+    fn test_filter_time_history() {
+        let entery =
+            |date: &str, headline: &str, account: &str| shared::models::TimeEnteryHistory {
+                date: date.to_string(),
+                headline: headline.to_string(),
+                account_origin: "FreeTime".to_string(),
+                account_target: account.to_string(),
+                ..shared::models::TimeEnteryHistory::default()
+            };
+        let history = vec![
+            entery("2022/10/02", "Rust book", "Education:Books"),
+            entery("2022/10/01", "Rust programming", "Education:Rust"),
+            entery("2022/10/03", "Running", "Sport"),
+            entery("2022/09/30", "rust Workshop", "Education:Rust"),
+        ];
+        let mut request = shared::models::RequestTimeHistory {
+            start: Some("2022-10-01".to_string()),
+            account: Some("Education".to_string()),
+            search: Some("RUST".to_string()),
+            sort: Some("desc".to_string()),
+            limit: Some(1),
+            ..shared::models::RequestTimeHistory::default()
+        };
+        let (page, total) = filter_time_history(history.clone(), &request).unwrap();
+        assert_eq!(total, 2);
+        assert_eq!(page, vec![history[0].clone()]);
+        request.offset = Some(1);
+        request.sort = Some("asc".to_string());
+        let (page, _) = filter_time_history(history.clone(), &request).unwrap();
+        assert_eq!(page, vec![history[0].clone()]);
+        // without selection everything is kept in the order of the journal
+        let (page, total) = filter_time_history(
+            history.clone(),
+            &shared::models::RequestTimeHistory::default(),
+        )
+        .unwrap();
+        assert_eq!((page, total), (history.clone(), 4));
+        request.sort = Some("newest".to_string());
+        assert!(filter_time_history(history, &request).is_err());
     }
 }
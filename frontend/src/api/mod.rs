@@ -1,5 +1,21 @@
 pub mod requests;
 
+use seed::prelude::*;
+
+/// notification of the pages, that the server rejected the access token
+#[derive(Clone)]
+pub struct Unauthorized;
+
+/// Notifies the login, if the request failed because of an expired access token.
+/// The login is refreshed and the page is loaded again.
+pub fn notify_unauthorized<Ms: 'static>(fetch_error: &FetchError, orders: &mut impl Orders<Ms>) {
+    if let FetchError::StatusError(status) = fetch_error {
+        if status.code == 401 {
+            orders.notify(Unauthorized);
+        }
+    }
+}
+
 fn get_api_url(path: String) -> String {
     return format!("http://127.0.0.1:8084/{}", path);
 }
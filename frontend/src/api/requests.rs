@@ -20,16 +20,222 @@ pub async fn get_login(
     .await
 }
 
-/// this function returns raw html for finance, time and music summary.
-pub async fn get_html(
+/// replaces the refresh token and returns a new access token
+pub async fn get_refresh(refresh_token: String) -> fetch::Result<shared::auth::UserLoginResponse> {
+    fetch(
+        Request::new(get_api_url(String::from("api/refresh.json")))
+            .method(Method::Post)
+            .json(&shared::auth::RefreshToken { refresh_token })?,
+    )
+    .await?
+    .check_status()?
+    .json()
+    .await
+}
+
+/// revokes the refresh token and the access token on the server
+pub async fn get_logout(
+    token: String,
+    refresh_token: String,
+) -> fetch::Result<shared::models::ResponseStatus> {
+    fetch(
+        Request::new(get_api_url(String::from("api/logout.json")))
+            .method(Method::Post)
+            .header(Header::bearer(token))
+            .json(&shared::auth::RefreshToken { refresh_token })?,
+    )
+    .await?
+    .check_status()?
+    .json()
+    .await
+}
+
+/// changes the password of the user and returns a new login, the other logins are revoked
+pub async fn get_password_change(
+    token: String,
+    request: shared::auth::PasswordChange,
+) -> fetch::Result<shared::auth::UserLoginResponse> {
+    fetch(
+        Request::new(get_api_url(String::from(
+            "api/auth/set_password_change.json",
+        )))
+        .method(Method::Post)
+        .header(Header::bearer(token))
+        .json(&request)?,
+    )
+    .await?
+    .check_status()?
+    .json()
+    .await
+}
+
+/// this function returns the balance report for finance, time and music summary.
+pub async fn get_report(
+    token: String,
+    request: shared::models::RequestReport,
+) -> fetch::Result<shared::models::ResponseReport> {
+    fetch(
+        Request::new(get_api_url(String::from("api/auth/get_report.json")))
+            .method(Method::Post)
+            .header(Header::bearer(token))
+            .json(&request)?,
+    )
+    .await?
+    .check_status()?
+    .json()
+    .await
+}
+
+/// returns the budgets of the user compared with the finance journals
+pub async fn get_budget_report(
+    token: String,
+    request: shared::models::RequestBudgetReport,
+) -> fetch::Result<shared::models::ResponseBudgetReport> {
+    fetch(
+        Request::new(get_api_url(String::from("api/auth/get_budget_report.json")))
+            .method(Method::Post)
+            .header(Header::bearer(token))
+            .json(&request)?,
+    )
+    .await?
+    .check_status()?
+    .json()
+    .await
+}
+
+/// creates or replaces the budget of an account
+pub async fn set_budget(
+    token: String,
+    budget: shared::models::Budget,
+) -> fetch::Result<shared::models::BudgetList> {
+    fetch(
+        Request::new(get_api_url(String::from("api/auth/set_budget.json")))
+            .method(Method::Post)
+            .header(Header::bearer(token))
+            .json(&budget)?,
+    )
+    .await?
+    .check_status()?
+    .json()
+    .await
+}
+
+/// removes the budget of an account
+pub async fn delete_budget(
+    token: String,
+    account: String,
+) -> fetch::Result<shared::models::BudgetList> {
+    fetch(
+        Request::new(get_api_url(String::from("api/auth/set_budget_delete.json")))
+            .method(Method::Post)
+            .header(Header::bearer(token))
+            .json(&shared::models::DeleteBudget { account })?,
+    )
+    .await?
+    .check_status()?
+    .json()
+    .await
+}
+
+/// returns the occurrences of the recurring transactions in the next days
+pub async fn get_recurring_upcoming(
+    token: String,
+    days: Option<u32>,
+) -> fetch::Result<shared::models::ResponseUpcoming> {
+    fetch(
+        Request::new(get_api_url(String::from(
+            "api/auth/get_recurring_upcoming.json",
+        )))
+        .method(Method::Post)
+        .header(Header::bearer(token))
+        .json(&shared::models::RequestUpcoming { days })?,
+    )
+    .await?
+    .check_status()?
+    .json()
+    .await
+}
+
+/// creates a recurring finance transaction
+pub async fn set_recurring(
+    token: String,
+    recurring: shared::models::RecurringTransaction,
+) -> fetch::Result<shared::models::RecurringList> {
+    fetch(
+        Request::new(get_api_url(String::from(
+            "api/auth/set_recurring_create.json",
+        )))
+        .method(Method::Post)
+        .header(Header::bearer(token))
+        .json(&recurring)?,
+    )
+    .await?
+    .check_status()?
+    .json()
+    .await
+}
+
+/// removes a recurring finance transaction
+pub async fn delete_recurring(
+    token: String,
+    id: String,
+) -> fetch::Result<shared::models::RecurringList> {
+    fetch(
+        Request::new(get_api_url(String::from(
+            "api/auth/set_recurring_delete.json",
+        )))
+        .method(Method::Post)
+        .header(Header::bearer(token))
+        .json(&shared::models::DeleteRecurring { id })?,
+    )
+    .await?
+    .check_status()?
+    .json()
+    .await
+}
+
+/// returns the bank profiles and import rules of the user
+pub async fn get_import_settings(token: String) -> fetch::Result<shared::models::ImportSettings> {
+    Request::new(get_api_url(String::from(
+        "api/auth/get_import_settings.json",
+    )))
+    .header(Header::bearer(token))
+    .fetch()
+    .await?
+    .check_status()?
+    .json()
+    .await
+}
+
+/// returns the proposed enteries of a bank statement
+pub async fn get_import_preview(
+    token: String,
+    request: shared::models::RequestImportPreview,
+) -> fetch::Result<shared::models::ResponseImportPreview> {
+    fetch(
+        Request::new(get_api_url(String::from(
+            "api/auth/get_import_preview.json",
+        )))
+        .method(Method::Post)
+        .header(Header::bearer(token))
+        .json(&request)?,
+    )
+    .await?
+    .check_status()?
+    .json()
+    .await
+}
+
+/// writes the accepted enteries of a bank statement
+pub async fn set_import(
     token: String,
-    selected: shared::models::HtmlSuggestion,
-) -> fetch::Result<shared::models::ResponseHtml> {
+    enteries: Vec<shared::models::NewFinanceEntery>,
+) -> fetch::Result<shared::models::ResponseStatus> {
     fetch(
-        Request::new(get_api_url(String::from("api/auth/get_html.json")))
+        Request::new(get_api_url(String::from("api/auth/set_import.json")))
             .method(Method::Post)
             .header(Header::bearer(token))
-            .json(&selected)?,
+            .json(&shared::models::RequestImport { enteries })?,
     )
     .await?
     .check_status()?
@@ -82,6 +288,28 @@ pub async fn get_finance_suggestion(
     .await
 }
 
+/// returns the tree of all accounts in the time and finance journals
+pub async fn get_account_tree(token: String) -> fetch::Result<shared::models::AccountTree> {
+    Request::new(get_api_url(String::from("api/auth/get_account_tree.json")))
+        .header(Header::bearer(token))
+        .fetch()
+        .await?
+        .check_status()?
+        .json()
+        .await
+}
+
+/// returns all journals of the user
+pub async fn get_journals(token: String) -> fetch::Result<shared::models::JournalList> {
+    Request::new(get_api_url(String::from("api/auth/get_journals.json")))
+        .header(Header::bearer(token))
+        .fetch()
+        .await?
+        .check_status()?
+        .json()
+        .await
+}
+
 /// this function returns a BTreeMap, witch encodes all running time Enteries
 pub async fn get_time_running_entery(
     token: String,
@@ -97,12 +325,27 @@ pub async fn get_time_running_entery(
     .await
 }
 
-/// this function returns a Vector, witch holds all history of time Enteries.
+/// returns one page of the time history, that matches the selection
 pub async fn get_time_history_entery(
     token: String,
+    request: shared::models::RequestTimeHistory,
 ) -> fetch::Result<shared::models::ResponseTimeEnteryHistory> {
-    Request::new(get_api_url(String::from(
-        "api/auth/get_time_entery_history.json",
+    let query = [
+        ("start", request.start),
+        ("end", request.end),
+        ("account", request.account),
+        ("search", request.search),
+        ("sort", request.sort),
+        ("limit", request.limit.map(|l| l.to_string())),
+        ("offset", request.offset.map(|o| o.to_string())),
+    ]
+    .into_iter()
+    .filter_map(|(key, value)| Some(format!("{}={}", key, Url::encode_uri_component(value?))))
+    .collect::<Vec<_>>()
+    .join("&");
+    Request::new(get_api_url(format!(
+        "api/auth/get_time_entery_history.json?{}",
+        query
     )))
     .header(Header::bearer(token))
     .fetch()
@@ -112,10 +355,27 @@ pub async fn get_time_history_entery(
     .await
 }
 
+/// returns overlapping enteries and untracked time of the selected days.
+pub async fn get_time_analysis(
+    token: String,
+    request: shared::models::RequestTimeAnalysis,
+) -> fetch::Result<shared::models::ResponseTimeAnalysis> {
+    fetch(
+        Request::new(get_api_url(String::from("api/auth/get_time_analysis.json")))
+            .method(Method::Post)
+            .header(Header::bearer(token))
+            .json(&request)?,
+    )
+    .await?
+    .check_status()?
+    .json()
+    .await
+}
+
 pub async fn start_time_entery(
     token: String,
     new_entery: shared::models::StartTimeEntery,
-) -> fetch::Result<shared::models::ResponseStatus> {
+) -> fetch::Result<shared::models::ResponseTimeEnteryStart> {
     fetch(
         Request::new(get_api_url(String::from(
             "api/auth/set_time_entery_start.json",
@@ -166,6 +426,43 @@ pub async fn kill_time_entery(
     .await
 }
 
+/// overwrites a finished time entery and returns it with its new id.
+pub async fn update_time_entery(
+    token: String,
+    entery: shared::models::TimeEnteryHistory,
+) -> fetch::Result<shared::models::ResponseTimeEnteryUpdate> {
+    fetch(
+        Request::new(get_api_url(String::from(
+            "api/auth/set_time_entery_update.json",
+        )))
+        .method(Method::Post)
+        .header(Header::bearer(token))
+        .json(&entery)?,
+    )
+    .await?
+    .check_status()?
+    .json()
+    .await
+}
+
+pub async fn delete_time_entery(
+    token: String,
+    entery: shared::models::DeleteTimeEntery,
+) -> fetch::Result<shared::models::ResponseStatus> {
+    fetch(
+        Request::new(get_api_url(String::from(
+            "api/auth/set_time_entery_delete.json",
+        )))
+        .method(Method::Post)
+        .header(Header::bearer(token))
+        .json(&entery)?,
+    )
+    .await?
+    .check_status()?
+    .json()
+    .await
+}
+
 pub async fn start_finance_entery(
     token: String,
     new_entery: shared::models::NewFinanceEntery,
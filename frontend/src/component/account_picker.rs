@@ -0,0 +1,76 @@
+//! Picker for accounts, that walks down the account tree segment by segment.
+use enclose::enc;
+use seed::{prelude::*, *};
+use shared::models::{AccountNode, JournalKind};
+
+/// The deepest node, whose account is a prefix of the input, and the nodes below it,
+/// that match the rest of the input.
+fn current_level<'a>(
+    tree: &'a [AccountNode],
+    account: &str,
+) -> (Option<&'a AccountNode>, Vec<&'a AccountNode>) {
+    let mut parent: Option<&AccountNode> = None;
+    let mut level = tree;
+    let mut rest = account;
+    for segment in account.split(':') {
+        match level.iter().find(|n| n.name == segment) {
+            Some(node) => {
+                parent = Some(node);
+                level = &node.children;
+                rest = account[node.account.len()..].trim_start_matches(':');
+            }
+            None => break,
+        }
+    }
+    let rest = rest.to_lowercase();
+    let mut nodes = level
+        .iter()
+        .filter(|n| n.name.to_lowercase().starts_with(&rest))
+        .collect::<Vec<_>>();
+    nodes.sort_by_key(|n| std::cmp::Reverse(n.usage));
+    (parent, nodes)
+}
+
+/// Shows the sub accounts of the input, most used first. Clicking an account selects it,
+/// the arrow selects the parent account.
+pub fn view<Ms: 'static>(
+    tree: &[AccountNode],
+    kind: JournalKind,
+    account: &str,
+    on_select: impl Fn(String) -> Ms + Clone + 'static,
+) -> Node<Ms> {
+    let (parent, nodes) = current_level(tree, account);
+    div![
+        C!["account-picker"],
+        style! {St::Display => "flex", St::FlexWrap => "wrap", St::Gap => px(5)},
+        parent.map(|p| {
+            let up = p
+                .account
+                .rsplit_once(':')
+                .map(|(up, _)| up.to_string())
+                .unwrap_or_default();
+            button!["▲", ev(Ev::Click, enc!((on_select) move |_| on_select(up))),]
+        }),
+        nodes
+            .into_iter()
+            .filter(|n| n.kinds.contains(&kind))
+            .map(|node| {
+                let selected = node.account.clone();
+                button![
+                    format!(
+                        "{}{} ({})",
+                        node.name,
+                        if node.children.is_empty() { "" } else { ":" },
+                        node.usage
+                    ),
+                    attrs! {At::Title => format!(
+                        "{}, last used {} in {}",
+                        node.account,
+                        node.last_used,
+                        node.journals.join(", ")
+                    )},
+                    ev(Ev::Click, enc!((on_select) move |_| on_select(selected))),
+                ]
+            }),
+    ]
+}
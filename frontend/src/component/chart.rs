@@ -0,0 +1,297 @@
+//! SVG charts of account balances. Every chart reports hovered parts with a tooltip text
+//! and clicked parts with their account.
+use enclose::enc;
+use seed::{prelude::*, *};
+use shared::amount::{Decimal, ToPrimitive};
+
+const WIDTH: f32 = 600.;
+const HEIGHT: f32 = 300.;
+const PADDING: f32 = 40.;
+const COLOURS: [&str; 10] = [
+    "#04a9b5", "#e0524f", "#f2b134", "#6a4c93", "#1982c4", "#8ac926", "#ff924c", "#c5ca30",
+    "#b5179e", "#52a675",
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ChartKind {
+    Bar,
+    Area,
+    Pie,
+}
+
+/// balances of an account, one value for every label of the chart
+#[derive(Clone, Debug, Default)]
+pub struct Series {
+    pub account: String,
+    pub colour: String,
+    pub values: Vec<Decimal>,
+}
+
+impl Series {
+    fn name(&self) -> &str {
+        self.account.rsplit(':').next().unwrap_or_default()
+    }
+
+    fn last(&self) -> Decimal {
+        self.values.last().copied().unwrap_or_default()
+    }
+}
+
+/// the balances are exact, only the drawing uses floats
+fn number(value: Decimal) -> f32 {
+    value.to_f32().unwrap_or_default()
+}
+
+pub fn colour(index: usize) -> String {
+    COLOURS[index % COLOURS.len()].to_string()
+}
+
+/// hover and click events of a part of a chart
+fn events<Ms: 'static>(
+    account: &str,
+    tooltip: String,
+    on_hover: &(impl Fn(Option<String>) -> Ms + Clone + 'static),
+    on_click: &(impl Fn(String) -> Ms + Clone + 'static),
+) -> Vec<EventHandler<Ms>> {
+    let account = account.to_string();
+    vec![
+        ev(
+            Ev::MouseEnter,
+            enc!((on_hover) move |_| on_hover(Some(tooltip))),
+        ),
+        ev(Ev::MouseLeave, enc!((on_hover) move |_| on_hover(None))),
+        ev(Ev::Click, enc!((on_click) move |_| on_click(account))),
+    ]
+}
+
+fn chart<Ms>(content: Vec<Node<Ms>>) -> Node<Ms> {
+    svg![
+        C!["chart"],
+        attrs! {
+            At::ViewBox => format!("0 0 {} {}", WIDTH, HEIGHT),
+            At::Width => "100%",
+        },
+        style! {St::Cursor => "Pointer", St::MaxWidth => px(WIDTH)},
+        content,
+    ]
+}
+
+fn label<Ms>(x: f32, y: f32, content: &str) -> Node<Ms> {
+    text![
+        attrs! {
+            At::X => x,
+            At::Y => y,
+            At::TextAnchor => "middle",
+            At::FontSize => 10,
+            At::Fill => "#ffffff",
+        },
+        content,
+    ]
+}
+
+/// One bar for the last value of every series. Negative values are drawn below the zero line.
+pub fn bar<Ms: 'static>(
+    series: &[Series],
+    format: impl Fn(Decimal) -> String,
+    on_hover: impl Fn(Option<String>) -> Ms + Clone + 'static,
+    on_click: impl Fn(String) -> Ms + Clone + 'static,
+) -> Node<Ms> {
+    let max = series.iter().map(|s| number(s.last())).fold(0., f32::max);
+    let min = series.iter().map(|s| number(s.last())).fold(0., f32::min);
+    let range = if max - min > 0. { max - min } else { 1. };
+    let scale = (HEIGHT - 2. * PADDING) / range;
+    let zero = PADDING + max * scale;
+    let width = (WIDTH - 2. * PADDING) / series.len().max(1) as f32;
+    chart(
+        series
+            .iter()
+            .enumerate()
+            .flat_map(|(i, s)| {
+                let x = PADDING + i as f32 * width;
+                let value = s.last();
+                let height = number(value) * scale;
+                let y = if height > 0. { zero - height } else { zero };
+                vec![
+                    rect![
+                        attrs! {
+                            At::X => x + width * 0.1,
+                            At::Y => y,
+                            At::Width => width * 0.8,
+                            At::Height => height.abs(),
+                            At::Fill => s.colour,
+                        },
+                        events(
+                            &s.account,
+                            format!("{}: {}", s.account, format(value)),
+                            &on_hover,
+                            &on_click,
+                        ),
+                    ],
+                    label(x + width / 2., HEIGHT - PADDING / 2., s.name()),
+                ]
+            })
+            .chain(std::iter::once(line_![attrs! {
+                At::X1 => PADDING,
+                At::Y1 => zero,
+                At::X2 => WIDTH - PADDING,
+                At::Y2 => zero,
+                At::Stroke => "#ffffff",
+            }]))
+            .collect(),
+    )
+}
+
+/// The series stacked over the labels. Absolute values are used, so expenses and incomes
+/// can be stacked.
+pub fn area<Ms: 'static>(
+    labels: &[String],
+    series: &[Series],
+    format: impl Fn(Decimal) -> String,
+    on_hover: impl Fn(Option<String>) -> Ms + Clone + 'static,
+    on_click: impl Fn(String) -> Ms + Clone + 'static,
+) -> Node<Ms> {
+    // upper border of every series at every label
+    let mut stacked = vec![vec![0.; labels.len()]];
+    for s in series {
+        let below = stacked.last().unwrap().clone();
+        stacked.push(
+            below
+                .iter()
+                .enumerate()
+                .map(|(i, b)| b + number(s.values.get(i).copied().unwrap_or_default()).abs())
+                .collect(),
+        );
+    }
+    let max = stacked.last().unwrap().iter().copied().fold(0., f32::max);
+    let scale = (HEIGHT - 2. * PADDING) / if max > 0. { max } else { 1. };
+    let step = (WIDTH - 2. * PADDING) / (labels.len().max(2) - 1) as f32;
+    let point = |i: usize, value: f32| {
+        format!(
+            "{},{}",
+            PADDING + i as f32 * step,
+            HEIGHT - PADDING - value * scale
+        )
+    };
+    let areas = series.iter().enumerate().map(|(n, s)| {
+        let points = stacked[n + 1]
+            .iter()
+            .enumerate()
+            .map(|(i, v)| point(i, *v))
+            .chain(
+                stacked[n]
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .map(|(i, v)| point(i, *v)),
+            )
+            .collect::<Vec<_>>()
+            .join(" ");
+        let tooltip = labels
+            .iter()
+            .zip(&s.values)
+            .map(|(l, v)| format!("{}: {}", l, format(*v)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        polygon![
+            attrs! {
+                At::Points => points,
+                At::Fill => s.colour,
+                At::FillOpacity => 0.8,
+            },
+            events(
+                &s.account,
+                format!("{} ({})", s.account, tooltip),
+                &on_hover,
+                &on_click,
+            ),
+        ]
+    });
+    let labels = labels
+        .iter()
+        .enumerate()
+        .map(|(i, l)| label(PADDING + i as f32 * step, HEIGHT - PADDING / 2., l));
+    chart(areas.chain(labels).collect())
+}
+
+/// One slice for the absolute last value of every series.
+pub fn pie<Ms: 'static>(
+    series: &[Series],
+    format: impl Fn(Decimal) -> String,
+    on_hover: impl Fn(Option<String>) -> Ms + Clone + 'static,
+    on_click: impl Fn(String) -> Ms + Clone + 'static,
+) -> Node<Ms> {
+    let total = series.iter().map(|s| number(s.last()).abs()).sum::<f32>();
+    let (cx, cy, r) = (WIDTH / 2., HEIGHT / 2., HEIGHT / 2. - PADDING / 2.);
+    let point = |angle: f32| (cx + r * angle.sin(), cy - r * angle.cos());
+    let mut angle = 0.;
+    let slices = series
+        .iter()
+        .filter(|s| !s.last().is_zero())
+        .map(|s| {
+            let share = number(s.last()).abs() / total;
+            let tooltip = format!("{}: {} ({:.1}%)", s.account, format(s.last()), share * 100.);
+            let events = events(&s.account, tooltip, &on_hover, &on_click);
+            let start = point(angle);
+            angle += share * std::f32::consts::TAU;
+            let end = point(angle);
+            if share >= 1. {
+                return circle![
+                    attrs! {At::Cx => cx, At::Cy => cy, At::R => r, At::Fill => s.colour},
+                    events,
+                ];
+            }
+            path![
+                attrs! {
+                    At::D => format!(
+                        "M {} {} L {} {} A {} {} 0 {} 1 {} {} Z",
+                        cx,
+                        cy,
+                        start.0,
+                        start.1,
+                        r,
+                        r,
+                        if share > 0.5 { 1 } else { 0 },
+                        end.0,
+                        end.1
+                    ),
+                    At::Fill => s.colour,
+                    At::Stroke => "#080710",
+                },
+                events,
+            ]
+        })
+        .collect();
+    chart(slices)
+}
+
+/// Clicking an account of the legend shows or hides it.
+pub fn legend<Ms: 'static>(
+    series: &[Series],
+    hidden: &[String],
+    on_toggle: impl Fn(String) -> Ms + Clone + 'static,
+) -> Node<Ms> {
+    div![
+        C!["chart-legend"],
+        style! {St::Display => "flex", St::FlexWrap => "wrap", St::Gap => px(10)},
+        series.iter().map(|s| {
+            let account = s.account.clone();
+            let visible = !hidden.contains(&s.account);
+            span![
+                style! {
+                    St::Cursor => "Pointer",
+                    St::Opacity => if visible { "1" } else { "0.4" },
+                    St::TextDecoration => if visible { "none" } else { "line-through" },
+                },
+                span![style! {
+                    St::Display => "inline-block",
+                    St::Width => px(12),
+                    St::Height => px(12),
+                    St::MarginRight => px(4),
+                    St::BackgroundColor => s.colour,
+                },],
+                s.name(),
+                ev(Ev::Click, enc!((on_toggle) move |_| on_toggle(account))),
+            ]
+        }),
+    ]
+}
@@ -0,0 +1,2 @@
+pub mod account_picker;
+pub mod chart;
@@ -11,6 +11,7 @@ pub struct General {
     pub form: Style,
     pub form_fix: Style,
     pub form_time_entery: Style,
+    pub form_conflict: Style,
 
     pub body_navbar: Style,
 
@@ -98,6 +99,9 @@ impl Default for General {
                 St::Width => px(460),
                 St::Height => px(540),
             },
+            form_conflict: style! {
+                St::Border => "2px solid #e0524f",
+            },
 
             body_navbar: style! {
                 //St::BackgroundColor => "#080710",
@@ -3,6 +3,7 @@
 use seed::{prelude::*, *};
 
 mod api;
+mod component;
 mod design;
 mod page;
 
@@ -11,6 +12,8 @@ use design::General;
 use shared::*;
 
 const STORAGE_KEY_CTX: &str = "ctx";
+// access tokens expire after 15 minutes
+const REFRESH_INTERVAL_MS: u32 = 10 * 60 * 1000;
 
 // ------ ------
 //     Init
@@ -21,13 +24,24 @@ fn init(url: Url, orders: &mut impl Orders<Msg>) -> Model {
     orders.subscribe(Msg::UrlChanged);
     orders
         .subscribe(Msg::UrlChanged)
+        .subscribe(|_: api::Unauthorized| Msg::Unauthorized)
         .notify(subs::UrlChanged(url.clone()));
-    let ctx = LocalStorage::get(STORAGE_KEY_CTX).ok();
+    let ctx: Option<shared::auth::UserLoginResponse> = LocalStorage::get(STORAGE_KEY_CTX).ok();
+    // the stored access token is probably expired, the page is loaded again after the refresh
+    if let Some(stored) = &ctx {
+        let refresh_token = stored.refresh_token.clone();
+        orders.perform_cmd(async { Msg::FetchedRefresh(get_refresh(refresh_token).await, true) });
+    }
+    orders.stream(streams::interval(REFRESH_INTERVAL_MS, || Msg::RefreshToken));
     Model {
         base_url: url.to_base_url(),
         page: Page::init(url, orders, &None),
+        refreshing: ctx.is_some(),
+        retried_token: None,
         ctx,
         login_data: shared::auth::UserLogin::default(),
+        password_change: shared::auth::PasswordChange::default(),
+        password_message: None,
     }
 }
 
@@ -39,7 +53,14 @@ pub struct Model {
     pub base_url: Url,
     pub page: Page,
     pub ctx: Option<shared::auth::UserLoginResponse>,
+    /// a refresh is running. A second refresh with the same token would revoke the login.
+    pub refreshing: bool,
+    /// access token of the refresh after a rejected request, it is not refreshed again
+    pub retried_token: Option<String>,
     pub login_data: shared::auth::UserLogin,
+    pub password_change: shared::auth::PasswordChange,
+    /// result of the last password change
+    pub password_message: Option<String>,
 }
 
 const MUSIC: &str = "music";
@@ -105,6 +126,19 @@ impl Page {
             _ => Self::NotFound,
         }
     }
+
+    fn set_ctx(&mut self, ctx: &Option<shared::auth::UserLoginResponse>) {
+        match self {
+            Self::LedgerSummary(model) => page::ledger_summary::set_ctx(model, ctx.clone()),
+            Self::TimeManagmentCreate(model) => {
+                page::time_managment_create::set_ctx(model, ctx.clone())
+            }
+            Self::FinanceManagmentCreate(model) => {
+                page::finance_managment_create::set_ctx(model, ctx.clone())
+            }
+            Self::Home | Self::NotFound => (),
+        }
+    }
 }
 
 // ------ ------
@@ -150,6 +184,17 @@ pub enum Msg {
     GetLoginRequest,
     GetLogoutRequest,
     FetchedLogin(fetch::Result<auth::UserLoginResponse>),
+    FetchedLogout(fetch::Result<shared::models::ResponseStatus>),
+    RefreshToken,
+    /// a page request was rejected, because the access token expired
+    Unauthorized,
+    /// the new login and if the page has to be loaded again
+    FetchedRefresh(fetch::Result<auth::UserLoginResponse>, bool),
+
+    SavePasswordCurrent(String),
+    SavePasswordNew(String),
+    GetPasswordChangeRequest,
+    FetchedPasswordChange(fetch::Result<auth::UserLoginResponse>),
 }
 
 // ------ ------
@@ -178,7 +223,44 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                 .perform_cmd(async { Msg::FetchedLogin(get_login(name, pwd).await) });
         }
         Msg::GetLogoutRequest => {
+            if let Some(ctx) = model.ctx.take() {
+                orders.perform_cmd(async {
+                    Msg::FetchedLogout(get_logout(ctx.token, ctx.refresh_token).await)
+                });
+            }
+            LocalStorage::remove(STORAGE_KEY_CTX).expect("remove logedin user.");
+        }
+        Msg::FetchedLogout(Ok(_)) => {
+            log!("Logged out.");
+        }
+        Msg::RefreshToken => refresh(model, orders, false),
+        Msg::Unauthorized => {
+            let token = model.ctx.as_ref().map(|ctx| &ctx.token);
+            if token.is_some() && token == model.retried_token.as_ref() {
+                log!("The refreshed login was rejected.");
+            } else {
+                refresh(model, orders, true);
+            }
+        }
+        Msg::FetchedRefresh(Ok(response_data), reload) => {
+            LocalStorage::insert(STORAGE_KEY_CTX, &response_data)
+                .expect("Failed to insert CTX to Local Storage.");
+            model.refreshing = false;
+            if reload {
+                model.retried_token = Some(response_data.token.clone());
+            }
+            model.ctx = Some(response_data);
+            if reload {
+                model.page = Page::init(Url::current(), orders, &model.ctx);
+            } else {
+                model.page.set_ctx(&model.ctx);
+            }
+        }
+        Msg::FetchedRefresh(Err(fetch_error), _) => {
+            // the login was revoked or is expired
+            log!("Refresh error:", fetch_error);
             LocalStorage::remove(STORAGE_KEY_CTX).expect("remove logedin user.");
+            model.refreshing = false;
             model.ctx = None;
         }
         Msg::FetchedLogin(Ok(response_data)) => {
@@ -188,6 +270,44 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
             model.ctx = Some(response_data);
         }
 
+        Msg::SavePasswordCurrent(pwd) => {
+            model.password_change.current_password = pwd;
+        }
+        Msg::SavePasswordNew(pwd) => {
+            model.password_change.new_password = pwd;
+        }
+        Msg::GetPasswordChangeRequest => {
+            if let Some(ctx) = &model.ctx {
+                let token = ctx.token.clone();
+                let request = model.password_change.clone();
+                orders.skip().perform_cmd(async {
+                    Msg::FetchedPasswordChange(get_password_change(token, request).await)
+                });
+            }
+        }
+        Msg::FetchedPasswordChange(Ok(response_data)) => {
+            LocalStorage::insert(STORAGE_KEY_CTX, &response_data)
+                .expect("Failed to insert CTX to Local Storage.");
+            model.ctx = Some(response_data);
+            model.page.set_ctx(&model.ctx);
+            model.password_change = shared::auth::PasswordChange::default();
+            model.password_message = Some("The password was changed.".to_string());
+        }
+        Msg::FetchedPasswordChange(Err(fetch_error)) => {
+            model.password_message = Some(match fetch_error {
+                FetchError::StatusError(status) if status.code == 401 => {
+                    "The current password is wrong.".to_string()
+                }
+                FetchError::StatusError(status) if status.code == 400 => {
+                    "The new password does not follow the password rules.".to_string()
+                }
+                _ => "The password could not be changed.".to_string(),
+            });
+        }
+
+        Msg::FetchedLogout(Err(fetch_error)) => {
+            log!("Logout error:", fetch_error);
+        }
         Msg::FetchedLogin(Err(fetch_error)) => {
             log!("Example_A error:", fetch_error);
             orders.skip();
@@ -219,6 +339,25 @@ fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
     }
 }
 
+/// Refreshes the login with the refresh token of the local storage, because another tab may
+/// have rotated the token of the model already.
+fn refresh(model: &mut Model, orders: &mut impl Orders<Msg>, reload: bool) {
+    if model.refreshing || model.ctx.is_none() {
+        return;
+    }
+    match LocalStorage::get::<_, shared::auth::UserLoginResponse>(STORAGE_KEY_CTX) {
+        Ok(stored) => {
+            model.refreshing = true;
+            let refresh_token = stored.refresh_token;
+            orders.skip().perform_cmd(async move {
+                Msg::FetchedRefresh(get_refresh(refresh_token).await, reload)
+            });
+        }
+        // logged out in another tab
+        Err(_) => model.ctx = None,
+    }
+}
+
 // ------ ------
 //     View
 // ------ ------
@@ -255,6 +394,8 @@ fn view(model: &Model) -> Node<Msg> {
             ],
             _ => empty![],
         },
+        IF!( model.ctx.is_some() && matches!(model.page, Page::Home) =>
+            view_password_change(&model.password_change, &model.password_message)),
         IF!( model.ctx.is_none() => view_login(&model.login_data)),
         match &model.page {
             Page::Home => page::home::view(),
@@ -364,6 +505,44 @@ fn view_login(login_data: &auth::UserLogin) -> Node<Msg> {
         ]
     ]
 }
+fn view_password_change(
+    password_change: &auth::PasswordChange,
+    message: &Option<String>,
+) -> Node<Msg> {
+    let general = General::default();
+    div![
+        C!["form"],
+        general.form,
+        h3!["Change Password"],
+        label!["Current Password", &general.label],
+        input![
+            input_ev(Ev::Input, Msg::SavePasswordCurrent),
+            attrs! {
+                At::Placeholder => "Current Password",
+                At::Value => password_change.current_password,
+                At::Type => "Password",
+            },
+            &general.input,
+        ],
+        label!["New Password", &general.label],
+        input![
+            input_ev(Ev::Input, Msg::SavePasswordNew),
+            attrs! {
+                At::Placeholder => "New Password",
+                At::Value => password_change.new_password,
+                At::Type => "Password",
+            },
+            &general.input,
+        ],
+        button![
+            ev(Ev::Click, |_| Msg::GetPasswordChangeRequest),
+            &general.button,
+            "Change Password"
+        ],
+        message.as_ref().map(|m| p![m]),
+    ]
+}
+
 // ------ ------
 //     Start
 // ------ ------
@@ -3,6 +3,11 @@ use fuzzy_matcher::skim::SkimMatcherV2;
 use fuzzy_matcher::FuzzyMatcher;
 use itertools::Itertools;
 use seed::{prelude::*, *};
+use shared::amount::Amount;
+
+use crate::component::account_picker;
+
+const FREQUENCIES: [&str; 4] = ["daily", "weekly", "monthly", "yearly"];
 
 // ------ ------
 //     Init
@@ -17,16 +22,43 @@ pub fn init(
         let token = ctx.clone().unwrap().token;
         async { Msg::GetSuggestion(token) }
     });
+    orders.perform_cmd({
+        let token = ctx.clone().unwrap().token;
+        async { Msg::FetchedJournals(api::requests::get_journals(token).await) }
+    });
+    orders.perform_cmd({
+        let token = ctx.clone().unwrap().token;
+        async { Msg::FetchedAccountTree(api::requests::get_account_tree(token).await) }
+    });
+    orders.send_msg(Msg::GetUpcoming);
+    orders.perform_cmd({
+        let token = ctx.clone().unwrap().token;
+        async { Msg::FetchedImportSettings(api::requests::get_import_settings(token).await) }
+    });
     Model {
         _base_url: url.to_base_url(),
         ctx,
         suggestions: None,
+        journals: Vec::new(),
+        accounts: Vec::new(),
         new_entery: shared::models::NewFinanceEntery::default(),
         suggestion_filter: "".to_string(),
         ammount: "".to_string(),
+        splits: Vec::new(),
+        frequency: "".to_string(),
+        upcoming: Vec::new(),
+        import_profiles: Vec::new(),
+        import_profile: "".to_string(),
+        import_content: "".to_string(),
+        import_preview: Vec::new(),
     }
 }
 
+/// sets the login after the access token was refreshed
+pub fn set_ctx(model: &mut Model, ctx: Option<shared::auth::UserLoginResponse>) {
+    model.ctx = ctx;
+}
+
 // ------ ------
 //     Model
 // ------ ------
@@ -35,9 +67,23 @@ pub struct Model {
     _base_url: Url,
     ctx: Option<shared::auth::UserLoginResponse>,
     suggestions: Option<shared::models::FinanceEnterySuggestion>,
+    // finance journals, that can be written to
+    journals: Vec<shared::models::JournalDefinition>,
+    accounts: Vec<shared::models::AccountNode>,
     new_entery: shared::models::NewFinanceEntery,
     suggestion_filter: String,
     ammount: String,
+    // further target accounts and ammounts of a split entery
+    splits: Vec<(String, String)>,
+    // repeat the entery with this frequency, if set
+    frequency: String,
+    upcoming: Vec<shared::models::Occurrence>,
+    import_profiles: Vec<shared::models::BankProfile>,
+    import_profile: String,
+    // content of the uploaded bank statement
+    import_content: String,
+    // proposed enteries and if they are written
+    import_preview: Vec<(bool, shared::models::ImportPreviewEntery)>,
 }
 
 // ------ Frequency ------
@@ -46,6 +92,13 @@ pub enum Msg {
     GetSuggestion(String),
     FetchedNewFinanceEntery(fetch::Result<shared::models::ResponseStatus>),
     FetchedSuggestion(fetch::Result<shared::models::FinanceEnterySuggestion>),
+    FetchedJournals(fetch::Result<shared::models::JournalList>),
+    FetchedAccountTree(fetch::Result<shared::models::AccountTree>),
+    FetchedRecurring(fetch::Result<shared::models::RecurringList>),
+    FetchedUpcoming(fetch::Result<shared::models::ResponseUpcoming>),
+    FetchedImportSettings(fetch::Result<shared::models::ImportSettings>),
+    FetchedImportPreview(fetch::Result<shared::models::ResponseImportPreview>),
+    FetchedImport(fetch::Result<shared::models::ResponseStatus>),
 
     SaveNewEnteryHeadline(String),
     SaveNewEnteryTarget(String),
@@ -53,8 +106,23 @@ pub enum Msg {
     SaveNewEnteryAmmount(String),
     SaveNewEnteryDate(String),
     SaveNewEnteryTargetFile(String),
+    AddSplit,
+    RemoveSplit(usize),
+    SaveSplitAccount(usize, String),
+    SaveSplitAmmount(usize, String),
+    SaveNewEnteryFrequency(String),
 
     NewFinanceEntery,
+    GetUpcoming,
+    DeleteRecurring(String),
+
+    SaveImportProfile(String),
+    ImportFileChanged(Option<web_sys::File>),
+    ImportFileRead(String),
+    ToggleImportEntery(usize),
+    SaveImportAccount(usize, String),
+    GetImportPreview,
+    Import,
 }
 // ------ ------
 //     Urls
@@ -91,6 +159,21 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
         Msg::SaveNewEnteryTargetFile(content) => {
             model.new_entery.target_file = content;
         }
+        Msg::AddSplit => {
+            model.splits.push((String::new(), String::new()));
+        }
+        Msg::RemoveSplit(index) => {
+            model.splits.remove(index);
+        }
+        Msg::SaveSplitAccount(index, content) => {
+            model.splits[index].0 = content;
+        }
+        Msg::SaveSplitAmmount(index, content) => {
+            model.splits[index].1 = content;
+        }
+        Msg::SaveNewEnteryFrequency(content) => {
+            model.frequency = content;
+        }
 
         Msg::GetSuggestion(token) => {
             orders.skip().perform_cmd({
@@ -102,33 +185,172 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
             if &model.new_entery.account_target == "" {
                 return;
             }
+            if !model.frequency.is_empty() && model.new_entery.date.is_none() {
+                log!("A recurring entery needs the date of its first occurrence.");
+                return;
+            }
             orders.skip().perform_cmd({
-                model.new_entery.ammount = match model.ammount.parse::<f32>() {
-                    Ok(n) => n,
-                    Err(_) => 0.0,
-                };
+                model.new_entery.ammount = parse_ammount(&model.ammount, model).unwrap_or_default();
                 let token = model.ctx.clone().unwrap().token;
                 let mut new_entery = model.new_entery.clone();
+                if !model.splits.is_empty() {
+                    new_entery.postings = split_postings(model);
+                }
                 new_entery.date = match new_entery.date {
                     Some(e) => Some(e.replace("-", "/")),
                     None => None,
                 };
-                async {
+                let frequency = model.frequency.clone();
+                async move {
+                    if !frequency.is_empty() {
+                        // the date of the entery is the first occurrence
+                        let recurring = shared::models::RecurringTransaction {
+                            start: new_entery.date.clone().unwrap_or_default(),
+                            frequency,
+                            entery: new_entery,
+                            ..shared::models::RecurringTransaction::default()
+                        };
+                        return Msg::FetchedRecurring(
+                            api::requests::set_recurring(token, recurring).await,
+                        );
+                    }
                     Msg::FetchedNewFinanceEntery(
                         api::requests::start_finance_entery(token, new_entery).await,
                     )
                 }
             });
         }
-        Msg::FetchedNewFinanceEntery(Ok(_response_data)) => {
+        Msg::FetchedNewFinanceEntery(Ok(response_data)) => {
+            for diagnostic in response_data.diagnostics {
+                log!(
+                    "Journal check:",
+                    format!("{}:{}", diagnostic.file, diagnostic.line),
+                    diagnostic.message
+                );
+            }
+            model.new_entery = shared::models::NewFinanceEntery::default();
+            model.splits.clear();
+        }
+        Msg::GetUpcoming => {
+            orders.skip().perform_cmd({
+                let token = model.ctx.clone().unwrap().token;
+                async {
+                    Msg::FetchedUpcoming(api::requests::get_recurring_upcoming(token, None).await)
+                }
+            });
+        }
+        Msg::DeleteRecurring(id) => {
+            orders.skip().perform_cmd({
+                let token = model.ctx.clone().unwrap().token;
+                async { Msg::FetchedRecurring(api::requests::delete_recurring(token, id).await) }
+            });
+        }
+        Msg::FetchedRecurring(Ok(_)) => {
             model.new_entery = shared::models::NewFinanceEntery::default();
+            model.splits.clear();
+            model.frequency.clear();
+            orders.send_msg(Msg::GetUpcoming);
+        }
+        Msg::FetchedUpcoming(Ok(response_data)) => {
+            model.upcoming = response_data.occurrences;
+        }
+        Msg::SaveImportProfile(content) => {
+            model.import_profile = content;
+        }
+        Msg::ImportFileChanged(Some(file)) => {
+            orders.perform_cmd(async move {
+                let text = JsFuture::from(file.text()).await.ok()?.as_string()?;
+                Some(Msg::ImportFileRead(text))
+            });
+        }
+        Msg::ImportFileChanged(None) => {
+            model.import_content.clear();
+        }
+        Msg::ImportFileRead(content) => {
+            model.import_content = content;
+        }
+        Msg::ToggleImportEntery(index) => {
+            model.import_preview[index].0 = !model.import_preview[index].0;
+        }
+        Msg::SaveImportAccount(index, content) => {
+            model.import_preview[index].1.entery.account_target = content;
+        }
+        Msg::GetImportPreview => {
+            if model.import_content.is_empty() {
+                return;
+            }
+            orders.skip().perform_cmd({
+                let token = model.ctx.clone().unwrap().token;
+                let request = shared::models::RequestImportPreview {
+                    profile: selected_profile(model).to_string(),
+                    target_file: selected_journal(model).to_string(),
+                    content: model.import_content.clone(),
+                };
+                async {
+                    Msg::FetchedImportPreview(
+                        api::requests::get_import_preview(token, request).await,
+                    )
+                }
+            });
+        }
+        Msg::Import => {
+            orders.skip().perform_cmd({
+                let token = model.ctx.clone().unwrap().token;
+                let enteries = model
+                    .import_preview
+                    .iter()
+                    .filter(|(selected, _)| *selected)
+                    .map(|(_, p)| p.entery.clone())
+                    .collect();
+                async { Msg::FetchedImport(api::requests::set_import(token, enteries).await) }
+            });
+        }
+        Msg::FetchedImportSettings(Ok(response_data)) => {
+            model.import_profiles = response_data.profiles;
+        }
+        Msg::FetchedImportPreview(Ok(response_data)) => {
+            // duplicates and enteries without target are not written by default
+            model.import_preview = response_data
+                .enteries
+                .into_iter()
+                .map(|p| (!p.duplicate && !p.entery.account_target.is_empty(), p))
+                .collect();
+        }
+        Msg::FetchedImport(Ok(response_data)) => {
+            for diagnostic in response_data.diagnostics {
+                log!(
+                    "Journal check:",
+                    format!("{}:{}", diagnostic.file, diagnostic.line),
+                    diagnostic.message
+                );
+            }
+            model.import_preview.clear();
+            model.import_content.clear();
         }
         Msg::FetchedSuggestion(Ok(response_data)) => {
             model.suggestions = Some(response_data);
         }
+        Msg::FetchedJournals(Ok(response_data)) => {
+            model.journals = response_data
+                .journals
+                .into_iter()
+                .filter(|j| j.kind == shared::models::JournalKind::Finance && !j.archived)
+                .collect();
+        }
+        Msg::FetchedAccountTree(Ok(response_data)) => {
+            model.accounts = response_data.accounts;
+        }
         Msg::FetchedSuggestion(Err(fetch_error))
+        | Msg::FetchedJournals(Err(fetch_error))
+        | Msg::FetchedAccountTree(Err(fetch_error))
+        | Msg::FetchedRecurring(Err(fetch_error))
+        | Msg::FetchedUpcoming(Err(fetch_error))
+        | Msg::FetchedImportSettings(Err(fetch_error))
+        | Msg::FetchedImportPreview(Err(fetch_error))
+        | Msg::FetchedImport(Err(fetch_error))
         | Msg::FetchedNewFinanceEntery(Err(fetch_error)) => {
             log!("Fetch error:", fetch_error);
+            api::notify_unauthorized(&fetch_error, orders);
             orders.skip();
         }
     }
@@ -167,7 +389,6 @@ pub fn view(model: &Model) -> Node<Msg> {
                 id!["suggestions_headline"],
                 suggestions
                     .iter()
-                    .rev()
                     .filter(|_s| empty)
                     .unique_by(|s| &s.headline)
                     .map(|s| { option![s.headline.clone()] }),
@@ -189,7 +410,6 @@ pub fn view(model: &Model) -> Node<Msg> {
                 id!["suggestions_target"],
                 suggestions
                     .iter()
-                    .rev()
                     .filter(|_s| empty)
                     .unique_by(|s| &s.account_target)
                     .map(|s| { option![s.account_target.clone()] }),
@@ -197,6 +417,12 @@ pub fn view(model: &Model) -> Node<Msg> {
                     .unique_by(|s| &s.account_target)
                     .map(|s| { option![s.account_target.clone()] })
             ],
+            account_picker::view(
+                &model.accounts,
+                shared::models::JournalKind::Finance,
+                &model.new_entery.account_target,
+                Msg::SaveNewEnteryTarget
+            ),
             input![
                 C!["input-content_origin"],
                 input_ev(Ev::Input, Msg::SaveNewEnteryOrigin),
@@ -212,7 +438,6 @@ pub fn view(model: &Model) -> Node<Msg> {
                 //show all if nothing is enterd yet
                 suggestions
                     .iter()
-                    .rev()
                     .filter(|_s| empty)
                     .unique_by(|s| &s.account_origin)
                     .map(|s| { option![s.account_origin.clone()] }),
@@ -220,6 +445,12 @@ pub fn view(model: &Model) -> Node<Msg> {
                     .unique_by(|s| &s.account_origin)
                     .map(|s| { option![s.account_origin.clone()] })
             ],
+            account_picker::view(
+                &model.accounts,
+                shared::models::JournalKind::Finance,
+                &model.new_entery.account_origin,
+                Msg::SaveNewEnteryOrigin
+            ),
             input![
                 C!["input-content_ammount"],
                 input_ev(Ev::Input, Msg::SaveNewEnteryAmmount),
@@ -234,23 +465,28 @@ pub fn view(model: &Model) -> Node<Msg> {
                 id!["suggestions_ammount"],
                 custom_suggestion(&suggestions, model)
                     .unique_by(|s| s.ammount.to_string())
-                    .map(|s| { option![format!("{:.2}", s.ammount)] }),
+                    .map(|s| { option![s.ammount.to_string()] }),
             ],
-            input![
+            model
+                .splits
+                .iter()
+                .enumerate()
+                .map(|(index, (account, ammount))| view_split(index, account, ammount)),
+            button![ev(Ev::Click, |_| Msg::AddSplit), "Split"],
+            IF!(!model.splits.is_empty() => label![format!(
+                "Total: {}",
+                split_total(model).iter().map(|a| a.to_string()).join(", ")
+            )]),
+            select![
                 C!["input-content-targetFile"],
-                input_ev(Ev::Input, Msg::SaveNewEnteryTargetFile),
-                attrs! {
-                    At::Placeholder => "Target File",
-                    At::AutoFocus => true.as_at_value();
-                    At::Value => &model.new_entery.target_file,
-                    At::List => "suggestions_target_file",
-                }
-            ],
-            datalist![
-                id!["suggestions_target_file"],
-                custom_suggestion(&suggestions, model)
-                    .unique_by(|s| &s.target_file)
-                    .map(|s| { option![s.target_file.clone()] })
+                input_ev(Ev::Change, Msg::SaveNewEnteryTargetFile),
+                model.journals.iter().map(|j| option![
+                    attrs! {
+                        At::Value => &j.name,
+                        At::Selected => (j.name == selected_journal(model)).as_at_value(),
+                    },
+                    &j.name
+                ]),
             ],
             input![
                 C!["input-content_date"],
@@ -262,11 +498,200 @@ pub fn view(model: &Model) -> Node<Msg> {
                     At::Value => &model.new_entery.date.clone().unwrap_or("".to_string()),
                 }
             ],
+            select![
+                C!["input-content-frequency"],
+                input_ev(Ev::Change, Msg::SaveNewEnteryFrequency),
+                option![
+                    attrs! {
+                        At::Value => "",
+                        At::Selected => model.frequency.is_empty().as_at_value(),
+                    },
+                    "once"
+                ],
+                FREQUENCIES.iter().map(|f| option![
+                    attrs! {
+                        At::Value => f,
+                        At::Selected => (&model.frequency == f).as_at_value(),
+                    },
+                    f
+                ]),
+            ],
             button![ev(Ev::Click, |_| Msg::NewFinanceEntery), "Hinzufuegen"],
         ],
+        IF!(!model.upcoming.is_empty() => view_upcoming(&model.upcoming)),
+        IF!(!model.import_profiles.is_empty() => view_import(model)),
+    ]
+}
+
+fn view_import(model: &Model) -> Node<Msg> {
+    div![
+        "Import bank statement",
+        div![
+            select![
+                C!["input-content-importProfile"],
+                input_ev(Ev::Change, Msg::SaveImportProfile),
+                model.import_profiles.iter().map(|p| option![
+                    attrs! {
+                        At::Value => &p.name,
+                        At::Selected => (p.name == selected_profile(model)).as_at_value(),
+                    },
+                    &p.name
+                ]),
+            ],
+            input![
+                C!["input-content-importFile"],
+                attrs! {
+                    At::Type => "file",
+                    At::Accept => ".csv,text/csv",
+                },
+                ev(Ev::Change, |event| {
+                    let file = event
+                        .target()
+                        .and_then(|t| t.dyn_into::<web_sys::HtmlInputElement>().ok())
+                        .and_then(|input| input.files())
+                        .and_then(|files| files.get(0));
+                    Msg::ImportFileChanged(file)
+                }),
+            ],
+            button![ev(Ev::Click, |_| Msg::GetImportPreview), "Preview"],
+        ],
+        IF!(!model.import_preview.is_empty() => div![
+            table![model.import_preview.iter().enumerate().map(|(index, (selected, p))| tr![
+                td![input![
+                    attrs! {
+                        At::Type => "checkbox",
+                        At::Checked => selected.as_at_value(),
+                    },
+                    ev(Ev::Change, move |_| Msg::ToggleImportEntery(index)),
+                ]],
+                td![p.entery.date.clone().unwrap_or_default()],
+                td![&p.entery.headline, br![], small![&p.description]],
+                td![p.entery.ammount.to_string()],
+                td![input![
+                    input_ev(Ev::Input, move |content| Msg::SaveImportAccount(index, content)),
+                    attrs! {
+                        At::Placeholder => "Target",
+                        At::Value => &p.entery.account_target,
+                        At::List => "suggestions_target",
+                    }
+                ]],
+                td![IF!(p.duplicate => "duplicate")],
+            ])],
+            button![ev(Ev::Click, |_| Msg::Import), "Import"],
+        ]),
+    ]
+}
+
+fn view_upcoming(upcoming: &[shared::models::Occurrence]) -> Node<Msg> {
+    div![
+        "Upcoming recurring enterys",
+        ul![upcoming.iter().map(|o| {
+            let id = o.id.clone();
+            li![
+                format!("{} {} ({})", o.date, o.headline, o.target_file),
+                button![ev(Ev::Click, move |_| Msg::DeleteRecurring(id)), "x"],
+            ]
+        })],
+    ]
+}
+
+fn view_split(index: usize, account: &str, ammount: &str) -> Node<Msg> {
+    div![
+        input![
+            C!["input-content_split_target"],
+            input_ev(Ev::Input, move |content| Msg::SaveSplitAccount(
+                index, content
+            )),
+            attrs! {
+                At::Placeholder => "Split Target",
+                At::Value => account,
+                At::List => "suggestions_target",
+            }
+        ],
+        input![
+            C!["input-content_split_ammount"],
+            input_ev(Ev::Input, move |content| Msg::SaveSplitAmmount(
+                index, content
+            )),
+            attrs! {
+                At::Placeholder => "Ammount",
+                At::Value => ammount,
+            }
+        ],
+        button![ev(Ev::Click, move |_| Msg::RemoveSplit(index)), "x"],
     ]
 }
 
+/// the origin gets the balancing value, target and splits get their ammount
+fn split_postings(model: &Model) -> Vec<shared::models::FinancePosting> {
+    let target = (&model.new_entery.account_target, &model.ammount);
+    std::iter::once(shared::models::FinancePosting {
+        account: model.new_entery.account_origin.clone(),
+        ammount: None,
+        cost: None,
+    })
+    .chain(
+        std::iter::once(target)
+            .chain(model.splits.iter().map(|(a, m)| (a, m)))
+            .map(|(account, ammount)| shared::models::FinancePosting {
+                account: account.clone(),
+                ammount: parse_ammount(ammount, model),
+                cost: None,
+            }),
+    )
+    .collect()
+}
+
+/// sum of the split postings for every commodity
+fn split_total(model: &Model) -> Vec<Amount> {
+    let mut total: Vec<Amount> = Vec::new();
+    for ammount in split_postings(model).into_iter().filter_map(|p| p.ammount) {
+        match total.iter_mut().find(|t| t.commodity == ammount.commodity) {
+            Some(t) => t.quantity += ammount.quantity,
+            None => total.push(ammount),
+        }
+    }
+    total
+}
+
+/// name of the selected journal. Without selection the first journal is the default.
+fn selected_journal(model: &Model) -> &str {
+    match model.new_entery.target_file.as_str() {
+        "" => model
+            .journals
+            .first()
+            .map(|j| j.name.as_str())
+            .unwrap_or(""),
+        name => name,
+    }
+}
+
+/// name of the selected bank profile. Without selection the first profile is the default.
+fn selected_profile(model: &Model) -> &str {
+    match model.import_profile.as_str() {
+        "" => model
+            .import_profiles
+            .first()
+            .map(|p| p.name.as_str())
+            .unwrap_or(""),
+        name => name,
+    }
+}
+
+/// ammount of the input. Without commodity the ammount is in the commodity of the journal.
+fn parse_ammount(input: &str, model: &Model) -> Option<Amount> {
+    let mut ammount = Amount::parse(input)?;
+    if ammount.commodity.is_empty() {
+        ammount.commodity = model
+            .journals
+            .iter()
+            .find(|j| j.name == selected_journal(model))
+            .map(|j| j.commodity.clone())
+            .unwrap_or_else(|| "€".to_string());
+    }
+    Some(ammount)
+}
+
 fn update_suggestion_filter(model: &mut Model) {
     model.suggestion_filter = if &model.new_entery.account_origin == ""
         && &model.new_entery.account_target == ""
@@ -358,31 +783,28 @@ pub fn custom_suggestion<'a>(
         .count() as i64
         * 5;
     //autofill
-    return suggestions
-        .iter()
-        .rev()
-        .filter(move |s| {
-            (&model.suggestion_filter == "account_target"
+    // the suggestions are ranked by the backend, best first
+    suggestions.iter().filter(move |s| {
+        (&model.suggestion_filter == "account_target"
+            && matcher
+                .fuzzy_match(
+                    &s.account_target,
+                    &model.new_entery.account_target.replace(" ", ""),
+                )
+                .unwrap_or(0)
+                > threshhold)
+            || (&model.suggestion_filter == "account_origin"
                 && matcher
                     .fuzzy_match(
-                        &s.account_target,
-                        &model.new_entery.account_target.replace(" ", ""),
+                        &s.account_origin,
+                        &model.new_entery.account_origin.replace(" ", ""),
                     )
                     .unwrap_or(0)
                     > threshhold)
-                || (&model.suggestion_filter == "account_origin"
-                    && matcher
-                        .fuzzy_match(
-                            &s.account_origin,
-                            &model.new_entery.account_origin.replace(" ", ""),
-                        )
-                        .unwrap_or(0)
-                        > threshhold)
-                || (&model.suggestion_filter == "headline"
-                    && matcher
-                        .fuzzy_match(&s.headline, &&model.new_entery.headline.replace(" ", ""))
-                        .unwrap_or(0)
-                        > threshhold)
-        })
-        .rev();
+            || (&model.suggestion_filter == "headline"
+                && matcher
+                    .fuzzy_match(&s.headline, &model.new_entery.headline.replace(" ", ""))
+                    .unwrap_or(0)
+                    > threshhold)
+    })
 }
@@ -2,9 +2,16 @@ use crate::api;
 use chrono::*;
 use itertools::Itertools;
 use seed::{prelude::*, *};
+use shared::amount::{Amount, Decimal};
 
+use crate::component::chart::{self, ChartKind};
 use crate::design::General;
 
+// reported periods of every budget
+const BUDGET_PERIODS: u32 = 6;
+// reported periods before the selection, that are shown in the area chart
+const CHART_HISTORY: u32 = 5;
+
 // ------ ------
 //     Init
 // ------ ------
@@ -38,13 +45,23 @@ pub fn init(
     selection_input.target = api_target.clone();
     orders.skip().perform_cmd({
         let token = ctx.clone().unwrap().token;
-        let selected = selected.clone();
-        async { Msg::FetchedSummary(api::requests::get_html(token, selected).await) }
+        let request = shared::models::RequestReport {
+            selection: selected.clone(),
+            html: true,
+            history: 0,
+        };
+        async { Msg::FetchedSummary(api::requests::get_report(token, request).await) }
     });
     orders.skip().perform_cmd({
         let token = ctx.clone().unwrap().token;
         async { Msg::FetchedSuggestion(api::requests::get_html_suggestion(token).await) }
     });
+    if api_target == FINANCE {
+        orders.skip().perform_cmd({
+            let token = ctx.clone().unwrap().token;
+            async { Msg::GetBudgetReport(token) }
+        });
+    }
     Model {
         ctx,
         _api_target: api_target,
@@ -55,9 +72,31 @@ pub fn init(
 
         summary: None,
         suggestion_filter: "".to_string(),
+
+        budgets: None,
+        new_budget: new_budget(),
+        new_budget_ammount: String::new(),
+
+        chart: None,
+        chart_report: None,
+        chart_drill: None,
+        chart_hidden: Vec::new(),
+        chart_tooltip: None,
+    }
+}
+
+fn new_budget() -> shared::models::Budget {
+    shared::models::Budget {
+        period: "month".to_string(),
+        ..shared::models::Budget::default()
     }
 }
 
+/// sets the login after the access token was refreshed
+pub fn set_ctx(model: &mut Model, ctx: Option<shared::auth::UserLoginResponse>) {
+    model.ctx = ctx;
+}
+
 // ------ ------
 //     Model
 // ------ ------
@@ -67,18 +106,33 @@ pub struct Model {
     _api_target: String,
 
     selected: shared::models::HtmlSuggestion,
-    summary: Option<shared::models::ResponseHtml>,
+    summary: Option<shared::models::ResponseReport>,
     suggestions: Option<shared::models::ResponseHtmlSuggestion>,
 
     selection_input: shared::models::HtmlSuggestion,
     suggestion_filter: String,
+
+    budgets: Option<shared::models::ResponseBudgetReport>,
+    new_budget: shared::models::Budget,
+    new_budget_ammount: String,
+
+    // without chart the report is shown as table
+    chart: Option<ChartKind>,
+    chart_report: Option<shared::models::ResponseReport>,
+    // parent account of the shown accounts
+    chart_drill: Option<String>,
+    chart_hidden: Vec<String>,
+    chart_tooltip: Option<String>,
 }
 
 const FINANCE: &str = "finance";
 
 pub enum Msg {
-    FetchedSummary(fetch::Result<shared::models::ResponseHtml>),
+    FetchedSummary(fetch::Result<shared::models::ResponseReport>),
     FetchedSuggestion(fetch::Result<shared::models::ResponseHtmlSuggestion>),
+    FetchedBudgetReport(fetch::Result<shared::models::ResponseBudgetReport>),
+    FetchedBudgetList(fetch::Result<shared::models::BudgetList>),
+    FetchedChart(fetch::Result<shared::models::ResponseReport>),
 
     SaveTimespan(String),
     SaveDate(String),
@@ -87,6 +141,20 @@ pub enum Msg {
     ClearDate,
     ClearDepth,
     SaveSelection,
+
+    GetBudgetReport(String),
+    SaveBudgetAccount(String),
+    SaveBudgetAmmount(String),
+    SaveBudgetPeriod(String),
+    ToggleBudgetRollover,
+    SetBudget,
+    DeleteBudget(String),
+
+    SelectChart(Option<ChartKind>),
+    ToggleSeries(String),
+    DrillDown(String),
+    DrillUp,
+    ShowTooltip(Option<String>),
 }
 
 // ------ ------
@@ -130,9 +198,17 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
             model.selected = model.selection_input.clone();
             orders.skip().perform_cmd({
                 let token = model.ctx.clone().unwrap().token;
-                let selected = model.selected.clone();
-                async { Msg::FetchedSummary(api::requests::get_html(token, selected).await) }
+                let request = shared::models::RequestReport {
+                    selection: model.selected.clone(),
+                    html: true,
+                    history: 0,
+                };
+                async { Msg::FetchedSummary(api::requests::get_report(token, request).await) }
             });
+            model.chart_report = None;
+            if model.chart.is_some() {
+                fetch_chart(orders, model);
+            }
         }
         Msg::FetchedSuggestion(Ok(response_data)) => {
             model.suggestions = Some(response_data);
@@ -141,8 +217,108 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
             model.summary = Some(response_data);
             model.suggestion_filter = "".to_string();
         }
-        Msg::FetchedSummary(Err(fetch_error)) | Msg::FetchedSuggestion(Err(fetch_error)) => {
+        Msg::GetBudgetReport(token) => {
+            orders.skip().perform_cmd(async {
+                let request = shared::models::RequestBudgetReport {
+                    periods: Some(BUDGET_PERIODS),
+                };
+                Msg::FetchedBudgetReport(api::requests::get_budget_report(token, request).await)
+            });
+        }
+        Msg::FetchedBudgetReport(Ok(response_data)) => {
+            model.budgets = Some(response_data);
+        }
+        Msg::SaveBudgetAccount(content) => {
+            model.new_budget.account = content;
+        }
+        Msg::SaveBudgetAmmount(content) => {
+            model.new_budget_ammount = content;
+        }
+        Msg::SaveBudgetPeriod(content) => {
+            model.new_budget.period = content;
+        }
+        Msg::ToggleBudgetRollover => {
+            model.new_budget.rollover = !model.new_budget.rollover;
+        }
+        Msg::SetBudget => {
+            let mut ammount = match Amount::parse(&model.new_budget_ammount) {
+                Some(a) => a,
+                None => return,
+            };
+            if ammount.commodity.is_empty() {
+                ammount.commodity = "€".to_string();
+            }
+            let budget = shared::models::Budget {
+                ammount,
+                ..model.new_budget.clone()
+            };
+            let token = model.ctx.clone().unwrap().token;
+            orders.skip().perform_cmd(async {
+                Msg::FetchedBudgetList(api::requests::set_budget(token, budget).await)
+            });
+        }
+        Msg::DeleteBudget(account) => {
+            let token = model.ctx.clone().unwrap().token;
+            orders.skip().perform_cmd(async {
+                Msg::FetchedBudgetList(api::requests::delete_budget(token, account).await)
+            });
+        }
+        Msg::FetchedBudgetList(Ok(_response_data)) => {
+            model.new_budget = new_budget();
+            model.new_budget_ammount = String::new();
+            let token = model.ctx.clone().unwrap().token;
+            orders.perform_cmd(async { Msg::GetBudgetReport(token) });
+        }
+        Msg::SelectChart(chart) => {
+            model.chart = chart;
+            model.chart_tooltip = None;
+            if model.chart.is_some() && model.chart_report.is_none() {
+                fetch_chart(orders, model);
+            }
+        }
+        Msg::FetchedChart(Ok(response_data)) => {
+            model.chart_report = Some(response_data);
+        }
+        Msg::ToggleSeries(account) => match model.chart_hidden.iter().position(|a| a == &account) {
+            Some(index) => {
+                model.chart_hidden.remove(index);
+            }
+            None => model.chart_hidden.push(account),
+        },
+        Msg::DrillDown(account) => {
+            let prefix = format!("{}:", account);
+            let has_children = model.chart_report.iter().any(|r| {
+                r.report
+                    .rows
+                    .iter()
+                    .chain(r.history.iter().flat_map(|h| h.rows.iter()))
+                    .any(|row| row.account.starts_with(&prefix))
+            });
+            if has_children {
+                model.chart_drill = Some(account);
+                model.chart_hidden = Vec::new();
+                model.chart_tooltip = None;
+            }
+        }
+        Msg::DrillUp => {
+            model.chart_drill = model
+                .chart_drill
+                .as_ref()
+                .and_then(|a| a.rsplit_once(':'))
+                .map(|(parent, _)| parent.to_string());
+            model.chart_hidden = Vec::new();
+            model.chart_tooltip = None;
+        }
+        Msg::ShowTooltip(tooltip) => {
+            model.chart_tooltip = tooltip;
+        }
+        Msg::FetchedSummary(Err(fetch_error))
+        | Msg::FetchedSuggestion(Err(fetch_error))
+        | Msg::FetchedBudgetReport(Err(fetch_error))
+        | Msg::FetchedBudgetList(Err(fetch_error))
+        | Msg::FetchedChart(Err(fetch_error)) => {
             log!("Fetch error:", fetch_error);
+            api::notify_unauthorized(&fetch_error, orders);
             orders.skip();
         }
     }
@@ -153,13 +329,12 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
 
 pub fn view(model: &Model) -> Node<Msg> {
     let summary_html = match model.summary.clone() {
-        Some(m) => m.html,
+        Some(m) => m.html.unwrap_or_default(),
         None => "".to_string(),
     };
-    let suggestions = match model.suggestions.clone() {
-        Some(m) => m.suggestions,
-        None => Vec::new(),
-    };
+    let selected = target_suggestion(model, &model.selected.target);
+    let custom = target_suggestion(model, &model.selection_input.target);
+    let custom_dates = custom_dates(model);
     let general = General::default();
     let empty_timespan = if &model.selection_input.timespan == "" {
         true
@@ -193,11 +368,11 @@ pub fn view(model: &Model) -> Node<Msg> {
             ],
             datalist![
                 id!["suggestions-timespan"],
-                suggestions
+                selected
+                    .filter(|_| empty_timespan)
                     .iter()
-                    .filter(|s| empty_timespan && s.target == model.selected.target)
-                    .unique_by(|s| &s.timespan)
-                    .map(|s| { option![s.timespan.clone()] }),
+                    .flat_map(|s| s.dates.keys())
+                    .map(|timespan| { option![timespan.clone()] }),
             ],
             input![
                 C!["input-content-date"],
@@ -218,19 +393,16 @@ pub fn view(model: &Model) -> Node<Msg> {
             ],
             datalist![
                 id!["suggestions-date"],
-                suggestions
+                selected
+                    .filter(|_| empty_timespan)
                     .iter()
-                    .filter(|s| empty_timespan && s.target == model.selected.target)
-                    .unique_by(|s| &s.date)
-                    .map(|s| { option![s.date.clone()] }),
-                suggestions
+                    .flat_map(|s| s.dates.values().flatten())
+                    .unique()
+                    .map(|date| { option![date.clone()] }),
+                custom_dates
                     .iter()
-                    .filter(move |s| {
-                        model.selection_input.target == s.target
-                            && model.selection_input.timespan == s.timespan
-                    })
-                    .unique_by(|s| &s.date)
-                    .map(|s| { option![s.date.clone()] })
+                    .flat_map(|dates| dates.iter())
+                    .map(|date| { option![date.clone()] })
                     .rev()
             ],
             input![
@@ -252,33 +424,250 @@ pub fn view(model: &Model) -> Node<Msg> {
             ],
             datalist![
                 id!["suggestions-depth"],
-                suggestions
-                    .iter()
-                    .filter(|s| empty_timespan && s.target == model.selected.target)
-                    .unique_by(|s| &s.depth)
-                    .map(|s| { option![s.depth.clone()] }),
-                suggestions
-                    .iter()
-                    .filter(move |s| {
-                        model.selection_input.target == s.target
-                            && model.selection_input.timespan == s.timespan
-                            && if model.selection_input.date != "" {
-                                model.selection_input.date == s.date
-                            } else {
-                                true
-                            }
+                selected
+                    .filter(|_| empty_timespan)
+                    .map(|s| s.depths())
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|depth| { option![depth] }),
+                custom
+                    .filter(|_| {
+                        custom_dates.is_some_and(|dates| {
+                            model.selection_input.date.is_empty()
+                                || dates.contains(&model.selection_input.date)
+                        })
                     })
-                    .unique_by(|s| &s.depth)
-                    .map(|s| { option![s.depth.clone()] })
+                    .map(|s| s.depths())
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|depth| { option![depth] })
             ],
         ],
+        view_chart_selection(model),
+        match model.chart {
+            Some(kind) => view_chart(model, kind),
+            None => div![
+                raw![&summary_html],
+                style! { St::Margin => "40px 40px 40px 40px"},
+            ],
+        },
+        IF!(model.selected.target == FINANCE => view_budgets(model)),
+    ]
+}
+
+fn view_chart_selection(model: &Model) -> Node<Msg> {
+    let general = General::default();
+    div![
+        C!["chart-selection"],
+        style! {St::Display => "flex", St::JustifyContent => "center", St::Gap => px(10)},
+        [
+            ("Table", None),
+            ("Bar", Some(ChartKind::Bar)),
+            ("Area", Some(ChartKind::Area)),
+            ("Pie", Some(ChartKind::Pie)),
+        ]
+        .into_iter()
+        .map(|(name, chart)| {
+            button![
+                ev(Ev::Click, move |_| Msg::SelectChart(chart)),
+                &general.button_small,
+                IF!(model.chart == chart => style! {St::TextDecoration => "underline"}),
+                name,
+            ]
+        }),
+    ]
+}
+
+fn view_chart(model: &Model, kind: ChartKind) -> Node<Msg> {
+    let general = General::default();
+    let (labels, series) = chart_series(model);
+    let commodity = match &model.chart_report {
+        Some(r) => r.report.commodity.clone(),
+        None => String::new(),
+    };
+    let format = |value: Decimal| Amount::new(value, &commodity).to_string();
+    let visible = series
+        .iter()
+        .filter(|s| !model.chart_hidden.contains(&s.account))
+        .cloned()
+        .collect_vec();
+    div![
+        C!["summary-chart"],
+        style! { St::Margin => "40px 40px 40px 40px"},
+        model.chart_drill.as_ref().map(|account| {
+            button![
+                ev(Ev::Click, |_| Msg::DrillUp),
+                &general.button_small,
+                format!("▲ {}", account),
+            ]
+        }),
+        chart::legend(&series, &model.chart_hidden, Msg::ToggleSeries),
+        div![
+            C!["chart-tooltip"],
+            style! {St::MinHeight => px(20), St::Margin => "10px 0"},
+            model.chart_tooltip.clone().unwrap_or_default(),
+        ],
+        match kind {
+            ChartKind::Bar => chart::bar(&visible, format, Msg::ShowTooltip, Msg::DrillDown),
+            ChartKind::Area => {
+                chart::area(&labels, &visible, format, Msg::ShowTooltip, Msg::DrillDown)
+            }
+            ChartKind::Pie => chart::pie(&visible, format, Msg::ShowTooltip, Msg::DrillDown),
+        },
+        IF!(model.chart_report.is_some() && series.is_empty() => div!["No data for the selection."]),
+    ]
+}
+
+/// The start of every reported period and the sub accounts of the drilled account with their
+/// balance in every period. Only the commodity of the journal is drawn, other commodities can
+/// not be compared with it. The series with the largest balances come first.
+fn chart_series(model: &Model) -> (Vec<String>, Vec<chart::Series>) {
+    let reports = match &model.chart_report {
+        Some(r) => r
+            .history
+            .iter()
+            .chain(std::iter::once(&r.report))
+            .collect_vec(),
+        None => Vec::new(),
+    };
+    let commodity = match &model.chart_report {
+        Some(r) => r.report.commodity.as_str(),
+        None => "",
+    };
+    let labels = reports.iter().map(|r| r.start.clone()).collect_vec();
+    let parent = |account: &str| account.rsplit_once(':').map(|(p, _)| p.to_string());
+    let mut series = reports
+        .iter()
+        .flat_map(|r| r.rows.iter())
+        .filter(|row| parent(&row.account) == model.chart_drill && row.commodity == commodity)
+        .map(|row| row.account.clone())
+        .unique()
+        .map(|account| chart::Series {
+            values: reports
+                .iter()
+                .map(|r| {
+                    r.rows
+                        .iter()
+                        .find(|row| row.account == account && row.commodity == commodity)
+                        .map(|row| row.balance)
+                        .unwrap_or_default()
+                })
+                .collect(),
+            account,
+            colour: String::new(),
+        })
+        .collect_vec();
+    let total = |s: &chart::Series| s.values.iter().map(|v| v.abs()).sum::<Decimal>();
+    series.sort_by_key(|s| std::cmp::Reverse(total(s)));
+    for (i, s) in series.iter_mut().enumerate() {
+        s.colour = chart::colour(i);
+    }
+    (labels, series)
+}
+
+fn view_budgets(model: &Model) -> Node<Msg> {
+    let general = General::default();
+    let budgets = match &model.budgets {
+        Some(b) => b.budgets.clone(),
+        None => Vec::new(),
+    };
+    div![
+        C!["budgets"],
+        style! { St::Margin => "40px 40px 40px 40px"},
+        budgets.iter().map(|report| {
+            let account = report.budget.account.clone();
+            table![
+                C!["budget-report"],
+                caption![
+                    format!(
+                        "{}: {} / {}{}",
+                        report.budget.account,
+                        report.budget.ammount,
+                        report.budget.period,
+                        if report.budget.rollover {
+                            " with rollover"
+                        } else {
+                            ""
+                        }
+                    ),
+                    button![
+                        ev(Ev::Click, move |_| Msg::DeleteBudget(account)),
+                        &general.button_small,
+                        "Delete"
+                    ],
+                ],
+                tr![th!["Period"], th!["Available"], th!["Actual"], th!["Remaining"]],
+                report.periods.iter().rev().map(|p| {
+                    tr![
+                        td![format!("{} - {}", p.start, p.end)],
+                        td![p.available.to_string()],
+                        td![p.actual.to_string()],
+                        td![
+                            IF!(p.remaining.quantity.is_sign_negative() => style! {St::Color => "#e0524f"}),
+                            p.remaining.to_string()
+                        ],
+                    ]
+                }),
+            ]
+        }),
         div![
-            raw![&summary_html],
-            style! { St::Margin => "40px 40px 40px 40px"},
+            C!["budget-new"],
+            input![
+                input_ev(Ev::Input, Msg::SaveBudgetAccount),
+                attrs! {
+                    At::Placeholder => "Budget Account",
+                    At::Value => &model.new_budget.account,
+                },
+                &general.input,
+            ],
+            input![
+                input_ev(Ev::Input, Msg::SaveBudgetAmmount),
+                attrs! {
+                    At::Placeholder => "Ammount",
+                    At::Value => &model.new_budget_ammount,
+                },
+                &general.input,
+            ],
+            select![
+                input_ev(Ev::Change, Msg::SaveBudgetPeriod),
+                ["week", "month", "year"].iter().map(|p| option![
+                    attrs! {
+                        At::Value => p,
+                        At::Selected => (p == &model.new_budget.period).as_at_value(),
+                    },
+                    p
+                ]),
+            ],
+            label![
+                input![
+                    ev(Ev::Change, |_| Msg::ToggleBudgetRollover),
+                    attrs! {
+                        At::Type => "checkbox",
+                        At::Checked => model.new_budget.rollover.as_at_value(),
+                    },
+                ],
+                "Rollover",
+            ],
+            button![ev(Ev::Click, |_| Msg::SetBudget), "Set Budget"],
         ],
     ]
 }
 
+fn fetch_chart(orders: &mut impl Orders<Msg>, model: &Model) {
+    let token = model.ctx.clone().unwrap().token;
+    let request = shared::models::RequestReport {
+        selection: shared::models::HtmlSuggestion {
+            depth: "all".to_string(),
+            ..model.selected.clone()
+        },
+        html: false,
+        history: CHART_HISTORY,
+    };
+    orders
+        .skip()
+        .perform_cmd(async { Msg::FetchedChart(api::requests::get_report(token, request).await) });
+}
+
 fn update_suggestion_filter(model: &mut Model) {
     model.suggestion_filter = if &model.selection_input.timespan == ""
         && &model.selection_input.date == ""
@@ -301,47 +690,42 @@ fn update_suggestion_filter(model: &mut Model) {
 }
 
 fn autofill(orders: &mut impl Orders<Msg>, model: &Model) {
-    let suggestions = match model.suggestions.clone() {
-        Some(m) => m.suggestions,
-        None => Vec::new(),
+    let dates = match custom_dates(model) {
+        Some(dates) => dates,
+        None => return,
     };
-
-    let suggestion_custom = custom_suggestion(&suggestions, model)
-        .unique_by(|s| &s.depth)
-        .map(|s| &s.depth)
-        .collect_vec();
-    if &suggestion_custom.len() == &(1 as usize) && &model.selection_input.depth == "" {
-        let autofill = suggestion_custom[0].to_string().clone();
+    let depths = target_suggestion(model, &model.selection_input.target)
+        .map(|s| s.depths())
+        .unwrap_or_default();
+    if depths.len() == 1 && model.selection_input.depth.is_empty() {
+        let autofill = depths[0].clone();
         orders
             .skip()
             .perform_cmd(async { Msg::SaveDepth(autofill) });
     }
-    let suggestion_custom = custom_suggestion(&suggestions, model)
-        .unique_by(|s| &s.date)
-        .map(|s| &s.date)
-        .collect_vec();
-    if &suggestion_custom.len() == &(1 as usize) && &model.selection_input.date == "" {
-        let autofill = suggestion_custom[0].to_string().clone();
+    if dates.len() == 1 && model.selection_input.date.is_empty() {
+        let autofill = dates[0].clone();
         orders.skip().perform_cmd(async { Msg::SaveDate(autofill) });
     }
-    let suggestion_custom = custom_suggestion(&suggestions, model)
-        .unique_by(|s| &s.timespan)
-        .map(|s| &s.timespan)
-        .collect_vec();
-    if &suggestion_custom.len() == &(1 as usize) && &model.selection_input.timespan == "" {
-        let autofill = suggestion_custom[0].to_string().clone();
-        orders
-            .skip()
-            .perform_cmd(async { Msg::SaveTimespan(autofill) });
-    }
 }
 
-pub fn custom_suggestion<'a>(
-    suggestions: &'a Vec<shared::models::HtmlSuggestion>,
+/// periods and depths of the target, that have data
+fn target_suggestion<'a>(
     model: &'a Model,
-) -> impl Iterator<Item = &'a shared::models::HtmlSuggestion> {
-    //TODO filter is not working
-    return suggestions.iter().filter(move |s| {
-        model.selection_input.target == s.target && model.selection_input.timespan == s.timespan
-    });
+    target: &str,
+) -> Option<&'a shared::models::TargetSuggestion> {
+    model
+        .suggestions
+        .as_ref()?
+        .suggestions
+        .iter()
+        .find(|s| s.target == target)
+}
+
+/// dates with data of the entered target and timespan
+fn custom_dates(model: &Model) -> Option<&Vec<String>> {
+    target_suggestion(model, &model.selection_input.target)?
+        .dates
+        .get(&model.selection_input.timespan)
+        .filter(|dates| !dates.is_empty())
 }
@@ -9,10 +9,15 @@ use seed::{prelude::*, *};
 use std::collections::BTreeMap;
 use web_sys::HtmlInputElement;
 
+use crate::component::account_picker;
 use crate::design::General;
 
 const ENTER_KEY: u32 = 13;
 const ESC_KEY: u32 = 27;
+// number of history enteries, that are loaded at once
+const HISTORY_COUNT: usize = 20;
+// distance to the end of the page in pixel, when the next history enteries are loaded
+const HISTORY_SCROLL_MARGIN: f64 = 300.0;
 
 type RunningEnteryId = String;
 type DeleteEnteryId = String;
@@ -44,26 +49,42 @@ pub fn init(
         let token = ctx.clone().unwrap().token;
         async { Msg::FetchedRunningEntery(api::requests::get_time_running_entery(token).await) }
     });
+    orders.stream(streams::window_event(Ev::Scroll, |_| Msg::HistoryScrolled));
+    orders.send_msg(Msg::GetHistory);
     orders.skip().perform_cmd({
         let token = ctx.clone().unwrap().token;
-        async { Msg::FetchedHistoryEntery(api::requests::get_time_history_entery(token).await) }
+        async { Msg::FetchedAccountTree(api::requests::get_account_tree(token).await) }
     });
     Model {
         _base_url: url.to_base_url(),
         ctx,
         suggestions: None,
+        accounts: Vec::new(),
         start_entery: shared::models::StartTimeEntery::default(),
         suggestion_filter: "".to_string(),
         //TODO group running_entery and running_entery timestamp together
         running_entery: None,
         running_entery_timestamp: None,
         history_entery: None,
+        history_request: shared::models::RequestTimeHistory {
+            sort: Some("desc".to_string()),
+            limit: Some(HISTORY_COUNT),
+            ..shared::models::RequestTimeHistory::default()
+        },
+        history_loading: false,
+        analysis: None,
         editing_offset: None,
+        editing_history: None,
         inverse_offset: -1,
         refs: Refs::default(),
     }
 }
 
+/// sets the login after the access token was refreshed
+pub fn set_ctx(model: &mut Model, ctx: Option<shared::auth::UserLoginResponse>) {
+    model.ctx = ctx;
+}
+
 // ------ ------
 //     Model
 // ------ ------
@@ -72,12 +93,18 @@ pub struct Model {
     _base_url: Url,
     ctx: Option<shared::auth::UserLoginResponse>,
     suggestions: Option<shared::models::HeadlineSuggestion>,
+    accounts: Vec<shared::models::AccountNode>,
     start_entery: shared::models::StartTimeEntery,
     suggestion_filter: String,
     running_entery: Option<shared::models::ResponseRunningLedgerTimeEntery>,
     running_entery_timestamp: Option<u32>,
+    // loaded pages of the history, newest first
     history_entery: Option<shared::models::ResponseTimeEnteryHistory>,
+    history_request: shared::models::RequestTimeHistory,
+    history_loading: bool,
+    analysis: Option<shared::models::ResponseTimeAnalysis>,
     editing_offset: Option<EditingNewTimeEntery>,
+    editing_history: Option<shared::models::TimeEnteryHistory>,
     inverse_offset: i32,
     refs: Refs,
 }
@@ -93,16 +120,26 @@ pub enum Msg {
     FetchedSuggestion(fetch::Result<shared::models::HeadlineSuggestion>),
     FetchedRunningEntery(fetch::Result<shared::models::ResponseRunningLedgerTimeEntery>),
     FetchedHistoryEntery(fetch::Result<shared::models::ResponseTimeEnteryHistory>),
-    FetchedStartTimeEntery(fetch::Result<shared::models::ResponseStatus>),
+    FetchedStartTimeEntery(fetch::Result<shared::models::ResponseTimeEnteryStart>),
     FetchedKillTimeEntery(fetch::Result<shared::models::ResponseStatus>),
     FetchedStopTimeEntery(fetch::Result<shared::models::ResponseStatus>),
     FetchedDeleteTimeEntery(fetch::Result<shared::models::ResponseStatus>),
+    FetchedUpdateTimeEntery(fetch::Result<shared::models::ResponseTimeEnteryUpdate>),
+    FetchedTimeAnalysis(fetch::Result<shared::models::ResponseTimeAnalysis>),
+    FetchedAccountTree(fetch::Result<shared::models::AccountTree>),
 
     StartTimeEntery,
     StopTimeEntery(RunningEnteryId),
     KillTimeEntery(RunningEnteryId),
     DeleteTimeEntery(DeleteEnteryId),
     UpdateRunningEnteryDuration,
+    GetHistory,
+    GetMoreHistory,
+    HistoryScrolled,
+    SaveHistorySearch(String),
+    SaveHistoryAccount(String),
+    SaveHistoryStart(String),
+    SaveHistoryEnd(String),
 
     SaveNewEnteryHeadline(String),
     SaveNewEnteryTarget(String),
@@ -117,6 +154,16 @@ pub enum Msg {
     SaveEditingRunningEnteryOffset,
     CancelRunningEnteryOffsetEdit,
     InverseRunningEnteryOffset,
+
+    StartHistoryEdit(DeleteEnteryId),
+    EditingHistoryHeadlineChanged(String),
+    EditingHistoryOriginChanged(String),
+    EditingHistoryTargetChanged(String),
+    EditingHistoryDateChanged(String),
+    EditingHistoryTimespanChanged(String),
+    EditingHistoryDurationChanged(String),
+    SaveHistoryEdit,
+    CancelHistoryEdit,
 }
 
 // ------ ------
@@ -249,7 +296,7 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                 }
             });
         }
-        Msg::StopTimeEntery(remove_line) => {
+        Msg::StopTimeEntery(id) => {
             //save offset if input is present
             if let Some(editing_offset) = model.editing_offset.take() {
                 let offset = editing_offset.offset;
@@ -270,13 +317,10 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                     .as_ref()
                     .unwrap()
                     .running_entery
-                    .get(&remove_line)
+                    .get(&id)
                     .unwrap()
                     .clone();
-                let stop_entery = shared::models::StopLedgerTimeEntery {
-                    remove_line,
-                    new_entery,
-                };
+                let stop_entery = shared::models::StopLedgerTimeEntery { id, new_entery };
                 log!(stop_entery);
                 async {
                     Msg::FetchedStopTimeEntery(
@@ -285,11 +329,11 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                 }
             });
         }
-        Msg::KillTimeEntery(remove_line) => {
+        Msg::KillTimeEntery(id) => {
             orders.skip().perform_cmd({
                 let token = model.ctx.clone().unwrap().token;
                 let kill_entery = shared::models::StopLedgerTimeEntery {
-                    remove_line,
+                    id,
                     new_entery: shared::models::NewTimeEntery::default(),
                 };
                 log!(kill_entery);
@@ -300,21 +344,71 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                 }
             });
         }
-        Msg::DeleteTimeEntery(remove_line) => {
+        Msg::DeleteTimeEntery(id) => {
             orders.skip().perform_cmd({
                 let token = model.ctx.clone().unwrap().token;
-                let delete_entery = shared::models::StopLedgerTimeEntery {
-                    remove_line,
-                    new_entery: shared::models::NewTimeEntery::default(),
-                };
+                let delete_entery = shared::models::DeleteTimeEntery { id };
                 log!(delete_entery);
                 async {
                     Msg::FetchedDeleteTimeEntery(
-                        api::requests::kill_time_entery(token, delete_entery).await,
+                        api::requests::delete_time_entery(token, delete_entery).await,
                     )
                 }
             });
         }
+        Msg::StartHistoryEdit(id) => {
+            model.editing_history = model
+                .history_entery
+                .as_ref()
+                .and_then(|h| h.history.iter().find(|e| e.id == id))
+                .cloned();
+        }
+        Msg::EditingHistoryHeadlineChanged(content) => {
+            if let Some(ref mut entery) = model.editing_history {
+                entery.headline = content;
+            }
+        }
+        Msg::EditingHistoryOriginChanged(content) => {
+            if let Some(ref mut entery) = model.editing_history {
+                entery.account_origin = content;
+            }
+        }
+        Msg::EditingHistoryTargetChanged(content) => {
+            if let Some(ref mut entery) = model.editing_history {
+                entery.account_target = content;
+            }
+        }
+        Msg::EditingHistoryDateChanged(content) => {
+            if let Some(ref mut entery) = model.editing_history {
+                entery.date = content.replace('-', "/");
+            }
+        }
+        Msg::EditingHistoryTimespanChanged(content) => {
+            if let Some(ref mut entery) = model.editing_history {
+                entery.timespan = content;
+            }
+        }
+        Msg::EditingHistoryDurationChanged(content) => {
+            if let Some(ref mut entery) = model.editing_history {
+                entery.duration = content.parse::<u32>().unwrap_or(entery.duration);
+            }
+        }
+        Msg::SaveHistoryEdit => {
+            if let Some(entery) = model.editing_history.take() {
+                orders.skip().perform_cmd({
+                    let token = model.ctx.clone().unwrap().token;
+                    log!("Update {:#?}", &entery);
+                    async {
+                        Msg::FetchedUpdateTimeEntery(
+                            api::requests::update_time_entery(token, entery).await,
+                        )
+                    }
+                });
+            }
+        }
+        Msg::CancelHistoryEdit => {
+            model.editing_history = None;
+        }
         Msg::UpdateRunningEnteryDuration => {
             let stop_minute: u32 =
                 (u32::from(chrono::Local::now().hour() * 60 + chrono::Local::now().minute()))
@@ -340,12 +434,7 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                     Msg::FetchedRunningEntery(api::requests::get_time_running_entery(token).await)
                 }
             });
-            orders.skip().perform_cmd({
-                let token = model.ctx.clone().unwrap().token;
-                async {
-                    Msg::FetchedHistoryEntery(api::requests::get_time_history_entery(token).await)
-                }
-            });
+            orders.send_msg(Msg::GetHistory);
         }
         Msg::FetchedKillTimeEntery(Ok(_response_data)) => {
             model.start_entery = shared::models::StartTimeEntery::default();
@@ -364,25 +453,32 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
                     Msg::FetchedRunningEntery(api::requests::get_time_running_entery(token).await)
                 }
             });
-            orders.skip().perform_cmd({
-                let token = model.ctx.clone().unwrap().token;
-                async {
-                    Msg::FetchedHistoryEntery(api::requests::get_time_history_entery(token).await)
-                }
-            });
+            orders.send_msg(Msg::GetHistory);
         }
         Msg::FetchedDeleteTimeEntery(Ok(_response_data)) => {
             model.start_entery = shared::models::StartTimeEntery::default();
+            orders.send_msg(Msg::GetHistory);
+        }
+        Msg::FetchedUpdateTimeEntery(Ok(response_data)) => {
+            for diagnostic in response_data.diagnostics {
+                log!(
+                    "Journal check:",
+                    format!("{}:{}", diagnostic.file, diagnostic.line),
+                    diagnostic.message
+                );
+            }
+            orders.send_msg(Msg::GetHistory);
             orders.skip().perform_cmd({
                 let token = model.ctx.clone().unwrap().token;
-                async {
-                    Msg::FetchedHistoryEntery(api::requests::get_time_history_entery(token).await)
-                }
+                async { Msg::FetchedSuggestion(api::requests::get_time_suggestion(token).await) }
             });
         }
         Msg::FetchedSuggestion(Ok(response_data)) => {
             model.suggestions = Some(response_data);
         }
+        Msg::FetchedAccountTree(Ok(response_data)) => {
+            model.accounts = response_data.accounts;
+        }
         Msg::FetchedRunningEntery(Ok(response_data)) => {
             log!("Running Enteries: ", response_data);
             model.running_entery = Some(response_data);
@@ -390,21 +486,119 @@ pub fn update(msg: Msg, model: &mut Model, orders: &mut impl Orders<Msg>) {
             log!("now min {:?}", now_minute);
             model.running_entery_timestamp = Some(now_minute);
         }
+        Msg::GetHistory => {
+            model.history_request.offset = None;
+            fetch_history(model, orders);
+        }
+        Msg::GetMoreHistory => {
+            let loaded = model.history_entery.as_ref().map(|h| h.history.len());
+            let total = model.history_entery.as_ref().map(|h| h.total);
+            if model.history_loading || loaded >= total {
+                orders.skip();
+                return;
+            }
+            model.history_request.offset = loaded;
+            fetch_history(model, orders);
+        }
+        Msg::HistoryScrolled => {
+            let window = window();
+            let bottom = window
+                .inner_height()
+                .ok()
+                .and_then(|h| h.as_f64())
+                .unwrap_or_default()
+                + window.scroll_y().unwrap_or_default();
+            let height = document()
+                .body()
+                .map(|b| b.scroll_height())
+                .unwrap_or_default() as f64;
+            if bottom + HISTORY_SCROLL_MARGIN >= height {
+                orders.send_msg(Msg::GetMoreHistory);
+            }
+            orders.skip();
+        }
+        Msg::SaveHistorySearch(content) => {
+            model.history_request.search = Some(content).filter(|c| !c.is_empty());
+            orders.send_msg(Msg::GetHistory);
+        }
+        Msg::SaveHistoryAccount(content) => {
+            model.history_request.account = Some(content).filter(|c| !c.is_empty());
+            orders.send_msg(Msg::GetHistory);
+        }
+        Msg::SaveHistoryStart(content) => {
+            model.history_request.start = Some(content).filter(|c| !c.is_empty());
+            orders.send_msg(Msg::GetHistory);
+        }
+        Msg::SaveHistoryEnd(content) => {
+            model.history_request.end = Some(content).filter(|c| !c.is_empty());
+            orders.send_msg(Msg::GetHistory);
+        }
         Msg::FetchedHistoryEntery(Ok(response_data)) => {
-            model.history_entery = Some(response_data);
+            model.history_loading = false;
+            // later pages are added to the loaded enteries
+            let history = match (model.history_request.offset, model.history_entery.take()) {
+                (Some(_), Some(mut loaded)) => {
+                    loaded.history.extend(response_data.history);
+                    loaded.total = response_data.total;
+                    loaded
+                }
+                _ => response_data,
+            };
+            // check the days of the shown enteries for conflicts
+            let dates = history.history.iter().map(|e| e.date.clone()).collect_vec();
+            if let (Some(date_from), Some(date_to)) = (dates.iter().min(), dates.iter().max()) {
+                orders.skip().perform_cmd({
+                    let token = model.ctx.clone().unwrap().token;
+                    let request = shared::models::RequestTimeAnalysis {
+                        date_from: date_from.clone(),
+                        date_to: date_to.clone(),
+                        ..shared::models::RequestTimeAnalysis::default()
+                    };
+                    async {
+                        Msg::FetchedTimeAnalysis(
+                            api::requests::get_time_analysis(token, request).await,
+                        )
+                    }
+                });
+            }
+            model.history_entery = Some(history);
+        }
+        Msg::FetchedTimeAnalysis(Ok(response_data)) => {
+            model.analysis = Some(response_data);
         }
         Msg::FetchedSuggestion(Err(fetch_error))
+        | Msg::FetchedAccountTree(Err(fetch_error))
         | Msg::FetchedRunningEntery(Err(fetch_error))
-        | Msg::FetchedHistoryEntery(Err(fetch_error))
         | Msg::FetchedStartTimeEntery(Err(fetch_error))
         | Msg::FetchedKillTimeEntery(Err(fetch_error))
         | Msg::FetchedStopTimeEntery(Err(fetch_error))
-        | Msg::FetchedDeleteTimeEntery(Err(fetch_error)) => {
+        | Msg::FetchedDeleteTimeEntery(Err(fetch_error))
+        | Msg::FetchedUpdateTimeEntery(Err(fetch_error))
+        | Msg::FetchedTimeAnalysis(Err(fetch_error)) => {
             log!("Fetch error:", fetch_error);
+            api::notify_unauthorized(&fetch_error, orders);
+            orders.skip();
+        }
+        Msg::FetchedHistoryEntery(Err(fetch_error)) => {
+            log!("Fetch error:", fetch_error);
+            api::notify_unauthorized(&fetch_error, orders);
+            model.history_loading = false;
             orders.skip();
         }
     }
 }
+
+/// loads the history page of the current selection
+fn fetch_history(model: &mut Model, orders: &mut impl Orders<Msg>) {
+    model.history_loading = true;
+    orders.skip().perform_cmd({
+        let token = model.ctx.clone().unwrap().token;
+        let request = model.history_request.clone();
+        async {
+            Msg::FetchedHistoryEntery(api::requests::get_time_history_entery(token, request).await)
+        }
+    });
+}
 // ------ ------
 //     View
 // ------ ------
@@ -422,6 +616,10 @@ pub fn view(model: &Model) -> Node<Msg> {
         Some(m) => m.history,
         None => Vec::new(),
     };
+    let overlaps = match &model.analysis {
+        Some(a) => a.days.iter().flat_map(|d| d.overlaps.iter()).collect_vec(),
+        None => Vec::new(),
+    };
     let empty = if &model.suggestion_filter == "" {
         true
     } else {
@@ -459,7 +657,6 @@ pub fn view(model: &Model) -> Node<Msg> {
                 id!["suggestions-headline"],
                 suggestions
                     .iter()
-                    .rev()
                     .filter(|_s| empty)
                     .unique_by(|s| &s.headline)
                     .map(|s| { option![s.headline.clone()] }),
@@ -482,7 +679,6 @@ pub fn view(model: &Model) -> Node<Msg> {
                 id!["suggestions_target"],
                 suggestions
                     .iter()
-                    .rev()
                     .filter(|_s| empty)
                     .unique_by(|s| &s.account_target)
                     .map(|s| { option![s.account_target.clone()] }),
@@ -490,6 +686,12 @@ pub fn view(model: &Model) -> Node<Msg> {
                     .unique_by(|s| &s.account_target)
                     .map(|s| { option![s.account_target.clone()] })
             ],
+            account_picker::view(
+                &model.accounts,
+                shared::models::JournalKind::Time,
+                &model.start_entery.account_target,
+                Msg::SaveNewEnteryTarget
+            ),
             div![
                 style! {St::Display => "flex", St::FlexDirection => "row", St::JustifyContent => "center", St::Width => "100%"},
                 button![
@@ -554,15 +756,16 @@ pub fn view(model: &Model) -> Node<Msg> {
             St::JustifyContent => "space-evenly",
             St::FlexWrap => "wrap",
             },
-            running_entery.iter().filter_map(|(remove_line, entery)| {
-                Some(view_runing_enteries(
-                    remove_line.to_string(),
+            running_entery.iter().map(|(id, entery)| {
+                view_runing_enteries(
+                    id.to_string(),
                     entery,
                     &model.editing_offset,
                     &model.refs.editing_running_entery_input,
-                ))
-            },),
+                )
+            }),
         ],
+        view_history_filter(&model.history_request, &general),
         div![
             style! {
             St::Width => "100%",
@@ -571,16 +774,68 @@ pub fn view(model: &Model) -> Node<Msg> {
             St::JustifyContent => "space-evenly",
             St::FlexWrap => "wrap",
             },
-            history_entery.iter().rev().take(20).map(|entery| {
+            history_entery.iter().map(|entery| {
                 Some(view_history_enteries(
                     entery,
-                    entery.remove_entery.to_string(),
+                    entery.id.to_string(),
+                    &model.editing_history,
+                    overlaps
+                        .iter()
+                        .filter(|o| o.first_id == entery.id || o.second_id == entery.id)
+                        .copied()
+                        .collect_vec(),
                 ))
             },),
         ],
     ]
 }
 
+fn view_history_filter(
+    request: &shared::models::RequestTimeHistory,
+    general: &General,
+) -> Node<Msg> {
+    div![
+        style! {St::Display => "flex", St::FlexDirection => "row", St::JustifyContent => "center", St::FlexWrap => "wrap", St::Width => "100%"},
+        input![
+            C!["input-content_history_search"],
+            input_ev(Ev::Input, Msg::SaveHistorySearch),
+            attrs! {
+                At::Placeholder => "Search",
+                At::Value => request.search.clone().unwrap_or_default(),
+            },
+            &general.input,
+        ],
+        input![
+            C!["input-content_history_account"],
+            input_ev(Ev::Input, Msg::SaveHistoryAccount),
+            attrs! {
+                At::Placeholder => "Account",
+                At::Value => request.account.clone().unwrap_or_default(),
+                At::List => "suggestions_target",
+            },
+            &general.input,
+        ],
+        input![
+            C!["input-content_history_start"],
+            input_ev(Ev::Input, Msg::SaveHistoryStart),
+            attrs! {
+                At::Type => "date",
+                At::Value => request.start.clone().unwrap_or_default(),
+            },
+            &general.input,
+        ],
+        input![
+            C!["input-content_history_end"],
+            input_ev(Ev::Input, Msg::SaveHistoryEnd),
+            attrs! {
+                At::Type => "date",
+                At::Value => request.end.clone().unwrap_or_default(),
+            },
+            &general.input,
+        ],
+    ]
+}
+
 //TODO add change name / headline of running entery
 fn view_runing_enteries(
     id: RunningEnteryId,
@@ -663,36 +918,120 @@ fn view_runing_enteries(
 
 fn view_history_enteries(
     history: &shared::models::TimeEnteryHistory,
-    id: RunningEnteryId,
+    id: DeleteEnteryId,
+    editing_history: &Option<shared::models::TimeEnteryHistory>,
+    overlaps: Vec<&shared::models::TimeOverlap>,
 ) -> Node<Msg> {
     let general = General::default();
-    div![
-        &general.form,
-        style! {
-            St::Display => "flex",
-            St::FlexDirection => "column",
-            St::JustifyContent => "flex-start",
-            St::Padding => "25px 25px 25px 25px",
-            St::Margin => "25px auto 25px auto",
-        },
-        h3![history.headline.clone()],
-        label![history.account_target.clone(), &general.label],
-        label![
-            format!(
-                "{} [ {} ] {}m",
-                history.timespan,
-                history.date.clone().replace("/", " "),
-                history.duration,
-            ),
-            &general.label
+    let form = style! {
+        St::Display => "flex",
+        St::FlexDirection => "column",
+        St::JustifyContent => "flex-start",
+        St::Padding => "25px 25px 25px 25px",
+        St::Margin => "25px auto 25px auto",
+    };
+    match editing_history {
+        Some(editing) if editing.id == id => {
+            let edit_keys = || {
+                keyboard_ev(Ev::KeyDown, |keyboard_event| {
+                    match keyboard_event.key_code() {
+                        ENTER_KEY => Some(Msg::SaveHistoryEdit),
+                        ESC_KEY => Some(Msg::CancelHistoryEdit),
+                        _ => None,
+                    }
+                })
+            };
+            div![
+                &general.form,
+                form,
+                input![
+                    input_ev(Ev::Input, Msg::EditingHistoryHeadlineChanged),
+                    edit_keys(),
+                    attrs! {At::Placeholder => "Headline", At::Value => editing.headline},
+                    &general.input,
+                ],
+                input![
+                    input_ev(Ev::Input, Msg::EditingHistoryOriginChanged),
+                    edit_keys(),
+                    attrs! {At::Placeholder => "Origin", At::Value => editing.account_origin},
+                    &general.input,
+                ],
+                input![
+                    input_ev(Ev::Input, Msg::EditingHistoryTargetChanged),
+                    edit_keys(),
+                    attrs! {At::Placeholder => "Target", At::Value => editing.account_target},
+                    &general.input,
+                ],
+                input![
+                    input_ev(Ev::Input, Msg::EditingHistoryDateChanged),
+                    attrs! {
+                        At::Type => "date",
+                        At::Value => editing.date.replace('/', "-"),
+                    },
+                    &general.input,
+                ],
+                input![
+                    input_ev(Ev::Input, Msg::EditingHistoryTimespanChanged),
+                    edit_keys(),
+                    attrs! {At::Placeholder => "HH:MM - HH:MM", At::Value => editing.timespan},
+                    &general.input,
+                ],
+                input![
+                    input_ev(Ev::Input, Msg::EditingHistoryDurationChanged),
+                    edit_keys(),
+                    attrs! {At::Placeholder => "Duration", At::Value => editing.duration},
+                    &general.input,
+                ],
+                button![
+                    "Cancel",
+                    ev(Ev::Click, |_| Msg::CancelHistoryEdit),
+                    &general.button,
+                    &general.button_small,
+                ],
+                button![
+                    "Save",
+                    ev(Ev::Click, |_| Msg::SaveHistoryEdit),
+                    &general.button,
+                    style! {St::MarginTop => px(25)},
+                ]
+            ]
+        }
+        _ => div![
+            &general.form,
+            form,
+            IF!(!overlaps.is_empty() => &general.form_conflict),
+            h3![history.headline.clone()],
+            label![history.account_target.clone(), &general.label],
+            label![
+                format!(
+                    "{} [ {} ] {}m",
+                    history.timespan,
+                    history.date.clone().replace("/", " "),
+                    history.duration,
+                ),
+                &general.label
+            ],
+            overlaps.iter().map(|o| {
+                label![
+                    format!("Overlaps {} ({}m)", o.timespan, o.duration),
+                    &general.label,
+                    style! {St::Color => "#e0524f"},
+                ]
+            }),
+            button![
+                "Edit",
+                ev(Ev::Click, enc!((id) move |_| Msg::StartHistoryEdit(id))),
+                &general.button,
+                &general.button_small,
+            ],
+            button![
+                "Delete",
+                ev(Ev::Click, enc!((id) move |_| Msg::DeleteTimeEntery(id))),
+                &general.button,
+                style! {St::MarginTop => px(25)},
+            ]
         ],
-        button![
-            "Delete",
-            ev(Ev::Click, enc!((id) move |_| Msg::DeleteTimeEntery(id))),
-            &general.button,
-            style! {St::MarginTop => px(25)},
-        ]
-    ]
+    }
 }
 
 fn update_suggestion_filter(model: &mut Model) {
@@ -721,7 +1060,8 @@ pub fn custom_suggestion<'a>(
         .count() as i64
         * 5;
     //autofill
-    return suggestions.iter().rev().filter(move |s| {
+    // the suggestions are ranked by the backend, best first
+    suggestions.iter().filter(move |s| {
         (&model.suggestion_filter == "account_target"
             && matcher
                 .fuzzy_match(
@@ -735,7 +1075,7 @@ pub fn custom_suggestion<'a>(
                     .fuzzy_match(&s.headline, &&model.start_entery.headline.replace(" ", ""))
                     .unwrap_or(0)
                     > threshhold)
-    });
+    })
 }
 
 fn autofill(orders: &mut impl Orders<Msg>, model: &Model) {
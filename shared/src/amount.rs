@@ -0,0 +1,224 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+pub use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+pub use rust_decimal::Decimal;
+
+// commodities written in front of the quantity
+const PREFIX_COMMODITIES: [&str; 2] = ["$", "£"];
+
+/// Exact quantity of a commodity, like `12.50€`, `$3.20`, `3 ETF` or `90m`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Amount {
+    pub quantity: Decimal,
+    pub commodity: String,
+}
+
+impl Amount {
+    pub fn new(quantity: Decimal, commodity: &str) -> Amount {
+        Amount {
+            quantity,
+            commodity: commodity.to_string(),
+        }
+    }
+
+    /// Parses amounts as ledger writes them. `12,50€` is read as `12.50€`, if there is no point.
+    /// If both are used, the last one is the decimal mark (`1.000,50€` and `1,000.50€`).
+    /// Returns None, if there is no quantity, more than one commodity or the quantity is
+    /// ambiguous like `1,000€` or `1.000€`.
+    pub fn parse(text: &str) -> Option<Amount> {
+        Amount::parse_notation(text, false)
+    }
+
+    /// Parses amounts, whose decimal mark is known to be the point, like the ones in the
+    /// journals. Unlike `parse` a single point is never a thousands separator (`3.125 ETF`).
+    pub fn parse_decimal_point(text: &str) -> Option<Amount> {
+        Amount::parse_notation(text, true)
+    }
+
+    fn parse_notation(text: &str, decimal_point: bool) -> Option<Amount> {
+        let text = text.trim();
+        let (negative, text) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest.trim_start()),
+            None => (false, text),
+        };
+        let is_number = |c: char| c.is_ascii_digit() || c == '.' || c == ',' || c == '-';
+        let start = text.find(is_number)?;
+        let end = text[start..]
+            .find(|c: char| !is_number(c))
+            .map(|e| start + e)
+            .unwrap_or(text.len());
+        let (prefix, suffix) = (text[..start].trim(), text[end..].trim());
+        let commodity = match (prefix.is_empty(), suffix.is_empty()) {
+            (true, _) => suffix,
+            (false, true) => prefix,
+            (false, false) => return None,
+        };
+        if commodity.contains(|c: char| c.is_ascii_digit() || c.is_whitespace()) {
+            return None;
+        }
+        let number = normalize_number(&text[start..end], decimal_point)?;
+        let quantity = Decimal::from_str(&number).ok()?;
+        Some(Amount::new(
+            if negative { -quantity } else { quantity },
+            commodity,
+        ))
+    }
+
+    /// the amount with the sign of `other`, used for costs, that are always written positive
+    pub fn with_sign_of(&self, other: &Amount) -> Amount {
+        let quantity = self.quantity.abs();
+        Amount::new(
+            if other.quantity.is_sign_negative() {
+                -quantity
+            } else {
+                quantity
+            },
+            &self.commodity,
+        )
+    }
+
+    /// digits after the decimal point, that are written for the commodity.
+    /// None keeps the digits of the quantity.
+    pub fn precision(&self) -> Option<u32> {
+        match self.commodity.as_str() {
+            "€" | "$" | "£" | "EUR" | "USD" | "GBP" | "CHF" => Some(2),
+            "m" => Some(0),
+            _ => None,
+        }
+    }
+
+    /// the quantity as it is written, rounded to the precision of the commodity
+    pub fn rounded(&self) -> Decimal {
+        match self.precision() {
+            Some(precision) => {
+                let mut quantity = self.quantity.round_dp(precision);
+                quantity.rescale(precision);
+                quantity
+            }
+            None => self.quantity.normalize(),
+        }
+    }
+}
+
+/// The number with a point as decimal mark and without thousands separators.
+/// With `decimal_point` a single point is never read as thousands separator.
+fn normalize_number(number: &str, decimal_point: bool) -> Option<String> {
+    let (decimal, separator) = match (number.rfind('.'), number.rfind(',')) {
+        (Some(point), Some(comma)) if comma > point => (',', '.'),
+        // `1.000` is one in english and one thousand in german notation
+        (Some(point), None)
+            if !decimal_point
+                && number.matches('.').count() == 1
+                && number.len() - point - 1 == 3 =>
+        {
+            return None
+        }
+        (Some(_), _) => ('.', ','),
+        (None, Some(_)) if number.matches(',').count() > 1 => return grouped(number, ','),
+        (None, Some(comma)) => {
+            // `1,000` is one thousand in english and one in german notation
+            if number.len() - comma - 1 == 3 {
+                return None;
+            }
+            (',', '.')
+        }
+        (None, None) => return Some(number.to_string()),
+    };
+    let (integer, fraction) = number.rsplit_once(decimal)?;
+    let integer = match integer.contains(separator) {
+        true => grouped(integer, separator)?,
+        false => integer.to_string(),
+    };
+    Some(format!("{}.{}", integer, fraction))
+}
+
+/// an integer with thousands separators like `1,000,000`
+fn grouped(number: &str, separator: char) -> Option<String> {
+    let mut groups = number.split(separator);
+    let first = groups.next()?;
+    let rest = groups.collect::<Vec<_>>();
+    if first.is_empty() || first.len() > 3 || rest.iter().any(|g| g.len() != 3) {
+        return None;
+    }
+    Some(format!("{}{}", first, rest.concat()))
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let quantity = self.rounded();
+        if self.commodity.is_empty() {
+            write!(f, "{}", quantity)
+        } else if PREFIX_COMMODITIES.contains(&self.commodity.as_str()) {
+            if quantity.is_sign_negative() {
+                write!(f, "-{}{}", self.commodity, quantity.abs())
+            } else {
+                write!(f, "{}{}", self.commodity, quantity)
+            }
+        } else if self.commodity.chars().all(char::is_alphabetic) && self.commodity.len() > 1 {
+            write!(f, "{} {}", quantity, self.commodity)
+        } else {
+            write!(f, "{}{}", quantity, self.commodity)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amount(quantity: &str, commodity: &str) -> Amount {
+        Amount::new(Decimal::from_str(quantity).unwrap(), commodity)
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Amount::parse("12.50€"), Some(amount("12.50", "€")));
+        assert_eq!(Amount::parse("-12,5 €"), Some(amount("-12.5", "€")));
+        assert_eq!(Amount::parse("$-3.20"), Some(amount("-3.20", "$")));
+        assert_eq!(Amount::parse("-$3.20"), Some(amount("-3.20", "$")));
+        assert_eq!(
+            Amount::parse("1,000.25 USD"),
+            Some(amount("1000.25", "USD"))
+        );
+        assert_eq!(Amount::parse("3 ETF"), Some(amount("3", "ETF")));
+        assert_eq!(Amount::parse("90m"), Some(amount("90", "m")));
+        assert_eq!(Amount::parse("42"), Some(amount("42", "")));
+        assert_eq!(Amount::parse("€ 3 ETF"), None);
+        assert_eq!(Amount::parse("€"), None);
+    }
+
+    #[test]
+    fn test_parse_separators() {
+        // one thousand or one, the notation is unknown
+        assert_eq!(Amount::parse("1,000€"), None);
+        assert_eq!(Amount::parse("1.000€"), None);
+        assert_eq!(Amount::parse("1.0000€"), Some(amount("1.0000", "€")));
+        // the journals always use the point as decimal mark
+        assert_eq!(
+            Amount::parse_decimal_point("3.125 ETF"),
+            Some(amount("3.125", "ETF"))
+        );
+        assert_eq!(Amount::parse_decimal_point("1,000€"), None);
+        assert_eq!(Amount::parse("1,50€"), Some(amount("1.50", "€")));
+        assert_eq!(Amount::parse("1,0000€"), Some(amount("1.0000", "€")));
+        assert_eq!(Amount::parse("1.000,50€"), Some(amount("1000.50", "€")));
+        assert_eq!(Amount::parse("1,000.50€"), Some(amount("1000.50", "€")));
+        assert_eq!(Amount::parse("1,000,000€"), Some(amount("1000000", "€")));
+        assert_eq!(Amount::parse("10,00,0€"), None);
+        assert_eq!(Amount::parse("1,0.5€"), None);
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(amount("10", "€").to_string(), "10.00€");
+        assert_eq!(amount("0.1", "€").to_string(), "0.10€");
+        assert_eq!(amount("-3.2", "$").to_string(), "-$3.20");
+        assert_eq!(amount("1.500", "ETF").to_string(), "1.5 ETF");
+        assert_eq!(amount("90", "m").to_string(), "90m");
+        // no rounding errors when adding
+        let sum = amount("0.1", "€").quantity + amount("0.2", "€").quantity;
+        assert_eq!(Amount::new(sum, "€").to_string(), "0.30€");
+    }
+}
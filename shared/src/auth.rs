@@ -9,5 +9,60 @@ pub struct UserLogin {
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct UserLoginResponse {
     pub username: String,
+    /// short lived access token
     pub token: String,
+    pub refresh_token: String,
+}
+
+/// refresh token of a login, it is replaced by every refresh
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct RefreshToken {
+    pub refresh_token: String,
+}
+
+/// user as shown to the admins, without the password hash
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct UserInfo {
+    pub username: String,
+    pub disabled: bool,
+    pub roles: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct UserList {
+    pub users: Vec<UserInfo>,
+}
+
+/// user created by an admin. Without roles the user gets the default role.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct UserCreate {
+    pub username: String,
+    pub password: String,
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct UserDisable {
+    pub username: String,
+    pub disabled: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct UserDelete {
+    pub username: String,
+}
+
+/// new password of a user, set by an admin
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct UserPasswordReset {
+    pub username: String,
+    pub password: String,
+}
+
+/// new password of the logged in user, that needs the current one
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct PasswordChange {
+    pub current_password: String,
+    pub new_password: String,
 }
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
+use crate::amount::{Amount, Decimal};
+
 ///LOGIN STRUCTS
 #[derive(Serialize, Deserialize)]
 pub struct NewUser {
@@ -33,9 +35,29 @@ impl Default for HtmlSuggestion {
     }
 }
 
+/// Periods and depths, for witch the journals of a target have data. Every date of a
+/// timespan can be combined with every depth.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct TargetSuggestion {
+    pub target: String,
+    /// first days of the periods with data per timespan, like `2022_10_01`
+    pub dates: BTreeMap<String, Vec<String>>,
+    /// levels of the deepest account
+    pub max_depth: usize,
+}
+
+impl TargetSuggestion {
+    /// `all` and every level up to the deepest account
+    pub fn depths(&self) -> Vec<String> {
+        std::iter::once("all".to_string())
+            .chain((1..=self.max_depth).map(|d| d.to_string()))
+            .collect()
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct ResponseHtmlSuggestion {
-    pub suggestions: Vec<HtmlSuggestion>,
+    pub suggestions: Vec<TargetSuggestion>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -43,21 +65,67 @@ pub struct ResponseHtml {
     pub html: String,
 }
 
+///Section with Reports
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct RequestReport {
+    pub selection: HtmlSuggestion,
+    pub html: bool,
+    /// number of periods before the selection, that are reported too
+    #[serde(default)]
+    pub history: u32,
+}
+
+/// Balance of an account in one commodity, an account with several commodities has one row
+/// per commodity. Parent accounts hold the sum of their children.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ReportRow {
+    pub account: String,
+    pub depth: u32,
+    pub balance: Decimal,
+    pub commodity: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct LedgerReport {
+    pub selection: HtmlSuggestion,
+    pub start: String, //first day of the report
+    pub end: String,   //last day of the report
+    /// commodity of the journal, amounts without commodity are in it
+    pub commodity: String,
+    pub rows: Vec<ReportRow>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ResponseReport {
+    pub report: LedgerReport,
+    pub html: Option<String>,
+    /// reports of the periods before the selection, the oldest first
+    #[serde(default)]
+    pub history: Vec<LedgerReport>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct HeadlineSuggestion {
     pub suggestions: Vec<TimeEnterySuggestion>,
 }
 
+/// number of suggestions, that are returned. They are ranked by use, the best first.
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct RequestSuggestion {
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
 pub struct TimeEnterySuggestion {
     pub headline: String,
     pub account_target: String,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
 pub struct TimeEnteryHistory {
-    pub remove_entery: String, //the entery as string as it stands in the file.
+    pub id: String,
     pub headline: String,
+    pub account_origin: String,
     pub account_target: String,
     pub date: String,
     pub timespan: String,
@@ -67,6 +135,137 @@ pub struct TimeEnteryHistory {
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct ResponseTimeEnteryHistory {
     pub history: Vec<TimeEnteryHistory>,
+    /// number of enteries matching the selection, without limit and offset
+    #[serde(default)]
+    pub total: usize,
+}
+
+/// id of the started or created time entery with the problems of the journal, it was
+/// written to
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ResponseTimeEnteryStart {
+    pub id: String,
+    #[serde(default)]
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// updated time entery with the problems of the journal, it was written to
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ResponseTimeEnteryUpdate {
+    pub entery: TimeEnteryHistory,
+    #[serde(default)]
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Selection of the time history. Dates are inclusive, `account` selects the enteries with
+/// an account below it and every word of `search` has to be in the headline.
+/// `sort` is `asc` or `desc` by date, without sort the order of the journal is kept.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct RequestTimeHistory {
+    pub start: Option<String>,
+    pub end: Option<String>,
+    pub account: Option<String>,
+    pub search: Option<String>,
+    pub sort: Option<String>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+/// Selection for the check of overlapping and untracked time.
+/// Dates are inclusive, the day is only checked between `day_start` and `day_end` (`HH:MM`).
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct RequestTimeAnalysis {
+    pub date_from: String,
+    pub date_to: String,
+    pub day_start: Option<String>,
+    pub day_end: Option<String>,
+    /// gaps shorter than this are ignored
+    pub min_gap: Option<u32>,
+    /// if set, a filler entery into this account is suggested for every gap
+    pub filler_account: Option<String>,
+}
+
+/// two enteries, that were tracked at the same time
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct TimeOverlap {
+    pub date: String,
+    pub first_id: String,
+    pub second_id: String,
+    pub timespan: String,
+    pub duration: u32,
+}
+
+/// time of the day without any entery
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct TimeGap {
+    pub date: String,
+    pub timespan: String,
+    pub duration: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct TimeDayAnalysis {
+    pub date: String,
+    pub tracked: u32,
+    pub untracked: u32,
+    pub overlaps: Vec<TimeOverlap>,
+    pub gaps: Vec<TimeGap>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ResponseTimeAnalysis {
+    pub days: Vec<TimeDayAnalysis>,
+    /// suggested enteries for the gaps, they have no id yet
+    pub fillers: Vec<TimeEnteryHistory>,
+}
+
+/// Selection of the time statistics. Dates are inclusive, `bucket` is `day`, `week` or `month`.
+/// Accounts are cut after `depth` levels, without depth the full account is used.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct RequestTimeStatistics {
+    pub start: String,
+    pub end: String,
+    pub bucket: String,
+    pub depth: Option<usize>,
+    /// if set, the minutes of every account are split up by headline
+    #[serde(default)]
+    pub headlines: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct TimeStatisticsHeadline {
+    pub headline: String,
+    pub minutes: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct TimeStatisticsAccount {
+    pub account: String,
+    pub minutes: u32,
+    /// only filled, if the headlines were requested
+    pub headlines: Vec<TimeStatisticsHeadline>,
+}
+
+/// minutes per account from `start` to `end`, both inclusive
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct TimeStatisticsBucket {
+    pub start: String,
+    pub end: String,
+    pub accounts: Vec<TimeStatisticsAccount>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ResponseTimeStatistics {
+    /// all accounts, that occur in one of the buckets
+    pub accounts: Vec<String>,
+    /// every bucket of the selection, also the ones without enteries
+    pub buckets: Vec<TimeStatisticsBucket>,
+}
+
+/// finished time entery, that should be removed from the history
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct DeleteTimeEntery {
+    pub id: String,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
@@ -105,8 +304,12 @@ impl Default for StartTimeEntery {
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct ResponseStatus {
     pub status: i8,
+    /// problems of the journal, that was written
+    #[serde(default)]
+    pub diagnostics: Vec<Diagnostic>,
 }
 
+/// running enteries by their id
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct ResponseRunningLedgerTimeEntery {
     pub running_entery: BTreeMap<String, NewTimeEntery>,
@@ -115,7 +318,7 @@ pub struct ResponseRunningLedgerTimeEntery {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct StopLedgerTimeEntery {
     pub new_entery: NewTimeEntery,
-    pub remove_line: String,
+    pub id: String, //id of the running entery
 }
 
 ///Section with Finance Enterys
@@ -124,12 +327,311 @@ pub struct NewFinanceEntery {
     pub headline: String,
     pub account_origin: String,
     pub account_target: String,
-    pub ammount: f32,
+    pub ammount: Amount,
     pub date: Option<String>,
     pub target_file: String,
+    /// All postings of an entery with more than two postings. If set, origin, target and
+    /// ammount are ignored.
+    #[serde(default)]
+    pub postings: Vec<FinancePosting>,
+}
+
+/// posting of a finance entery. A posting without ammount gets the balancing value.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct FinancePosting {
+    pub account: String,
+    pub ammount: Option<Amount>,
+    /// total cost of the ammount in another commodity, like `3 ETF @@ 60.30€`
+    #[serde(default)]
+    pub cost: Option<Amount>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct FinanceEnterySuggestion {
     pub suggestions: Vec<NewFinanceEntery>,
 }
+
+///Section with Journals
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalKind {
+    Time,
+    #[default]
+    Finance,
+    Music,
+}
+
+/// journal file of the user. Archived journals are kept for reports, but can not be written to.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct JournalDefinition {
+    pub name: String,
+    /// file name in the directory of the user
+    pub path: String,
+    pub commodity: String,
+    pub kind: JournalKind,
+    #[serde(default)]
+    pub archived: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct JournalList {
+    pub journals: Vec<JournalDefinition>,
+}
+
+/// journal, that should be archived
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ArchiveJournal {
+    pub name: String,
+}
+
+///Section with Accounts
+/// Account of the tree. Usage and last use include the sub accounts.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct AccountNode {
+    /// last segment of the account
+    pub name: String,
+    /// full account like `Girokonto:N2`
+    pub account: String,
+    /// number of postings
+    pub usage: u32,
+    pub last_used: String,
+    /// names of the journals, that use the account
+    pub journals: Vec<String>,
+    pub kinds: Vec<JournalKind>,
+    pub children: Vec<AccountNode>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct AccountTree {
+    pub accounts: Vec<AccountNode>,
+}
+
+///Section with Journal Checks
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// problem in a line of a journal file
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub file: String,
+    pub line: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ResponseCheck {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+///Section with Export
+/// Selection of the exported data. Dates are inclusive. Only enteries with an account
+/// below `account` are exported. Without journals all journals of the kind are exported.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct RequestExport {
+    /// `csv` or `json`
+    pub format: String,
+    pub start: Option<String>,
+    pub end: Option<String>,
+    pub account: Option<String>,
+    #[serde(default)]
+    pub journals: Vec<String>,
+}
+
+/// finance transaction with all its postings. Postings without ammount get the balancing value.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct FinanceTransaction {
+    /// id of the transaction in its journal
+    #[serde(default)]
+    pub id: String,
+    pub date: String,
+    pub headline: String,
+    pub journal: String,
+    pub postings: Vec<FinancePosting>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ResponseFinanceExport {
+    pub transactions: Vec<FinanceTransaction>,
+}
+
+///Section with Bank Import
+/// Columns of the CSV export of a bank. Columns are selected by their header.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct BankProfile {
+    pub name: String,
+    /// account of the bank in the journal, like `Girokonto:N2`
+    pub account: String,
+    pub delimiter: char,
+    /// lines in front of the header
+    #[serde(default)]
+    pub skip_rows: usize,
+    pub date_column: String,
+    /// chrono format of the dates, like `%d.%m.%Y`
+    pub date_format: String,
+    pub payee_column: String,
+    #[serde(default)]
+    pub description_column: Option<String>,
+    pub ammount_column: String,
+    /// ammounts are written like `1.234,56`
+    #[serde(default)]
+    pub decimal_comma: bool,
+    /// commodity of ammounts without commodity. Empty for the commodity of the journal.
+    #[serde(default)]
+    pub commodity: String,
+}
+
+/// Bank transactions, whose payee or description match the pattern, are booked to the account.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ImportRule {
+    pub pattern: String,
+    pub account: String,
+    /// headline of the entery, the payee if not set
+    #[serde(default)]
+    pub headline: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ImportSettings {
+    pub profiles: Vec<BankProfile>,
+    pub rules: Vec<ImportRule>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct RequestImportPreview {
+    pub profile: String,
+    pub target_file: String,
+    /// content of the CSV file
+    pub content: String,
+}
+
+/// proposed entery for one line of the CSV file
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ImportPreviewEntery {
+    pub line: usize,
+    pub payee: String,
+    pub description: String,
+    pub entery: NewFinanceEntery,
+    /// pattern of the matching rule. Without match the target account is empty.
+    pub rule: Option<String>,
+    /// the journal has a transaction of the bank account with the same date and ammount
+    pub duplicate: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ResponseImportPreview {
+    pub enteries: Vec<ImportPreviewEntery>,
+}
+
+/// enteries of the preview, that should be written
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct RequestImport {
+    pub enteries: Vec<NewFinanceEntery>,
+}
+
+///Section with Budgets
+/// Budget of an account and its sub accounts for every `week`, `month` or `year`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Budget {
+    pub account: String,
+    pub ammount: Amount,
+    pub period: String,
+    /// unspent ammounts are added to the next period, overspent ones are taken from it
+    #[serde(default)]
+    pub rollover: bool,
+    /// first day of the budget. Without start the rollover begins with the first reported period.
+    #[serde(default)]
+    pub start: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct BudgetList {
+    pub budgets: Vec<Budget>,
+}
+
+/// budget, that should be removed
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct DeleteBudget {
+    pub account: String,
+}
+
+/// number of reported periods, the current period included
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct RequestBudgetReport {
+    pub periods: Option<u32>,
+}
+
+/// One period of a budget. `available` is the budget with the rollover of the last period.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct BudgetPeriodReport {
+    pub start: String,
+    pub end: String,
+    pub budget: Amount,
+    pub rollover: Amount,
+    pub available: Amount,
+    pub actual: Amount,
+    pub remaining: Amount,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct BudgetReport {
+    pub budget: Budget,
+    /// oldest period first, the last one is the current period
+    pub periods: Vec<BudgetPeriodReport>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ResponseBudgetReport {
+    pub budgets: Vec<BudgetReport>,
+}
+
+///Section with Recurring Transactions
+/// Finance entery, that is created every `daily`, `weekly`, `monthly` or `yearly` period
+/// from start until end (inclusive).
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct RecurringTransaction {
+    /// set by the backend
+    #[serde(default)]
+    pub id: String,
+    pub entery: NewFinanceEntery,
+    pub frequency: String,
+    pub start: String,
+    pub end: Option<String>,
+    /// date of the last created occurrence, set by the backend
+    #[serde(default)]
+    pub last: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct RecurringList {
+    pub recurring: Vec<RecurringTransaction>,
+}
+
+/// recurring transaction, that should be removed
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct DeleteRecurring {
+    pub id: String,
+}
+
+/// occurrences in the next `days` days, today included
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct RequestUpcoming {
+    pub days: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Occurrence {
+    pub id: String,
+    pub date: String,
+    pub headline: String,
+    pub target_file: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ResponseUpcoming {
+    pub occurrences: Vec<Occurrence>,
+}